@@ -0,0 +1,43 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use rust_puzzle::constraint::{CompositeConstraint, DefaultConstraint, KnightsMoveConstraint};
+use rust_puzzle::sudoku_generator::{FillOrder, Generator};
+
+// Scan and random order thrash so badly on a 9x9 anti-knight grid that they
+// are only measured on 6x6 grids.
+fn fill_orders(c: &mut Criterion) {
+    let constraint = CompositeConstraint::new(DefaultConstraint, KnightsMoveConstraint);
+    let orders = [
+        ("scan", FillOrder::Scan),
+        ("random", FillOrder::Random),
+        ("most constrained", FillOrder::MostConstrained),
+    ];
+
+    for (name, fill_order) in orders {
+        c.bench_function(&format!("6x6 anti-knight fill, {} order", name), |b| {
+            b.iter(|| {
+                let mut generator =
+                    Generator::new(ChaCha8Rng::seed_from_u64(0)).with_fill_order(fill_order);
+                black_box(generator.generate(3, 2, constraint.clone()).unwrap())
+            })
+        });
+    }
+}
+
+fn most_constrained_9x9(c: &mut Criterion) {
+    let constraint = CompositeConstraint::new(DefaultConstraint, KnightsMoveConstraint);
+
+    c.bench_function("9x9 anti-knight fill, most constrained order", |b| {
+        b.iter(|| {
+            let mut generator = Generator::new(ChaCha8Rng::seed_from_u64(0))
+                .with_fill_order(FillOrder::MostConstrained);
+            black_box(generator.generate(3, 3, constraint.clone()).unwrap())
+        })
+    });
+}
+
+criterion_group!(benches, fill_orders, most_constrained_9x9);
+criterion_main!(benches);
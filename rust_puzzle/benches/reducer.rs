@@ -0,0 +1,53 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use rust_puzzle::constraint::DefaultConstraint;
+use rust_puzzle::solver::{BacktrackingSolver, CachingSolver};
+use rust_puzzle::sudoku_generator::Reducer;
+use rust_puzzle::Sudoku;
+
+const SOLUTION: &str = "3x3;\
+    5,3,4,6,7,8,9,1,2,\
+    6,7,2,1,9,5,3,4,8,\
+    1,9,8,3,4,2,5,6,7,\
+    8,5,9,7,6,1,4,2,3,\
+    4,2,6,8,5,3,7,9,1,\
+    7,1,3,9,2,4,8,5,6,\
+    9,6,1,5,3,7,2,8,4,\
+    2,8,7,4,1,9,6,3,5,\
+    3,4,5,2,8,6,1,7,9";
+
+fn full_sudoku() -> Sudoku<DefaultConstraint> {
+    Sudoku::parse(SOLUTION, DefaultConstraint).unwrap()
+}
+
+fn backtracking_reduction(c: &mut Criterion) {
+    c.bench_function("reduce with backtracking solver", |b| {
+        b.iter(|| {
+            let mut sudoku = full_sudoku();
+            let rng = ChaCha8Rng::seed_from_u64(0);
+            Reducer::new(BacktrackingSolver, rng).reduce(&mut sudoku);
+            black_box(sudoku)
+        })
+    });
+}
+
+fn caching_reduction(c: &mut Criterion) {
+    c.bench_function("reduce with caching solver", |b| {
+        b.iter(|| {
+            let mut sudoku = full_sudoku();
+            let rng = ChaCha8Rng::seed_from_u64(0);
+            Reducer::new(CachingSolver::default(), rng).reduce(&mut sudoku);
+            black_box(sudoku)
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = backtracking_reduction, caching_reduction
+}
+criterion_main!(benches);
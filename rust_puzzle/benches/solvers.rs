@@ -0,0 +1,83 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use rust_puzzle::constraint::DefaultConstraint;
+use rust_puzzle::solver::{BacktrackingSolver, SmallGridSolver, Solver, SolverSession};
+use rust_puzzle::Sudoku;
+
+const PUZZLES: [&str; 3] = [
+    "3x3;\
+     , , , ,8,1, , , ,\
+     , ,2, , ,7,8, , ,\
+     ,5,3, , , ,1,7, ,\
+    3,7, , , , , , , ,\
+    6, , , , , , , ,3,\
+     , , , , , , ,2,4,\
+     ,6,9, , , ,2,3, ,\
+     , ,5,9, , ,4, , ,\
+     , , ,6,5, , , , ",
+    "3x3;\
+    5,3, , ,7, , , , ,\
+    6, , ,1,9,5, , , ,\
+     ,9,8, , , , ,6, ,\
+    8, , , ,6, , , ,3,\
+    4, , ,8, ,3, , ,1,\
+    7, , , ,2, , , ,6,\
+     ,6, , , , ,2,8, ,\
+     , , ,4,1,9, , ,5,\
+     , , , ,8, , ,7,9",
+    "2x2;1, , , , , ,3, , ,4, , , , , ,2",
+];
+
+fn sudokus() -> Vec<Sudoku<DefaultConstraint>> {
+    PUZZLES
+        .iter()
+        .map(|puzzle| Sudoku::parse(puzzle, DefaultConstraint).unwrap())
+        .collect()
+}
+
+fn backtracking_solver(c: &mut Criterion) {
+    let sudokus = sudokus();
+    let solver = BacktrackingSolver;
+
+    c.bench_function("backtracking solver", |b| {
+        b.iter(|| {
+            for sudoku in sudokus.iter() {
+                black_box(solver.solve(sudoku));
+            }
+        })
+    });
+}
+
+fn small_grid_solver(c: &mut Criterion) {
+    let sudokus = sudokus();
+    let solver = SmallGridSolver;
+
+    c.bench_function("small grid solver", |b| {
+        b.iter(|| {
+            for sudoku in sudokus.iter() {
+                black_box(solver.solve(sudoku));
+            }
+        })
+    });
+}
+
+fn solver_session(c: &mut Criterion) {
+    let sudokus = sudokus();
+    let mut session = SolverSession::new();
+
+    c.bench_function("solver session", |b| {
+        b.iter(|| {
+            for sudoku in sudokus.iter() {
+                black_box(session.solve_into(sudoku));
+            }
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    backtracking_solver,
+    small_grid_solver,
+    solver_session
+);
+criterion_main!(benches);
@@ -0,0 +1,102 @@
+use crate::error::{SudokuError, SudokuResult};
+
+// The symbols used to write the numbers of a grid, where the symbol at index
+// i stands for the number i + 1. Letters match case-insensitively when
+// parsing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Alphabet {
+    symbols: Vec<char>,
+}
+
+const DEFAULT_SYMBOLS: &str = "123456789ABCDEFG";
+const HEXADECIMAL_SYMBOLS: &str = "0123456789ABCDEF";
+
+impl Alphabet {
+    pub fn new(symbols: &str) -> SudokuResult<Alphabet> {
+        let symbols: Vec<char> = symbols.chars().collect();
+
+        for (i, &symbol) in symbols.iter().enumerate() {
+            let reserved = symbol == ',' || symbol == ';' || symbol.is_whitespace();
+            let duplicate = symbols[..i]
+                .iter()
+                .any(|other| other.eq_ignore_ascii_case(&symbol));
+
+            if reserved || duplicate {
+                return Err(SudokuError::InvalidNumber);
+            }
+        }
+
+        Ok(Alphabet { symbols })
+    }
+
+    // 0-F for the numbers 1 to 16, as used by many hex sudokus.
+    pub fn hexadecimal() -> Alphabet {
+        Alphabet::new(HEXADECIMAL_SYMBOLS).unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn symbol(&self, number: usize) -> Option<char> {
+        if number == 0 {
+            None
+        } else {
+            self.symbols.get(number - 1).cloned()
+        }
+    }
+
+    pub fn number(&self, symbol: char) -> Option<usize> {
+        self.symbols
+            .iter()
+            .position(|s| s.eq_ignore_ascii_case(&symbol))
+            .map(|index| index + 1)
+    }
+}
+
+// 1-9 followed by A-G, enough for grids up to 16x16.
+impl Default for Alphabet {
+    fn default() -> Alphabet {
+        Alphabet::new(DEFAULT_SYMBOLS).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn default_alphabet() {
+        let alphabet = Alphabet::default();
+
+        assert_eq!(16, alphabet.len());
+        assert_eq!(Some('1'), alphabet.symbol(1));
+        assert_eq!(Some('A'), alphabet.symbol(10));
+        assert_eq!(Some('G'), alphabet.symbol(16));
+        assert_eq!(None, alphabet.symbol(0));
+        assert_eq!(None, alphabet.symbol(17));
+        assert_eq!(Some(10), alphabet.number('a'));
+        assert_eq!(None, alphabet.number('0'));
+    }
+
+    #[test]
+    fn hexadecimal_alphabet() {
+        let alphabet = Alphabet::hexadecimal();
+
+        assert_eq!(Some(1), alphabet.number('0'));
+        assert_eq!(Some(16), alphabet.number('F'));
+        assert_eq!(Some('F'), alphabet.symbol(16));
+    }
+
+    #[test]
+    fn invalid_alphabets() {
+        assert_eq!(Err(SudokuError::InvalidNumber), Alphabet::new("12a3A"));
+        assert_eq!(Err(SudokuError::InvalidNumber), Alphabet::new("12,3"));
+        assert_eq!(Err(SudokuError::InvalidNumber), Alphabet::new("1 2"));
+    }
+}
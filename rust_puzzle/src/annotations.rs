@@ -0,0 +1,259 @@
+use crate::error::SudokuResult;
+use crate::utilities::Grid;
+use crate::SudokuGrid;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub struct Color {
+    red: u8,
+    green: u8,
+    blue: u8,
+}
+
+impl Color {
+    pub fn rgb(red: u8, green: u8, blue: u8) -> Color {
+        Color { red, green, blue }
+    }
+
+    pub fn red(&self) -> u8 {
+        self.red
+    }
+
+    pub fn green(&self) -> u8 {
+        self.green
+    }
+
+    pub fn blue(&self) -> u8 {
+        self.blue
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum PencilMarkStyle {
+    Corner,
+    Center,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct CellAnnotation {
+    color: Option<Color>,
+    pencil_mark_style: Option<PencilMarkStyle>,
+    tags: Vec<String>,
+}
+
+impl CellAnnotation {
+    pub fn color(&self) -> Option<Color> {
+        self.color
+    }
+
+    pub fn pencil_mark_style(&self) -> Option<PencilMarkStyle> {
+        self.pencil_mark_style
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.color.is_none() && self.pencil_mark_style.is_none() && self.tags.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Annotations {
+    cells: Grid<CellAnnotation>,
+}
+
+impl Annotations {
+    pub fn new(size: usize) -> Annotations {
+        Annotations {
+            cells: Grid::new(size, CellAnnotation::default()),
+        }
+    }
+
+    pub fn for_sudoku_grid(grid: &SudokuGrid) -> Annotations {
+        Annotations::new(grid.size())
+    }
+
+    pub fn size(&self) -> usize {
+        self.cells.size()
+    }
+
+    pub fn fits(&self, grid: &SudokuGrid) -> bool {
+        self.size() == grid.size()
+    }
+
+    pub fn cell(&self, column: usize, row: usize) -> SudokuResult<&CellAnnotation> {
+        self.cells.get(column, row)
+    }
+
+    pub fn set_color(&mut self, column: usize, row: usize, color: Color) -> SudokuResult<()> {
+        self.cells.get_mut(column, row)?.color = Some(color);
+        Ok(())
+    }
+
+    pub fn clear_color(&mut self, column: usize, row: usize) -> SudokuResult<()> {
+        self.cells.get_mut(column, row)?.color = None;
+        Ok(())
+    }
+
+    pub fn set_pencil_mark_style(
+        &mut self,
+        column: usize,
+        row: usize,
+        style: PencilMarkStyle,
+    ) -> SudokuResult<()> {
+        self.cells.get_mut(column, row)?.pencil_mark_style = Some(style);
+        Ok(())
+    }
+
+    pub fn clear_pencil_mark_style(&mut self, column: usize, row: usize) -> SudokuResult<()> {
+        self.cells.get_mut(column, row)?.pencil_mark_style = None;
+        Ok(())
+    }
+
+    pub fn add_tag(&mut self, column: usize, row: usize, tag: &str) -> SudokuResult<bool> {
+        let cell = self.cells.get_mut(column, row)?;
+
+        if cell.has_tag(tag) {
+            Ok(false)
+        } else {
+            cell.tags.push(tag.to_owned());
+            Ok(true)
+        }
+    }
+
+    pub fn remove_tag(&mut self, column: usize, row: usize, tag: &str) -> SudokuResult<bool> {
+        let cell = self.cells.get_mut(column, row)?;
+        let len_before = cell.tags.len();
+        cell.tags.retain(|t| t != tag);
+        Ok(cell.tags.len() != len_before)
+    }
+
+    pub fn clear_cell(&mut self, column: usize, row: usize) -> SudokuResult<()> {
+        *self.cells.get_mut(column, row)? = CellAnnotation::default();
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(CellAnnotation::default());
+    }
+
+    pub fn highlight(&mut self, cells: &[(usize, usize)], color: Color) -> SudokuResult<()> {
+        for &(column, row) in cells {
+            self.set_color(column, row, color)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn annotated_cells(&self) -> impl Iterator<Item = (usize, usize, &CellAnnotation)> {
+        self.cells.iter().filter(|(_, _, cell)| !cell.is_empty())
+    }
+
+    pub fn cells_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.cells
+            .iter()
+            .filter(move |(_, _, cell)| cell.has_tag(tag))
+            .map(|(column, row, _)| (column, row))
+    }
+
+    pub fn grid(&self) -> &Grid<CellAnnotation> {
+        &self.cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::SudokuError;
+
+    #[test]
+    fn new_annotations_are_empty() {
+        let annotations = Annotations::for_sudoku_grid(&SudokuGrid::new(3, 3).unwrap());
+
+        assert_eq!(9, annotations.size());
+        assert_eq!(0, annotations.annotated_cells().count());
+        assert!(annotations.cell(8, 8).unwrap().is_empty());
+    }
+
+    #[test]
+    fn colors_and_styles() {
+        let mut annotations = Annotations::new(4);
+        let red = Color::rgb(255, 0, 0);
+        annotations.highlight(&[(0, 0), (1, 2)], red).unwrap();
+        annotations
+            .set_pencil_mark_style(3, 3, PencilMarkStyle::Center)
+            .unwrap();
+
+        assert_eq!(Some(red), annotations.cell(1, 2).unwrap().color());
+        assert_eq!(None, annotations.cell(2, 1).unwrap().color());
+        assert_eq!(
+            Some(PencilMarkStyle::Center),
+            annotations.cell(3, 3).unwrap().pencil_mark_style()
+        );
+        assert_eq!(3, annotations.annotated_cells().count());
+
+        annotations.clear_color(0, 0).unwrap();
+        annotations.clear_cell(3, 3).unwrap();
+
+        assert_eq!(1, annotations.annotated_cells().count());
+    }
+
+    #[test]
+    fn tags() {
+        let mut annotations = Annotations::new(4);
+
+        assert!(annotations.add_tag(1, 1, "pair").unwrap());
+        assert!(!annotations.add_tag(1, 1, "pair").unwrap());
+        assert!(annotations.add_tag(2, 1, "pair").unwrap());
+        assert!(annotations.add_tag(2, 1, "target").unwrap());
+
+        let pair_cells: Vec<(usize, usize)> = annotations.cells_with_tag("pair").collect();
+        assert_eq!(vec![(1, 1), (2, 1)], pair_cells);
+
+        assert!(annotations.remove_tag(1, 1, "pair").unwrap());
+        assert!(!annotations.remove_tag(1, 1, "pair").unwrap());
+        assert!(annotations.cell(1, 1).unwrap().tags().is_empty());
+        assert_eq!(2, annotations.cell(2, 1).unwrap().tags().len());
+        assert!(annotations.cell(2, 1).unwrap().has_tag("target"));
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let mut annotations = Annotations::new(4);
+
+        assert_eq!(Err(SudokuError::OutOfBounds), annotations.cell(4, 0));
+        assert_eq!(
+            Err(SudokuError::OutOfBounds),
+            annotations.set_color(0, 4, Color::rgb(0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn annotations_serde_consistent() {
+        let mut annotations = Annotations::new(4);
+        annotations.set_color(2, 3, Color::rgb(1, 2, 3)).unwrap();
+        annotations
+            .set_pencil_mark_style(0, 1, PencilMarkStyle::Corner)
+            .unwrap();
+        annotations.add_tag(0, 1, "note").unwrap();
+
+        let json = serde_json::to_string(&annotations).unwrap();
+        let reconstructed: Annotations = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(annotations, reconstructed);
+    }
+
+    #[test]
+    fn deserialize_rejects_wrong_cell_count() {
+        let json = r#"{"cells":{"size":2,"cells":[]}}"#;
+        assert!(serde_json::from_str::<Annotations>(json).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use crate::alphabet::Alphabet;
+use crate::error::{ArchiveError, ArchiveResult, SudokuParseError, SudokuParseResult};
+use crate::{DimensionPolicy, SudokuGrid};
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Lines};
+use std::path::Path;
+
+#[cfg(feature = "io-compress")]
+use flate2::read::GzDecoder;
+#[cfg(feature = "io-compress")]
+use std::io::Read;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArchiveFormat {
+    // One puzzle per line as size * size symbols (1-9, then A-G), with "."
+    // or "0" for empty cells, e.g. the usual 81 character lines.
+    Sdm,
+
+    // One puzzle per line in the format of SudokuGrid::parse.
+    Codes,
+}
+
+fn parse_sdm_line(line: &str, policy: DimensionPolicy) -> SudokuParseResult<SudokuGrid> {
+    let symbols: Vec<char> = line.chars().collect();
+    let size = (1..=symbols.len())
+        .find(|size| size * size >= symbols.len())
+        .unwrap_or(0);
+
+    if size * size != symbols.len() {
+        return Err(SudokuParseError::WrongNumberOfCells);
+    }
+
+    let (block_width, block_height) = policy
+        .block_dims(size)
+        .ok_or(SudokuParseError::InvalidDimensions)?;
+    let mut grid = SudokuGrid::new(block_width, block_height)
+        .map_err(|_| SudokuParseError::InvalidDimensions)?;
+    let alphabet = Alphabet::default();
+    let mut placements = Vec::with_capacity(symbols.len());
+
+    for (index, &symbol) in symbols.iter().enumerate() {
+        if symbol == '.' || symbol == '0' {
+            continue;
+        }
+
+        let number = alphabet
+            .number(symbol)
+            .ok_or(SudokuParseError::NumberFormatError)?;
+        placements.push((index % size, index / size, number));
+    }
+
+    grid.apply_bulk(&placements)
+        .map_err(|_| SudokuParseError::InvalidNumber)?;
+    Ok(grid)
+}
+
+// Reads puzzles one line at a time, so archives of any size can be processed
+// in constant memory. Empty lines and lines starting with '#' are skipped.
+pub struct PuzzleReader<R: BufRead> {
+    lines: Lines<R>,
+    format: ArchiveFormat,
+    policy: DimensionPolicy,
+    line_number: usize,
+}
+
+impl<R: BufRead> PuzzleReader<R> {
+    pub fn new(reader: R, format: ArchiveFormat) -> PuzzleReader<R> {
+        PuzzleReader {
+            lines: reader.lines(),
+            format,
+            policy: DimensionPolicy::default(),
+            line_number: 0,
+        }
+    }
+
+    // Sets how block dimensions are chosen for SDM lines, which only convey
+    // the size.
+    pub fn with_policy(mut self, policy: DimensionPolicy) -> PuzzleReader<R> {
+        self.policy = policy;
+        self
+    }
+
+    pub fn format(&self) -> ArchiveFormat {
+        self.format
+    }
+
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+}
+
+#[cfg(feature = "io-compress")]
+impl<R: Read> PuzzleReader<BufReader<GzDecoder<R>>> {
+    pub fn from_gzip(reader: R, format: ArchiveFormat) -> PuzzleReader<BufReader<GzDecoder<R>>> {
+        PuzzleReader::new(BufReader::new(GzDecoder::new(reader)), format)
+    }
+}
+
+impl PuzzleReader<Box<dyn BufRead>> {
+    // Opens an archive file, decompressing it on the fly if it starts with
+    // the gzip magic number. Reading gzip archives requires the io-compress
+    // feature.
+    pub fn open(
+        path: impl AsRef<Path>,
+        format: ArchiveFormat,
+    ) -> ArchiveResult<PuzzleReader<Box<dyn BufRead>>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let compressed = reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+        Ok(PuzzleReader::new(decompress(reader, compressed)?, format))
+    }
+}
+
+#[cfg(feature = "io-compress")]
+fn decompress<R: BufRead + 'static>(reader: R, compressed: bool) -> io::Result<Box<dyn BufRead>> {
+    if compressed {
+        Ok(Box::new(BufReader::new(GzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+#[cfg(not(feature = "io-compress"))]
+fn decompress<R: BufRead + 'static>(reader: R, compressed: bool) -> io::Result<Box<dyn BufRead>> {
+    if compressed {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "gzip archives require the io-compress feature",
+        ))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+impl<R: BufRead> Iterator for PuzzleReader<R> {
+    type Item = ArchiveResult<SudokuGrid>;
+
+    fn next(&mut self) -> Option<ArchiveResult<SudokuGrid>> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(ArchiveError::Io(error))),
+            };
+            self.line_number += 1;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let grid = match self.format {
+                ArchiveFormat::Sdm => parse_sdm_line(line, self.policy),
+                ArchiveFormat::Codes => SudokuGrid::parse(line),
+            };
+
+            return Some(grid.map_err(|error| ArchiveError::Parse {
+                line: self.line_number,
+                error,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::io::Cursor;
+
+    const SDM: &str = "\
+# two classic puzzles
+53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79
+
+000000010400000000020000000000050407008000300001090000300400200050100000000806000
+";
+
+    #[test]
+    fn read_sdm() {
+        let grids: Vec<SudokuGrid> = PuzzleReader::new(Cursor::new(SDM), ArchiveFormat::Sdm)
+            .collect::<ArchiveResult<_>>()
+            .unwrap();
+
+        assert_eq!(2, grids.len());
+        assert_eq!(3, grids[0].block_width());
+        assert_eq!(Some(5), grids[0].get_cell(0, 0).unwrap());
+        assert_eq!(30, grids[0].count_clues());
+        assert_eq!(17, grids[1].count_clues());
+    }
+
+    #[test]
+    fn read_codes() {
+        let codes = "2x2;1,,,,,,,,,,,,,,,\n3x2;,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,6\n";
+        let grids: Vec<SudokuGrid> = PuzzleReader::new(Cursor::new(codes), ArchiveFormat::Codes)
+            .collect::<ArchiveResult<_>>()
+            .unwrap();
+
+        assert_eq!(SudokuGrid::parse("2x2;1,,,,,,,,,,,,,,,").unwrap(), grids[0]);
+        assert_eq!(Some(6), grids[1].get_cell(5, 5).unwrap());
+    }
+
+    #[test]
+    fn malformed_lines_report_line_number() {
+        let mut reader =
+            PuzzleReader::new(Cursor::new("1...............\n\n12.\n"), ArchiveFormat::Sdm);
+
+        assert!(reader.next().unwrap().is_ok());
+
+        match reader.next() {
+            Some(Err(ArchiveError::Parse { line, error })) => {
+                assert_eq!(3, line);
+                assert_eq!(SudokuParseError::WrongNumberOfCells, error);
+            }
+            _ => panic!("expected a parse error"),
+        }
+
+        assert!(reader.next().is_none());
+    }
+
+    #[cfg(feature = "io-compress")]
+    #[test]
+    fn read_gzip() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(SDM.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let path = std::env::temp_dir().join("rust_puzzle_archive_test.sdm.gz");
+        std::fs::write(&path, &compressed).unwrap();
+
+        let from_reader = PuzzleReader::from_gzip(Cursor::new(compressed), ArchiveFormat::Sdm);
+        let from_file = PuzzleReader::open(&path, ArchiveFormat::Sdm).unwrap();
+
+        assert_eq!(2, from_reader.filter(Result::is_ok).count());
+        assert_eq!(2, from_file.filter(Result::is_ok).count());
+        std::fs::remove_file(path).unwrap();
+    }
+}
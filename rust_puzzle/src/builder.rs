@@ -0,0 +1,417 @@
+use crate::error::{SudokuBuildError, SudokuBuildResult};
+use crate::{parse_dimensions, DimensionPolicy, SudokuGrid};
+
+use std::convert::TryInto;
+
+// Builds a grid row by row, where 0 marks an empty cell. If no dimensions
+// are given, they are inferred from the row length according to the
+// dimension policy, by default the most square blocks (e.g. 3x2 for 6x6).
+#[derive(Clone, Debug, Default)]
+pub struct SudokuGridBuilder {
+    dimensions: Option<(usize, usize)>,
+    policy: DimensionPolicy,
+    rows: Vec<Vec<Option<usize>>>,
+    error: Option<SudokuBuildError>,
+}
+
+impl SudokuGridBuilder {
+    pub fn new() -> SudokuGridBuilder {
+        SudokuGridBuilder::default()
+    }
+
+    pub fn dimensions(mut self, block_width: usize, block_height: usize) -> SudokuGridBuilder {
+        self.dimensions = Some((block_width, block_height));
+        self
+    }
+
+    pub fn policy(mut self, policy: DimensionPolicy) -> SudokuGridBuilder {
+        self.policy = policy;
+        self
+    }
+
+    pub fn dimensions_code(mut self, code: &str) -> SudokuGridBuilder {
+        match parse_dimensions(code) {
+            Ok(dimensions) => self.dimensions = Some(dimensions),
+            Err(_) => {
+                self.error
+                    .get_or_insert(SudokuBuildError::InvalidDimensions);
+            }
+        }
+
+        self
+    }
+
+    // Appends cells in row-major order. Without explicit dimensions, the
+    // number of cells must be a square so the row length can be derived.
+    pub fn cells<I, N>(mut self, cells: I) -> SudokuGridBuilder
+    where
+        I: IntoIterator<Item = N>,
+        N: TryInto<usize>,
+    {
+        let cells: Vec<N> = cells.into_iter().collect();
+        let size = match self.dimensions {
+            Some((block_width, block_height)) => block_width * block_height,
+            None => (1..=cells.len())
+                .find(|size| size * size >= cells.len())
+                .unwrap_or(0),
+        };
+
+        if size == 0 || !cells.len().is_multiple_of(size) {
+            self.error.get_or_insert(SudokuBuildError::WrongRowLength {
+                row: self.rows.len() + cells.len() / size.max(1),
+                expected: size,
+                found: cells.len() % size.max(1),
+            });
+            return self;
+        }
+
+        let mut cells = cells.into_iter();
+
+        while cells.len() > 0 {
+            self = self.row(cells.by_ref().take(size));
+        }
+
+        self
+    }
+
+    pub fn row<I, N>(mut self, row: I) -> SudokuGridBuilder
+    where
+        I: IntoIterator<Item = N>,
+        N: TryInto<usize>,
+    {
+        let row_index = self.rows.len();
+        let cells = row
+            .into_iter()
+            .enumerate()
+            .map(|(column, number)| match number.try_into() {
+                Ok(0) => None,
+                Ok(number) => Some(number),
+                Err(_) => {
+                    self.error.get_or_insert(SudokuBuildError::InvalidNumber {
+                        column,
+                        row: row_index,
+                    });
+                    None
+                }
+            })
+            .collect();
+        self.rows.push(cells);
+        self
+    }
+
+    pub fn build(self) -> SudokuBuildResult<SudokuGrid> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        let size = match self.rows.first() {
+            Some(row) => row.len(),
+            None => return Err(SudokuBuildError::NoRows),
+        };
+        let (block_width, block_height) = match self.dimensions {
+            Some(dimensions) => dimensions,
+            None => self
+                .policy
+                .block_dims(size)
+                .ok_or(SudokuBuildError::CannotInferDimensions(size))?,
+        };
+
+        if block_width * block_height != size || size == 0 {
+            return Err(SudokuBuildError::InvalidDimensions);
+        }
+
+        if self.rows.len() != size {
+            return Err(SudokuBuildError::WrongNumberOfRows {
+                expected: size,
+                found: self.rows.len(),
+            });
+        }
+
+        let mut grid = SudokuGrid::new(block_width, block_height)
+            .map_err(|_| SudokuBuildError::InvalidDimensions)?;
+
+        for (row_index, row) in self.rows.iter().enumerate() {
+            if row.len() != size {
+                return Err(SudokuBuildError::WrongRowLength {
+                    row: row_index,
+                    expected: size,
+                    found: row.len(),
+                });
+            }
+
+            for (column, cell) in row.iter().enumerate() {
+                if let Some(number) = *cell {
+                    grid.set_cell(column, row_index, number).map_err(|_| {
+                        SudokuBuildError::InvalidNumber {
+                            column,
+                            row: row_index,
+                        }
+                    })?;
+                }
+            }
+        }
+
+        Ok(grid)
+    }
+}
+
+// Builds a grid from rows of numbers, optionally preceded by the block
+// dimensions, or from a flat list of cells after a dimension code, panicking
+// if they do not form a valid grid: sudoku_grid![[1, 0], ...],
+// sudoku_grid![3, 2; [1, 0, ...], ...] or sudoku_grid!(3x2: [1, 0, ...])
+#[macro_export]
+macro_rules! sudoku_grid {
+    ($dimensions:tt : [$($number:expr),* $(,)?]) => {
+        $crate::builder::SudokuGridBuilder::new()
+            .dimensions_code(stringify!($dimensions))
+            .cells([$($number),*])
+            .build()
+            .unwrap()
+    };
+
+    ($block_width:expr, $block_height:expr; $([$($number:expr),* $(,)?]),+ $(,)?) => {
+        $crate::builder::SudokuGridBuilder::new()
+            .dimensions($block_width, $block_height)
+            $(.row([$($number),*]))+
+            .build()
+            .unwrap()
+    };
+
+    ($([$($number:expr),* $(,)?]),+ $(,)?) => {
+        $crate::builder::SudokuGridBuilder::new()
+            $(.row([$($number),*]))+
+            .build()
+            .unwrap()
+    };
+}
+
+// Wraps a grid in a sudoku whose constraint is given as a sum of the names of
+// irreducible constraints or parenthesized expressions, panicking if the grid
+// violates it: sudoku!(grid, constraints: default + diagonals)
+#[macro_export]
+macro_rules! sudoku {
+    (@constraint $first:tt + $($rest:tt)+) => {
+        $crate::constraint::CompositeConstraint::new(
+            $crate::sudoku!(@constraint $first),
+            $crate::sudoku!(@constraint $($rest)+)
+        )
+    };
+
+    (@constraint default) => { $crate::constraint::DefaultConstraint };
+    (@constraint row) => { $crate::constraint::RowConstraint };
+    (@constraint column) => { $crate::constraint::ColumnConstraint };
+    (@constraint block) => { $crate::constraint::BlockConstraint };
+    (@constraint diagonals) => { $crate::constraint::DiagonalsConstraint };
+    (@constraint knights_move) => { $crate::constraint::KnightsMoveConstraint };
+    (@constraint kings_move) => { $crate::constraint::KingsMoveConstraint };
+    (@constraint diagonally_adjacent) => {
+        $crate::constraint::DiagonallyAdjacentConstraint
+    };
+    (@constraint adjacent_consecutive) => {
+        $crate::constraint::AdjacentConsecutiveConstraint
+    };
+    (@constraint ($constraint:expr)) => { $constraint };
+
+    ($grid:expr, constraints: $($constraint:tt)+) => {
+        {
+            let sudoku = $crate::Sudoku::new_with_grid(
+                $grid,
+                $crate::sudoku!(@constraint $($constraint)+)
+            )
+            .unwrap();
+            assert!(sudoku.is_valid(), "sudoku violates its constraints");
+            sudoku
+        }
+    };
+
+    ($grid:expr) => {
+        $crate::sudoku!($grid, constraints: default)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{CompositeConstraint, DefaultConstraint, DiagonalsConstraint};
+    use crate::Sudoku;
+
+    #[test]
+    fn build_with_inferred_dimensions() {
+        let grid = SudokuGridBuilder::new()
+            .row([1, 0, 0, 0])
+            .row([0, 0, 3, 0])
+            .row([0, 4, 0, 0])
+            .row([0, 0, 0, 2])
+            .build()
+            .unwrap();
+
+        assert_eq!(SudokuGrid::parse("2x2;1,,,,,,3,,,4,,,,,,2").unwrap(), grid);
+    }
+
+    #[test]
+    fn inferred_dimensions_follow_policy() {
+        let rows = |builder: SudokuGridBuilder, size| {
+            (0..size).fold(builder, |builder, _| builder.row(vec![0; size]))
+        };
+        let square = rows(SudokuGridBuilder::new(), 12).build().unwrap();
+        let wide = rows(
+            SudokuGridBuilder::new().policy(DimensionPolicy::PreferWide),
+            12,
+        )
+        .build()
+        .unwrap();
+
+        assert_eq!((4, 3), (square.block_width(), square.block_height()));
+        assert_eq!((6, 2), (wide.block_width(), wide.block_height()));
+        assert_eq!(
+            Err(SudokuBuildError::CannotInferDimensions(7)),
+            rows(SudokuGridBuilder::new(), 7).build()
+        );
+    }
+
+    #[test]
+    fn explicit_dimensions() {
+        let grid = SudokuGridBuilder::new()
+            .dimensions(2, 3)
+            .row([1, 0, 0, 0, 0, 0])
+            .row([0; 6])
+            .row([0; 6])
+            .row([0; 6])
+            .row([0; 6])
+            .row([0, 0, 0, 0, 0, 6])
+            .build()
+            .unwrap();
+
+        assert_eq!(2, grid.block_width());
+        assert_eq!(3, grid.block_height());
+        assert_eq!(Some(6), grid.get_cell(5, 5).unwrap());
+    }
+
+    #[test]
+    fn precise_errors() {
+        assert_eq!(
+            Err(SudokuBuildError::NoRows),
+            SudokuGridBuilder::new().build()
+        );
+        assert_eq!(
+            Err(SudokuBuildError::WrongRowLength {
+                row: 2,
+                expected: 4,
+                found: 3
+            }),
+            SudokuGridBuilder::new()
+                .row([0; 4])
+                .row([0; 4])
+                .row([0; 3])
+                .row([0; 4])
+                .build()
+        );
+        assert_eq!(
+            Err(SudokuBuildError::WrongNumberOfRows {
+                expected: 4,
+                found: 2
+            }),
+            SudokuGridBuilder::new().row([0; 4]).row([0; 4]).build()
+        );
+        assert_eq!(
+            Err(SudokuBuildError::InvalidNumber { column: 1, row: 1 }),
+            SudokuGridBuilder::new()
+                .row([0; 4])
+                .row([0, 5, 0, 0])
+                .row([0; 4])
+                .row([0; 4])
+                .build()
+        );
+        assert_eq!(
+            Err(SudokuBuildError::InvalidNumber { column: 3, row: 0 }),
+            SudokuGridBuilder::new().row([0, 0, 0, -1]).build()
+        );
+        assert_eq!(
+            Err(SudokuBuildError::InvalidDimensions),
+            SudokuGridBuilder::new()
+                .dimensions(3, 2)
+                .row([0; 4])
+                .build()
+        );
+    }
+
+    #[test]
+    fn macro_builds_grid() {
+        let grid = sudoku_grid![[1, 0, 0, 0], [0, 0, 3, 0], [0, 4, 0, 0], [0, 0, 0, 2],];
+        let tall = sudoku_grid![1, 2;
+            [0, 0],
+            [0, 1],
+        ];
+
+        assert_eq!(SudokuGrid::parse("2x2;1,,,,,,3,,,4,,,,,,2").unwrap(), grid);
+        assert_eq!(1, tall.block_width());
+        assert_eq!(Some(1), tall.get_cell(1, 1).unwrap());
+    }
+
+    #[test]
+    fn macro_builds_grid_from_dimension_code() {
+        let grid = sudoku_grid!(2x2: [
+            1, 0, 0, 0,
+            0, 0, 3, 0,
+            0, 4, 0, 0,
+            0, 0, 0, 2,
+        ]);
+        let inferred = SudokuGridBuilder::new()
+            .cells([1, 0, 0, 0, 0, 0, 3, 0, 0, 4, 0, 0, 0, 0, 0, 2])
+            .build();
+
+        assert_eq!(SudokuGrid::parse("2x2;1,,,,,,3,,,4,,,,,,2").unwrap(), grid);
+        assert_eq!(Ok(grid), inferred);
+    }
+
+    #[test]
+    fn cells_with_wrong_count_are_rejected() {
+        assert_eq!(
+            Err(SudokuBuildError::WrongRowLength {
+                row: 2,
+                expected: 4,
+                found: 1
+            }),
+            SudokuGridBuilder::new()
+                .dimensions(2, 2)
+                .cells([0; 9])
+                .build()
+        );
+        assert_eq!(
+            Err(SudokuBuildError::InvalidDimensions),
+            SudokuGridBuilder::new()
+                .dimensions_code("2by2")
+                .cells([0; 16])
+                .build()
+        );
+    }
+
+    #[test]
+    fn sudoku_macro_composes_constraints() {
+        let sudoku = sudoku!(sudoku_grid!(2x2: [
+            1, 0, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 2,
+        ]), constraints: default + diagonals);
+        let explicit: Sudoku<CompositeConstraint<DefaultConstraint, DiagonalsConstraint>> =
+            sudoku!(SudokuGrid::new(2, 2).unwrap(), constraints: default + (DiagonalsConstraint));
+
+        assert!(!sudoku.is_valid_number(3, 0, 1).unwrap());
+        assert!(sudoku.is_valid_number(3, 0, 3).unwrap());
+        assert_eq!(0, explicit.grid().count_clues());
+    }
+
+    #[test]
+    #[should_panic]
+    fn sudoku_macro_rejects_invalid_grid() {
+        sudoku!(sudoku_grid!(2x2: [
+            1, 1, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+        ]));
+    }
+}
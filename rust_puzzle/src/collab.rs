@@ -0,0 +1,225 @@
+use crate::error::{SudokuError, SudokuResult};
+use crate::utilities::Grid;
+use crate::SudokuGrid;
+
+use serde::{Deserialize, Serialize};
+
+// Orders concurrent writes to the same cell. Timestamps are Lamport clocks,
+// ties between replicas are broken by the replica id, so every replica picks
+// the same winner.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub struct Stamp {
+    timestamp: u64,
+    replica: u32,
+}
+
+impl Stamp {
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn replica(&self) -> u32 {
+        self.replica
+    }
+}
+
+// A write of a number to a cell, or a clearing of it if the number is `None`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct CellOp {
+    column: usize,
+    row: usize,
+    number: Option<usize>,
+    stamp: Stamp,
+}
+
+impl CellOp {
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn number(&self) -> Option<usize> {
+        self.number
+    }
+
+    pub fn stamp(&self) -> Stamp {
+        self.stamp
+    }
+}
+
+// A grid edited concurrently by several replicas, which exchange the
+// operations returned by local edits. Every cell keeps the write with the
+// greatest stamp (last writer wins), so replicas which applied the same set
+// of operations hold the same grid, regardless of order or duplicates. The
+// filled cells of the initial grid are givens and immutable.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CollaborativeGrid {
+    grid: SudokuGrid,
+    givens: SudokuGrid,
+    stamps: Grid<Option<Stamp>>,
+    replica: u32,
+    clock: u64,
+}
+
+impl CollaborativeGrid {
+    pub fn new(grid: SudokuGrid, replica: u32) -> CollaborativeGrid {
+        CollaborativeGrid {
+            stamps: Grid::for_sudoku_grid(&grid, None),
+            givens: grid.clone(),
+            grid,
+            replica,
+            clock: 0,
+        }
+    }
+
+    pub fn grid(&self) -> &SudokuGrid {
+        &self.grid
+    }
+
+    pub fn replica(&self) -> u32 {
+        self.replica
+    }
+
+    pub fn is_given(&self, column: usize, row: usize) -> SudokuResult<bool> {
+        Ok(self.givens.get_cell(column, row)?.is_some())
+    }
+
+    fn local_op(
+        &mut self,
+        column: usize,
+        row: usize,
+        number: Option<usize>,
+    ) -> SudokuResult<CellOp> {
+        self.clock += 1;
+        let op = CellOp {
+            column,
+            row,
+            number,
+            stamp: Stamp {
+                timestamp: self.clock,
+                replica: self.replica,
+            },
+        };
+
+        self.check_op(&op)?;
+        self.apply(&op);
+        Ok(op)
+    }
+
+    // Enters a number locally and returns the operation to send to the other
+    // replicas.
+    pub fn set_cell(&mut self, column: usize, row: usize, number: usize) -> SudokuResult<CellOp> {
+        self.local_op(column, row, Some(number))
+    }
+
+    pub fn clear_cell(&mut self, column: usize, row: usize) -> SudokuResult<CellOp> {
+        self.local_op(column, row, None)
+    }
+
+    fn check_op(&self, op: &CellOp) -> SudokuResult<()> {
+        if self.is_given(op.column, op.row)? {
+            return Err(SudokuError::GivenCell);
+        }
+
+        match op.number {
+            Some(number) if number == 0 || number > self.grid.size() => {
+                Err(SudokuError::InvalidNumber)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Applies the operation if it is newer than the last write to its cell
+    // and returns whether it did.
+    fn apply(&mut self, op: &CellOp) -> bool {
+        let stamp = &mut self.stamps[(op.column, op.row)];
+        self.clock = self.clock.max(op.stamp.timestamp);
+
+        if stamp.is_some_and(|stamp| stamp >= op.stamp) {
+            return false;
+        }
+
+        *stamp = Some(op.stamp);
+
+        match op.number {
+            Some(number) => self.grid.set_cell(op.column, op.row, number).unwrap(),
+            None => self.grid.clear_cell(op.column, op.row).unwrap(),
+        }
+
+        true
+    }
+
+    // Merges operations received from other replicas and returns how many of
+    // them changed the grid. The operations are validated first, so if any of
+    // them is out of bounds, has an invalid number or targets a given cell,
+    // nothing is applied.
+    pub fn apply_remote_ops<I>(&mut self, ops: I) -> SudokuResult<usize>
+    where
+        I: IntoIterator<Item = CellOp>,
+    {
+        let ops: Vec<CellOp> = ops.into_iter().collect();
+
+        for op in ops.iter() {
+            self.check_op(op)?;
+        }
+
+        Ok(ops.iter().filter(|op| self.apply(op)).count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn replicas() -> (CollaborativeGrid, CollaborativeGrid) {
+        let grid = SudokuGrid::parse("2x2;1,,,,,,,,,,,,,,,").unwrap();
+        (
+            CollaborativeGrid::new(grid.clone(), 1),
+            CollaborativeGrid::new(grid, 2),
+        )
+    }
+
+    #[test]
+    fn concurrent_writes_converge() {
+        let (mut a, mut b) = replicas();
+        let op_a = a.set_cell(1, 0, 2).unwrap();
+        let op_b = b.set_cell(1, 0, 3).unwrap();
+        let op_c = b.set_cell(2, 0, 4).unwrap();
+
+        assert_eq!(2, a.apply_remote_ops(vec![op_b, op_c]).unwrap());
+        assert_eq!(0, b.apply_remote_ops(vec![op_a]).unwrap());
+        assert_eq!(a.grid(), b.grid());
+        assert_eq!(Some(3), a.grid().get_cell(1, 0).unwrap());
+        assert_eq!(0, a.apply_remote_ops(vec![op_b, op_c]).unwrap());
+    }
+
+    #[test]
+    fn later_writes_win() {
+        let (mut a, mut b) = replicas();
+        let op_b = b.set_cell(1, 0, 3).unwrap();
+        a.apply_remote_ops(vec![op_b]).unwrap();
+        let op_a = a.clear_cell(1, 0).unwrap();
+
+        assert!(op_a.stamp() > op_b.stamp());
+        assert_eq!(1, b.apply_remote_ops(vec![op_a]).unwrap());
+        assert_eq!(None, b.grid().get_cell(1, 0).unwrap());
+    }
+
+    #[test]
+    fn givens_are_immutable() {
+        let (mut a, mut b) = replicas();
+        let op = b.set_cell(1, 0, 2).unwrap();
+        let forged = CellOp { column: 0, ..op };
+
+        assert_eq!(Err(SudokuError::GivenCell), a.set_cell(0, 0, 2));
+        assert_eq!(
+            Err(SudokuError::GivenCell),
+            a.apply_remote_ops(vec![op, forged])
+        );
+        assert_eq!(None, a.grid().get_cell(1, 0).unwrap());
+    }
+}
@@ -1,9 +1,13 @@
 use crate::constraint::{Constraint, Group, ReductionError};
+use crate::transform::{GridTransform, TransformableConstraint};
 use crate::SudokuGrid;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::any::Any;
 
-#[derive(Clone, Deserialize, Serialize)]
+// Serialized as `{"composite": [first, second]}`, so that nested composites
+// of tagged built-in constraints name every component.
+#[derive(Clone, Serialize)]
+#[serde(into = "CompositeConstraintData<C1, C2>")]
 pub struct CompositeConstraint<C1, C2>
 where
     C1: Constraint + Clone + 'static,
@@ -13,6 +17,56 @@ where
     c2: C2,
 }
 
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CompositeConstraintData<C1, C2> {
+    Composite(C1, C2),
+}
+
+// Before they were tagged, composites were serialized as
+// `{"c1": first, "c2": second}`. Human-readable formats still accept that
+// form. Binary formats such as postcard cannot tell the two forms apart and
+// only read the tagged one.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CompositeConstraintForms<C1, C2> {
+    Tagged(CompositeConstraintData<C1, C2>),
+    Legacy { c1: C1, c2: C2 },
+}
+
+impl<'de, C1, C2> Deserialize<'de> for CompositeConstraint<C1, C2>
+where
+    C1: Constraint + Clone + Deserialize<'de> + 'static,
+    C2: Constraint + Clone + Deserialize<'de> + 'static,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (c1, c2) = if deserializer.is_human_readable() {
+            match CompositeConstraintForms::deserialize(deserializer)? {
+                CompositeConstraintForms::Tagged(CompositeConstraintData::Composite(c1, c2))
+                | CompositeConstraintForms::Legacy { c1, c2 } => (c1, c2),
+            }
+        } else {
+            let CompositeConstraintData::Composite(c1, c2) =
+                CompositeConstraintData::deserialize(deserializer)?;
+            (c1, c2)
+        };
+        Ok(CompositeConstraint { c1, c2 })
+    }
+}
+
+impl<C1, C2> From<CompositeConstraint<C1, C2>> for CompositeConstraintData<C1, C2>
+where
+    C1: Constraint + Clone + 'static,
+    C2: Constraint + Clone + 'static,
+{
+    fn from(constraint: CompositeConstraint<C1, C2>) -> Self {
+        CompositeConstraintData::Composite(constraint.c1, constraint.c2)
+    }
+}
+
 impl<C1, C2> CompositeConstraint<C1, C2>
 where
     C1: Constraint + Clone + 'static,
@@ -48,4 +102,86 @@ pub enum CompositeData<D1, D2> {
     Second(D2),
 }
 
+impl<C1, C2> TransformableConstraint for CompositeConstraint<C1, C2>
+where
+    C1: TransformableConstraint + 'static,
+    C2: TransformableConstraint + 'static,
+{
+    fn allows_relabeling(&self) -> bool {
+        self.c1.allows_relabeling() && self.c2.allows_relabeling()
+    }
+
+    fn allows_permutation(&self) -> bool {
+        self.c1.allows_permutation() && self.c2.allows_permutation()
+    }
+
+    fn transform(&mut self, transform: &GridTransform) {
+        self.c1.transform(transform);
+        self.c2.transform(transform);
+    }
+}
+
 //line 108
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::presets::{ArgyleConstraint, WindokuConstraint};
+    use crate::constraint::RowConstraint;
+
+    #[test]
+    fn composite_round_trip() {
+        let constraint = CompositeConstraint::new(
+            CompositeConstraint::new(RowConstraint, WindokuConstraint),
+            ArgyleConstraint,
+        );
+        let json = serde_json::to_string(&constraint).unwrap();
+        let from_json: CompositeConstraint<
+            CompositeConstraint<RowConstraint, WindokuConstraint>,
+            ArgyleConstraint,
+        > = serde_json::from_str(&json).unwrap();
+        let bytes = postcard::to_allocvec(&constraint).unwrap();
+        let from_bytes: CompositeConstraint<
+            CompositeConstraint<RowConstraint, WindokuConstraint>,
+            ArgyleConstraint,
+        > = postcard::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            r#"{"composite":[{"composite":["row","windoku"]},"argyle"]}"#,
+            json
+        );
+        assert_eq!(json, serde_json::to_string(&from_json).unwrap());
+        assert_eq!(json, serde_json::to_string(&from_bytes).unwrap());
+        assert!(
+            serde_json::from_str::<CompositeConstraint<RowConstraint, ArgyleConstraint>>(
+                r#"{"composite":["argyle","row"]}"#
+            )
+            .is_err()
+        );
+    }
+
+    // A composite as written before constraints were serialized as tags.
+    const LEGACY_COMPOSITE: &str = r#"{"c1":{"c1":null,"c2":null},"c2":null}"#;
+
+    #[test]
+    fn legacy_composite_is_accepted() {
+        let constraint: CompositeConstraint<
+            CompositeConstraint<RowConstraint, WindokuConstraint>,
+            ArgyleConstraint,
+        > = serde_json::from_str(LEGACY_COMPOSITE).unwrap();
+        let json = serde_json::to_string(&constraint).unwrap();
+
+        assert_eq!(
+            r#"{"composite":[{"composite":["row","windoku"]},"argyle"]}"#,
+            json
+        );
+        assert!(
+            serde_json::from_str::<CompositeConstraint<RowConstraint, ArgyleConstraint>>(
+                r#"{"c1":"argyle","c2":null}"#
+            )
+            .is_err()
+        );
+    }
+}
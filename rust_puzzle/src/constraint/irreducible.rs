@@ -1,5 +1,8 @@
-use crate::constraint::{self, Constraint, Group, ReductionError};
+use crate::constraint::{
+    self, Constraint, Group, GroupVisitor, ReductionError, Rule, RuleTag, Violation,
+};
 use crate::utilities::USizeSet;
+use crate::view::PuzzleView;
 use crate::SudokuGrid;
 
 use serde::{Deserialize, Serialize};
@@ -10,18 +13,33 @@ use std::slice::Iter;
 
 pub trait IrreducibleConstraint {
     #[inline]
-    fn check(&self, grid: &SudokuGrid) -> bool {
+    fn check(&self, grid: &dyn PuzzleView) -> bool {
         constraint::default_check(self, grid)
     }
 
     #[inline]
-    fn check_cell(&self, grid: &SudokuGrid, column: usize, row: usize) -> bool {
+    fn check_cell(&self, grid: &dyn PuzzleView, column: usize, row: usize) -> bool {
         constraint::default_check_cell(self, grid, column, row)
     }
 
-    fn check_number(&self, grid: &SudokuGrid, column: usize, row: usize, number: usize) -> bool;
+    fn check_number(&self, grid: &dyn PuzzleView, column: usize, row: usize, number: usize) -> bool;
 
-    fn get_groups(&self, grid: &SudokuGrid) -> Vec<Group>;
+    #[inline]
+    fn check_detailed(&self, grid: &dyn PuzzleView) -> Vec<Violation> {
+        constraint::default_check_detailed(self, grid, self.rule_name())
+    }
+
+    fn rule_name(&self) -> &'static str {
+        constraint::DEFAULT_RULE_NAME
+    }
+
+    fn get_groups(&self, grid: &dyn PuzzleView) -> Vec<Group>;
+
+    fn for_each_group(&self, grid: &dyn PuzzleView, f: &mut GroupVisitor<'_>) {
+        for group in self.get_groups(grid) {
+            f(&group);
+        }
+    }
 
     fn to_objects(&self) -> Vec<&dyn Any>
     where
@@ -36,25 +54,40 @@ impl<C: IrreducibleConstraint + ?Sized> Constraint for C {
     type RevertInfo = ();
 
     #[inline]
-    fn check(&self, grid: &SudokuGrid) -> bool {
+    fn check(&self, grid: &dyn PuzzleView) -> bool {
         <C as IrreducibleConstraint>::check(self, grid)
     }
 
     #[inline]
-    fn check_cell(&self, grid: &SudokuGrid, column: usize, row: usize) -> bool {
+    fn check_cell(&self, grid: &dyn PuzzleView, column: usize, row: usize) -> bool {
         <C as IrreducibleConstraint>::check_cell(self, grid, column, row)
     }
 
     #[inline]
-    fn check_number(&self, grid: &SudokuGrid, column: usize, row: usize, number: usize) -> bool {
+    fn check_number(&self, grid: &dyn PuzzleView, column: usize, row: usize, number: usize) -> bool {
         <C as IrreducibleConstraint>::check_number(self, grid, column, row, number)
     }
 
     #[inline]
-    fn get_groups(&self, grid: &SudokuGrid) -> Vec<Group> {
+    fn check_detailed(&self, grid: &dyn PuzzleView) -> Vec<Violation> {
+        <C as IrreducibleConstraint>::check_detailed(self, grid)
+    }
+
+    #[inline]
+    fn rule_name(&self) -> &'static str {
+        <C as IrreducibleConstraint>::rule_name(self)
+    }
+
+    #[inline]
+    fn get_groups(&self, grid: &dyn PuzzleView) -> Vec<Group> {
         <C as IrreducibleConstraint>::get_groups(self, grid)
     }
 
+    #[inline]
+    fn for_each_group(&self, grid: &dyn PuzzleView, f: &mut GroupVisitor<'_>) {
+        <C as IrreducibleConstraint>::for_each_group(self, grid, f)
+    }
+
     fn list_reductions(&self, _: &SudokuGrid) -> Vec<Self::Reduction> {
         Vec::new()
     }
@@ -74,6 +107,7 @@ impl<C: IrreducibleConstraint + ?Sized> Constraint for C {
     }
 }
 #[derive(Clone, Deserialize, Serialize)]
+#[serde(into = "Rule", try_from = "RuleTag")]
 pub struct RowConstraint;
 
 //107
@@ -1,19 +1,34 @@
 use std::any::Any;
+use std::collections::{BTreeMap, HashSet};
 
 use crate::SudokuGrid;
+use crate::validation::StructuralProblem;
+use crate::view::PuzzleView;
 
 pub mod composite;
 pub mod irreducible;
+#[cfg(feature = "parallel-check")]
+pub mod parallel;
+pub mod presets;
 pub mod reducible;
+pub mod rules;
+pub mod simple;
+pub mod versioning;
 
 pub use composite::*;
 pub use irreducible::*;
+#[cfg(feature = "parallel-check")]
+pub use parallel::ParallelCheck;
 pub use reducible::*;
+pub use rules::{CustomRule, Rule, RuleReduction, RuleRevertInfo, RuleSet, RuleTag};
+pub use simple::{ConstraintRegistry, RegisteredRules, SimpleConstraint};
+pub use versioning::UnsupportedVersion;
 
 pub type Group = Vec<(usize, usize)>;
+pub type GroupVisitor<'a> = dyn FnMut(&[(usize, usize)]) + 'a;
 
 #[inline]
-pub(crate) fn default_check<C>(this: &C, grid: &SudokuGrid) -> bool 
+pub(crate) fn default_check<C>(this: &C, grid: &dyn PuzzleView) -> bool 
 where C: Constraint + ?Sized {
     let size = grid.size();
 
@@ -29,7 +44,7 @@ where C: Constraint + ?Sized {
 }
 
 #[inline]
-pub(crate) fn default_check_cell<C>(this: &C, grid: &SudokuGrid, column: usize, row: usize) -> bool
+pub(crate) fn default_check_cell<C>(this: &C, grid: &dyn PuzzleView, column: usize, row: usize) -> bool
 where C: Constraint + ?Sized {
     if let Some(number) = grid.get_cell(column, row).unwrap() {
         this.check_number(grid, column, row, number)
@@ -39,26 +54,166 @@ where C: Constraint + ?Sized {
     }
 }
 
+// A broken rule, named like in rule sets (e.g. "row" or "knights_move"),
+// with the cells and values involved.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Violation {
+    rule: &'static str,
+    cells: Vec<(usize, usize)>,
+    values: Vec<usize>,
+}
+
+impl Violation {
+    pub fn new(
+        rule: &'static str,
+        cells: Vec<(usize, usize)>,
+        values: Vec<usize>,
+    ) -> Violation {
+        Violation {
+            rule,
+            cells,
+            values,
+        }
+    }
+
+    pub fn rule(&self) -> &'static str {
+        self.rule
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    pub fn values(&self) -> &[usize] {
+        &self.values
+    }
+}
+
+pub(crate) const DEFAULT_RULE_NAME: &str = "constraint";
+
+// Full rows, columns and blocks are named as such, whichever constraint
+// they belong to.
+fn house_name(grid: &dyn PuzzleView, group: &[(usize, usize)]) -> Option<&'static str> {
+    let (first_column, first_row) = *group.first()?;
+    let block = |column: usize, row: usize|
+        (column / grid.block_width(), row / grid.block_height());
+
+    if group.len() != grid.size() {
+        None
+    }
+    else if group.iter().all(|&(_, row)| row == first_row) {
+        Some("row")
+    }
+    else if group.iter().all(|&(column, _)| column == first_column) {
+        Some("column")
+    }
+    else if group.iter().all(|&(c, r)| block(c, r) == block(first_column, first_row)) {
+        Some("block")
+    }
+    else {
+        None
+    }
+}
+
+// Reports every number that occurs more than once in one of the groups,
+// then every remaining filled cell that fails check_number on its own.
+// Violations are reported under `rule` unless their group is a house.
+pub(crate) fn default_check_detailed<C>(this: &C, grid: &dyn PuzzleView, rule: &'static str)
+    -> Vec<Violation>
+where C: Constraint + ?Sized {
+    let mut violations = Vec::new();
+    let mut explained = HashSet::new();
+
+    this.for_each_group(grid, &mut |group| {
+        let mut occurrences: BTreeMap<usize, Vec<(usize, usize)>> = BTreeMap::new();
+
+        for &(column, row) in group.iter() {
+            if let Some(number) = grid.get_cell(column, row).unwrap() {
+                occurrences.entry(number).or_default().push((column, row));
+            }
+        }
+
+        for (number, cells) in occurrences {
+            if cells.len() > 1 {
+                explained.extend(cells.iter().cloned());
+                let name = house_name(grid, group).unwrap_or(rule);
+                violations.push(Violation::new(name, cells, vec![number]));
+            }
+        }
+    });
+
+    let size = grid.size();
+
+    for row in 0..size {
+        for column in 0..size {
+            if explained.contains(&(column, row)) {
+                continue;
+            }
+
+            if let Some(number) = grid.get_cell(column, row).unwrap() {
+                if !this.check_number(grid, column, row, number) {
+                    let cells = vec![(column, row)];
+                    violations.push(Violation::new(rule, cells, vec![number]));
+                }
+            }
+        }
+    }
+
+    violations
+}
+
 #[derive(Debug)]
 pub enum ReductionError {
     InvalidReduction
 }
 
+// Grids are passed as `&dyn PuzzleView`, so constraints can check overlay
+// views as well and remain usable as trait objects.
 pub trait Constraint{
     type Reduction;
     type RevertInfo;
     
-    fn check(&self, grid: &SudokuGrid) -> bool {
+    fn check(&self, grid: &dyn PuzzleView) -> bool {
         default_check(self, grid)
     }
 
-    fn check_cell(&self, grid: &SudokuGrid, column: usize, row: usize) -> bool {
+    fn check_cell(&self, grid: &dyn PuzzleView, column: usize, row: usize) -> bool {
         default_check_cell(self, grid, column, row)
     }
 
-    fn check_number(&self, grid: &SudokuGrid, column: usize, row: usize, number: usize) -> bool;
+    fn check_number(&self, grid: &dyn PuzzleView, column: usize, row: usize, number: usize) -> bool;
+
+    fn check_detailed(&self, grid: &dyn PuzzleView) -> Vec<Violation> {
+        default_check_detailed(self, grid, self.rule_name())
+    }
+
+    // The name violations of this constraint are reported under.
+    fn rule_name(&self) -> &'static str {
+        DEFAULT_RULE_NAME
+    }
     
-    fn get_groups(&self, grid: &SudokuGrid) -> Vec<Group>;
+    fn get_groups(&self, grid: &dyn PuzzleView) -> Vec<Group>;
+
+    // Calls `f` with every group. Composed constraints should override this
+    // to visit the groups of their parts without collecting them first.
+    fn for_each_group(&self, grid: &dyn PuzzleView, f: &mut GroupVisitor<'_>) {
+        for group in self.get_groups(grid) {
+            f(&group);
+        }
+    }
+
+    // Reports references to cells or numbers the grid does not have.
+    fn structural_problems(&self, _grid: &SudokuGrid) -> Vec<StructuralProblem> {
+        Vec::new()
+    }
+
+    // Called whenever a sudoku is assembled from a grid and this constraint.
+    fn compatible_with(&self, grid: &SudokuGrid) -> Result<(), StructuralProblem> {
+        match self.structural_problems(grid).into_iter().next() {
+            Some(problem) => Err(problem),
+            None => Ok(())
+        }
+    }
 
     fn list_reductions(&self, solution: &SudokuGrid) -> Vec<Self::Reduction>;
 
@@ -72,6 +227,15 @@ pub trait Constraint{
     }
 }
 
+// Computes the complete set of clues of a clue-bearing constraint (e.g. all
+// consecutive markers) implied by a full solution, so the Reducer can strip
+// them down afterwards.
+pub trait ClueDeriver {
+    type Constraint: Constraint + Clone;
+
+    fn derive_clues(&self, solution: &SudokuGrid) -> Self::Constraint;
+}
+
 pub trait Subconstraint {
     fn get_subconstraint<S: Constraint + Sized + 'static>(&self) -> Option<&S>;
 
@@ -94,4 +258,41 @@ impl<C: Constraint + Sized + 'static> Subconstraint for C {
 
         None
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Sudoku;
+
+    fn violations(code: &str) -> Vec<Violation> {
+        Sudoku::parse(code, DefaultConstraint).unwrap().violations()
+    }
+
+    #[test]
+    fn valid_grid_has_no_violations() {
+        assert!(violations("2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,1").is_empty());
+    }
+
+    #[test]
+    fn duplicates_are_reported_per_group() {
+        let violations = violations("2x2;1,,,1,,,,,,,,,,,,");
+
+        assert_eq!(1, violations.len());
+        assert_eq!("row", violations[0].rule());
+        assert_eq!(&[(0, 0), (3, 0)], violations[0].cells());
+        assert_eq!(&[1], violations[0].values());
+    }
+
+    #[test]
+    fn duplicates_in_multiple_groups() {
+        let violations = violations("2x2;2,,,,,2,,,,,,,2,,,");
+
+        // (0, 0) and (1, 1) share a block, (0, 0) and (0, 3) share a column.
+        assert_eq!(2, violations.len());
+        assert!(violations.iter().all(|v| v.values() == [2]));
+        assert!(violations.iter().any(|v| v.cells() == [(0, 0), (0, 3)] && v.rule() == "column"));
+        assert!(violations.iter().any(|v| v.cells() == [(0, 0), (1, 1)] && v.rule() == "block"));
+    }
+}
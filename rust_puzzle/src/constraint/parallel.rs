@@ -0,0 +1,86 @@
+use crate::constraint::Constraint;
+use crate::SudokuGrid;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+// Grids with fewer numbers are always checked sequentially, since spawning
+// the threads would take longer than the check itself.
+pub const PARALLEL_CHECK_MIN_SIZE: usize = 16;
+
+// Checks large grids by splitting their rows across threads. This is the
+// parallel form of the default `Constraint::check`, i.e. every filled cell
+// must pass `check_cell`. It is a separate trait because Constraint does not
+// require Sync.
+pub trait ParallelCheck: Constraint + Sync {
+    fn check_parallel(&self, grid: &SudokuGrid) -> bool {
+        let size = grid.size();
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+        if size < PARALLEL_CHECK_MIN_SIZE || threads < 2 {
+            return self.check(grid);
+        }
+
+        let rows_per_thread = size.div_ceil(threads.min(size));
+        let failed = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            for first_row in (0..size).step_by(rows_per_thread) {
+                let failed = &failed;
+
+                scope.spawn(move || {
+                    for row in first_row..(first_row + rows_per_thread).min(size) {
+                        if failed.load(Ordering::Relaxed) {
+                            return;
+                        }
+
+                        if !(0..size).all(|column| self.check_cell(grid, column, row)) {
+                            failed.store(true, Ordering::Relaxed);
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        !failed.into_inner()
+    }
+}
+
+impl<C: Constraint + Sync + ?Sized> ParallelCheck for C {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+
+    fn pattern_grid() -> SudokuGrid {
+        let mut grid = SudokuGrid::new(4, 4).unwrap();
+
+        for row in 0..16 {
+            for column in 0..16 {
+                let number = (4 * (row % 4) + row / 4 + column) % 16 + 1;
+                grid.set_cell(column, row, number).unwrap();
+            }
+        }
+
+        grid
+    }
+
+    #[test]
+    fn parallel_check_agrees_with_check() {
+        let mut grid = pattern_grid();
+
+        assert!(DefaultConstraint.check_parallel(&grid));
+
+        grid.clear_cell(3, 15).unwrap();
+        assert!(DefaultConstraint.check_parallel(&grid));
+
+        let number = grid.get_cell(0, 15).unwrap().unwrap();
+        grid.set_cell(3, 15, number).unwrap();
+        assert!(!DefaultConstraint.check(&grid));
+        assert!(!DefaultConstraint.check_parallel(&grid));
+    }
+}
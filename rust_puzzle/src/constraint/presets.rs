@@ -0,0 +1,273 @@
+use crate::constraint::{
+    AdjacentConsecutiveConstraint, CompositeConstraint, DefaultConstraint, DiagonalsConstraint,
+    Group, GroupVisitor, IrreducibleConstraint, KingsMoveConstraint, KnightsMoveConstraint, Rule,
+    RuleTag,
+};
+use crate::view::PuzzleView;
+
+use serde::{Deserialize, Serialize};
+
+// Checks the number against every group of `constraint` which contains the
+// cell, visiting the groups one at a time.
+fn check_groups<C>(
+    constraint: &C,
+    grid: &dyn PuzzleView,
+    column: usize,
+    row: usize,
+    number: usize,
+) -> bool
+where
+    C: IrreducibleConstraint,
+{
+    let mut valid = true;
+
+    IrreducibleConstraint::for_each_group(constraint, grid, &mut |group| {
+        if valid && group.contains(&(column, row)) {
+            valid = group
+                .iter()
+                .all(|&(c, r)| (c, r) == (column, row) || !grid.has_number(c, r, number).unwrap());
+        }
+    });
+
+    valid
+}
+
+fn collect_groups<C>(constraint: &C, grid: &dyn PuzzleView) -> Vec<Group>
+where
+    C: IrreducibleConstraint,
+{
+    let mut groups = Vec::new();
+    IrreducibleConstraint::for_each_group(constraint, grid, &mut |group| {
+        groups.push(group.to_vec())
+    });
+    groups
+}
+
+// Four extra regions of block size ("windows"), each one cell away from the
+// border of the grid and from each other. In a 9x9 grid their top left
+// corners are at (1, 1), (5, 1), (1, 5) and (5, 5).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(into = "Rule", try_from = "RuleTag")]
+pub struct WindokuConstraint;
+
+impl IrreducibleConstraint for WindokuConstraint {
+    fn check_number(
+        &self,
+        grid: &dyn PuzzleView,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> bool {
+        check_groups(self, grid, column, row, number)
+    }
+
+    fn rule_name(&self) -> &'static str {
+        "windoku"
+    }
+
+    fn get_groups(&self, grid: &dyn PuzzleView) -> Vec<Group> {
+        collect_groups(self, grid)
+    }
+
+    fn for_each_group(&self, grid: &dyn PuzzleView, f: &mut GroupVisitor<'_>) {
+        let size = grid.size();
+        let (block_width, block_height) = (grid.block_width(), grid.block_height());
+        let mut window = Vec::with_capacity(block_width * block_height);
+
+        for top in (1..size)
+            .step_by(block_height + 1)
+            .filter(|top| top + block_height < size)
+        {
+            for left in (1..size)
+                .step_by(block_width + 1)
+                .filter(|left| left + block_width < size)
+            {
+                window.clear();
+                window.extend(
+                    (top..top + block_height)
+                        .flat_map(|r| (left..left + block_width).map(move |c| (c, r))),
+                );
+                f(&window);
+            }
+        }
+    }
+}
+
+// The eight diagonal lines of the argyle pattern, on which numbers may not
+// repeat. In a 9x9 grid they start next to the main diagonals and at the
+// fifth cell of the border, in general one cell and one block width plus one
+// cells away from the main diagonals.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(into = "Rule", try_from = "RuleTag")]
+pub struct ArgyleConstraint;
+
+impl IrreducibleConstraint for ArgyleConstraint {
+    fn check_number(
+        &self,
+        grid: &dyn PuzzleView,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> bool {
+        check_groups(self, grid, column, row, number)
+    }
+
+    fn rule_name(&self) -> &'static str {
+        "argyle"
+    }
+
+    fn get_groups(&self, grid: &dyn PuzzleView) -> Vec<Group> {
+        collect_groups(self, grid)
+    }
+
+    fn for_each_group(&self, grid: &dyn PuzzleView, f: &mut GroupVisitor<'_>) {
+        let size = grid.size() as isize;
+        let mut line = Vec::with_capacity(grid.size());
+
+        for offset in [1, grid.block_width() as isize + 1] {
+            if offset >= size - 1 {
+                continue;
+            }
+
+            for diagonal in [offset, -offset] {
+                let cells = (0..size).filter(|c| (0..size).contains(&(c - diagonal)));
+                line.clear();
+                line.extend(cells.clone().map(|c| (c as usize, (c - diagonal) as usize)));
+                f(&line);
+                line.clear();
+                line.extend(cells.map(|c| ((size - 1 - c) as usize, (c - diagonal) as usize)));
+                f(&line);
+            }
+        }
+    }
+}
+
+pub type ClassicXConstraint = CompositeConstraint<DefaultConstraint, DiagonalsConstraint>;
+pub type WindokuPresetConstraint = CompositeConstraint<DefaultConstraint, WindokuConstraint>;
+pub type ArgylePresetConstraint = CompositeConstraint<DefaultConstraint, ArgyleConstraint>;
+pub type MiracleConstraint = CompositeConstraint<
+    CompositeConstraint<
+        CompositeConstraint<DefaultConstraint, KnightsMoveConstraint>,
+        KingsMoveConstraint,
+    >,
+    AdjacentConsecutiveConstraint,
+>;
+
+// Default rules with both main diagonals.
+pub fn classic_x() -> ClassicXConstraint {
+    CompositeConstraint::new(DefaultConstraint, DiagonalsConstraint)
+}
+
+pub fn windoku() -> WindokuPresetConstraint {
+    CompositeConstraint::new(DefaultConstraint, WindokuConstraint)
+}
+
+pub fn argyle() -> ArgylePresetConstraint {
+    CompositeConstraint::new(DefaultConstraint, ArgyleConstraint)
+}
+
+// Default rules with no equal numbers a knight's or king's move apart and no
+// consecutive numbers in orthogonally adjacent cells.
+pub fn miracle() -> MiracleConstraint {
+    CompositeConstraint::new(
+        CompositeConstraint::new(
+            CompositeConstraint::new(DefaultConstraint, KnightsMoveConstraint),
+            KingsMoveConstraint,
+        ),
+        AdjacentConsecutiveConstraint,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::Constraint;
+    use crate::solver::{BacktrackingSolver, Solution, Solver};
+    use crate::{Sudoku, SudokuGrid};
+
+    #[test]
+    fn windows_and_argyle_lines() {
+        let grid = SudokuGrid::new(3, 3).unwrap();
+        let windows = Constraint::get_groups(&WindokuConstraint, &grid);
+        let lines = Constraint::get_groups(&ArgyleConstraint, &grid);
+
+        assert_eq!(4, windows.len());
+        assert!(windows[3].contains(&(5, 5)) && windows[3].contains(&(7, 7)));
+        assert_eq!(8, lines.len());
+        assert_eq!(
+            vec![8, 8, 8, 8, 5, 5, 5, 5],
+            lines.iter().map(Vec::len).collect::<Vec<_>>()
+        );
+        assert!(lines
+            .iter()
+            .any(|line| line.contains(&(4, 0)) && line.contains(&(8, 4))));
+        assert!(lines
+            .iter()
+            .any(|line| line.contains(&(4, 0)) && line.contains(&(0, 4))));
+    }
+
+    #[test]
+    fn groups_are_visited_without_collecting() {
+        let mut grid = SudokuGrid::new(3, 3).unwrap();
+        let mut visited = Vec::new();
+        Constraint::for_each_group(&ArgyleConstraint, &grid, &mut |group| {
+            visited.push(group.to_vec())
+        });
+        Constraint::for_each_group(&WindokuConstraint, &grid, &mut |group| {
+            visited.push(group.to_vec())
+        });
+        let mut groups = Constraint::get_groups(&ArgyleConstraint, &grid);
+        groups.append(&mut Constraint::get_groups(&WindokuConstraint, &grid));
+
+        assert_eq!(groups, visited);
+
+        grid.set_cell(1, 1, 4).unwrap();
+        grid.set_cell(1, 0, 5).unwrap();
+
+        assert!(!Constraint::check_number(
+            &WindokuConstraint,
+            &grid,
+            3,
+            3,
+            4
+        ));
+        assert!(Constraint::check_number(&WindokuConstraint, &grid, 5, 5, 4));
+        assert!(!Constraint::check_number(&ArgyleConstraint, &grid, 3, 2, 5));
+        assert!(Constraint::check_number(&ArgyleConstraint, &grid, 3, 3, 5));
+    }
+
+    #[test]
+    fn miracle_sudoku_is_solved() {
+        // The original miracle sudoku by Mitchell Lee has only the givens at
+        // (2, 4) and (6, 5), the start of the first row is added to keep the test
+        // fast.
+        let mut sudoku = Sudoku::new_empty(3, 3, miracle()).unwrap();
+        sudoku.grid_mut().set_cell(2, 4, 1).unwrap();
+        sudoku.grid_mut().set_cell(6, 5, 2).unwrap();
+
+        for (column, number) in [4, 8, 3, 7].into_iter().enumerate() {
+            sudoku.grid_mut().set_cell(column, 0, number).unwrap();
+        }
+        let expected = SudokuGrid::parse(
+            "3x3;\
+            4,8,3,7,2,6,1,5,9,\
+            7,2,6,1,5,9,4,8,3,\
+            1,5,9,4,8,3,7,2,6,\
+            8,3,7,2,6,1,5,9,4,\
+            2,6,1,5,9,4,8,3,7,\
+            5,9,4,8,3,7,2,6,1,\
+            3,7,2,6,1,5,9,4,8,\
+            6,1,5,9,4,8,3,7,2,\
+            9,4,8,3,7,2,6,1,5",
+        )
+        .unwrap();
+
+        assert!(miracle().check(&expected));
+        assert_eq!(
+            Solution::Unique(expected),
+            BacktrackingSolver.solve(&sudoku)
+        );
+    }
+}
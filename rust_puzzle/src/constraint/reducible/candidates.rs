@@ -0,0 +1,298 @@
+use crate::constraint::versioning::{self, UnsupportedVersion};
+use crate::constraint::{Constraint, Group, ReductionError};
+use crate::transform::{GridTransform, TransformableConstraint};
+use crate::utilities::USizeSet;
+use crate::validation::{self, StructuralProblem};
+use crate::view::PuzzleView;
+use crate::SudokuGrid;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct CandidateRestriction {
+    column: usize,
+    row: usize,
+    candidates: USizeSet,
+}
+
+impl CandidateRestriction {
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn candidates(&self) -> &USizeSet {
+        &self.candidates
+    }
+}
+
+// Restricts individual cells to externally given candidate sets, e.g. pencil
+// marks from an imported puzzle. Each restriction can be lifted as a
+// reduction.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "GivenCandidatesData", into = "GivenCandidatesData")]
+pub struct GivenCandidatesConstraint {
+    restrictions: Vec<CandidateRestriction>,
+}
+
+const GIVEN_CANDIDATES_VERSION: u32 = 1;
+
+#[derive(Deserialize, Serialize)]
+struct GivenCandidatesData {
+    #[serde(default)]
+    version: u32,
+    restrictions: Vec<CandidateRestriction>,
+}
+
+impl TryFrom<GivenCandidatesData> for GivenCandidatesConstraint {
+    type Error = UnsupportedVersion;
+
+    fn try_from(data: GivenCandidatesData) -> Result<Self, UnsupportedVersion> {
+        // Nothing to migrate yet, see the versioning module.
+        versioning::check_version(data.version, GIVEN_CANDIDATES_VERSION)?;
+
+        Ok(GivenCandidatesConstraint {
+            restrictions: data.restrictions,
+        })
+    }
+}
+
+impl From<GivenCandidatesConstraint> for GivenCandidatesData {
+    fn from(constraint: GivenCandidatesConstraint) -> Self {
+        GivenCandidatesData {
+            version: GIVEN_CANDIDATES_VERSION,
+            restrictions: constraint.restrictions,
+        }
+    }
+}
+
+impl GivenCandidatesConstraint {
+    pub fn new() -> GivenCandidatesConstraint {
+        GivenCandidatesConstraint::default()
+    }
+
+    fn position(&self, column: usize, row: usize) -> Option<usize> {
+        self.restrictions
+            .iter()
+            .position(|r| r.column == column && r.row == row)
+    }
+
+    // Sets the candidates of the given cell, returning the previous ones.
+    pub fn restrict(
+        &mut self,
+        column: usize,
+        row: usize,
+        candidates: USizeSet,
+    ) -> Option<USizeSet> {
+        if let Some(index) = self.position(column, row) {
+            let old = std::mem::replace(&mut self.restrictions[index].candidates, candidates);
+            return Some(old);
+        }
+
+        self.restrictions.push(CandidateRestriction {
+            column,
+            row,
+            candidates,
+        });
+        None
+    }
+
+    pub fn lift(&mut self, column: usize, row: usize) -> Option<USizeSet> {
+        let index = self.position(column, row)?;
+        Some(self.restrictions.remove(index).candidates)
+    }
+
+    pub fn candidates(&self, column: usize, row: usize) -> Option<&USizeSet> {
+        self.position(column, row)
+            .map(|index| &self.restrictions[index].candidates)
+    }
+
+    pub fn restrictions(&self) -> &[CandidateRestriction] {
+        &self.restrictions
+    }
+}
+
+impl Constraint for GivenCandidatesConstraint {
+    type Reduction = (usize, usize);
+    type RevertInfo = USizeSet;
+
+    fn check_number(&self, _: &dyn PuzzleView, column: usize, row: usize, number: usize) -> bool {
+        match self.candidates(column, row) {
+            Some(candidates) => candidates.contains(number),
+            None => true,
+        }
+    }
+
+    fn get_groups(&self, _: &dyn PuzzleView) -> Vec<Group> {
+        Vec::new()
+    }
+
+    fn rule_name(&self) -> &'static str {
+        "candidates"
+    }
+
+    fn structural_problems(&self, grid: &SudokuGrid) -> Vec<StructuralProblem> {
+        let mut problems = Vec::new();
+
+        for restriction in self.restrictions.iter() {
+            validation::check_cell_in_bounds(
+                grid,
+                restriction.column,
+                restriction.row,
+                &mut problems,
+            );
+
+            if restriction.candidates.upper() != grid.size() {
+                problems.push(StructuralProblem::DimensionMismatch {
+                    expected: grid.size(),
+                    found: restriction.candidates.upper(),
+                });
+            }
+        }
+
+        problems
+    }
+
+    fn list_reductions(&self, _: &SudokuGrid) -> Vec<(usize, usize)> {
+        self.restrictions
+            .iter()
+            .map(|r| (r.column, r.row))
+            .collect()
+    }
+
+    fn reduce(
+        &mut self,
+        _: &SudokuGrid,
+        &(column, row): &(usize, usize),
+    ) -> Result<USizeSet, ReductionError> {
+        self.lift(column, row)
+            .ok_or(ReductionError::InvalidReduction)
+    }
+
+    fn revert(&mut self, _: &SudokuGrid, &(column, row): &(usize, usize), candidates: USizeSet) {
+        self.restrict(column, row, candidates);
+    }
+}
+
+impl TransformableConstraint for GivenCandidatesConstraint {
+    fn transform(&mut self, transform: &GridTransform) {
+        for restriction in self.restrictions.iter_mut() {
+            let (column, row) = transform.map_cell(restriction.column, restriction.row);
+            let old = &restriction.candidates;
+            let mut candidates = USizeSet::new(old.lower(), old.upper()).unwrap();
+
+            for number in old.iter() {
+                candidates.insert(transform.map_number(number)).unwrap();
+            }
+
+            *restriction = CandidateRestriction {
+                column,
+                row,
+                candidates,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{CompositeConstraint, DefaultConstraint};
+    use crate::set;
+    use crate::solver::{BacktrackingSolver, Solution, Solver};
+    use crate::Sudoku;
+
+    #[test]
+    fn restrictions_limit_numbers() {
+        let mut constraint = GivenCandidatesConstraint::new();
+        constraint.restrict(0, 0, set!(1, 4; 2, 3));
+        let sudoku = Sudoku::new_empty(2, 2, constraint).unwrap();
+
+        assert!(!sudoku.is_valid_number(0, 0, 1).unwrap());
+        assert!(sudoku.is_valid_number(0, 0, 2).unwrap());
+        assert!(sudoku.is_valid_number(1, 0, 1).unwrap());
+    }
+
+    #[test]
+    fn restrict_replaces_and_lift_removes() {
+        let mut constraint = GivenCandidatesConstraint::new();
+
+        assert_eq!(None, constraint.restrict(1, 2, set!(1, 4; 1)));
+        assert_eq!(
+            Some(set!(1, 4; 1)),
+            constraint.restrict(1, 2, set!(1, 4; 4))
+        );
+        assert_eq!(Some(&set!(1, 4; 4)), constraint.candidates(1, 2));
+        assert_eq!(1, constraint.restrictions().len());
+        assert_eq!(Some(set!(1, 4; 4)), constraint.lift(1, 2));
+        assert_eq!(None, constraint.lift(1, 2));
+    }
+
+    #[test]
+    fn reduce_and_revert() {
+        let grid = SudokuGrid::new(2, 2).unwrap();
+        let mut constraint = GivenCandidatesConstraint::new();
+        constraint.restrict(0, 0, set!(1, 4; 2));
+        constraint.restrict(3, 3, set!(1, 4; 1, 4));
+
+        let reductions = constraint.list_reductions(&grid);
+        assert_eq!(vec![(0, 0), (3, 3)], reductions);
+
+        let revert_info = constraint.reduce(&grid, &(0, 0)).unwrap();
+        assert_eq!(None, constraint.candidates(0, 0));
+        assert!(constraint.reduce(&grid, &(0, 0)).is_err());
+
+        constraint.revert(&grid, &(0, 0), revert_info);
+        assert_eq!(Some(&set!(1, 4; 2)), constraint.candidates(0, 0));
+    }
+
+    #[test]
+    fn solver_honors_restrictions() {
+        // Without restrictions, the four clues leave the puzzle ambiguous.
+        let grid = SudokuGrid::parse("2x2; , , , , , ,3,4, , , , , , ,2,1").unwrap();
+        let mut constraint = GivenCandidatesConstraint::new();
+        constraint.restrict(0, 0, set!(1, 4; 3));
+        constraint.restrict(1, 0, set!(1, 4; 4));
+        constraint.restrict(0, 1, set!(1, 4; 1));
+        constraint.restrict(0, 2, set!(1, 4; 2));
+        let sudoku = Sudoku::new_with_grid(
+            grid,
+            CompositeConstraint::new(DefaultConstraint, constraint),
+        )
+        .unwrap();
+        let expected = SudokuGrid::parse("2x2;3,4,1,2,1,2,3,4,2,1,4,3,4,3,2,1").unwrap();
+
+        assert_eq!(
+            Solution::Unique(expected),
+            BacktrackingSolver.solve(&sudoku)
+        );
+    }
+
+    #[test]
+    fn constraint_serde_round_trip() {
+        let mut constraint = GivenCandidatesConstraint::new();
+        constraint.restrict(2, 1, set!(1, 9; 2, 5, 7));
+        let json = serde_json::to_string(&constraint).unwrap();
+
+        assert_eq!(constraint, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn transform_moves_and_relabels_restrictions() {
+        let mut constraint = GivenCandidatesConstraint::new();
+        constraint.restrict(1, 0, set!(1, 4; 1, 2));
+        constraint.transform(&GridTransform::transposition(2, 2));
+
+        assert_eq!(Some(&set!(1, 4; 1, 2)), constraint.candidates(0, 1));
+
+        let relabeling = GridTransform::relabeling(2, 2, vec![4, 3, 2, 1]).unwrap();
+        constraint.transform(&relabeling);
+
+        assert_eq!(Some(&set!(1, 4; 3, 4)), constraint.candidates(0, 1));
+    }
+}
@@ -0,0 +1,443 @@
+use crate::constraint::versioning::{self, UnsupportedVersion};
+use crate::constraint::{ClueDeriver, Constraint, Group, ReductionError};
+use crate::transform::{GridTransform, TransformableConstraint};
+use crate::validation::{self, StructuralProblem};
+use crate::view::PuzzleView;
+use crate::SudokuGrid;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeSet;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub enum EdgeDirection {
+    Right,
+    Below,
+}
+
+// The border between the cell at (column, row) and its neighbor to the right
+// or below.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub struct Edge {
+    column: usize,
+    row: usize,
+    direction: EdgeDirection,
+}
+
+impl Edge {
+    pub fn right(column: usize, row: usize) -> Edge {
+        Edge {
+            column,
+            row,
+            direction: EdgeDirection::Right,
+        }
+    }
+
+    pub fn below(column: usize, row: usize) -> Edge {
+        Edge {
+            column,
+            row,
+            direction: EdgeDirection::Below,
+        }
+    }
+
+    // Returns the edge between the two given cells, if they are orthogonally
+    // adjacent.
+    pub fn between(column_1: usize, row_1: usize, column_2: usize, row_2: usize) -> Option<Edge> {
+        let (column, row) = (column_1.min(column_2), row_1.min(row_2));
+
+        if row_1 == row_2 && column_1.abs_diff(column_2) == 1 {
+            Some(Edge::right(column, row))
+        } else if column_1 == column_2 && row_1.abs_diff(row_2) == 1 {
+            Some(Edge::below(column, row))
+        } else {
+            None
+        }
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn direction(&self) -> EdgeDirection {
+        self.direction
+    }
+
+    pub fn other_cell(&self) -> (usize, usize) {
+        match self.direction {
+            EdgeDirection::Right => (self.column + 1, self.row),
+            EdgeDirection::Below => (self.column, self.row + 1),
+        }
+    }
+}
+
+fn all_edges(size: usize) -> impl Iterator<Item = Edge> {
+    (0..size).flat_map(move |row| {
+        (0..size).flat_map(move |column| {
+            let right = if column + 1 < size {
+                Some(Edge::right(column, row))
+            } else {
+                None
+            };
+            let below = if row + 1 < size {
+                Some(Edge::below(column, row))
+            } else {
+                None
+            };
+            right.into_iter().chain(below)
+        })
+    })
+}
+
+// The full consecutive variant: cells separated by a marked edge must
+// contain consecutive digits, and cells separated by an unmarked edge must
+// not. Since removing a marker would change the meaning of that edge rather
+// than just hide information, reductions instead exempt an edge from both
+// rules.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "ConsecutivePairsData", into = "ConsecutivePairsData")]
+pub struct ConsecutivePairsConstraint {
+    markers: BTreeSet<Edge>,
+    exempt: BTreeSet<Edge>,
+}
+
+const CONSECUTIVE_PAIRS_VERSION: u32 = 1;
+
+#[derive(Deserialize, Serialize)]
+struct ConsecutivePairsData {
+    #[serde(default)]
+    version: u32,
+    markers: BTreeSet<Edge>,
+    #[serde(default)]
+    exempt: BTreeSet<Edge>,
+}
+
+impl TryFrom<ConsecutivePairsData> for ConsecutivePairsConstraint {
+    type Error = UnsupportedVersion;
+
+    fn try_from(data: ConsecutivePairsData) -> Result<Self, UnsupportedVersion> {
+        // Nothing to migrate yet, see the versioning module.
+        versioning::check_version(data.version, CONSECUTIVE_PAIRS_VERSION)?;
+
+        Ok(ConsecutivePairsConstraint {
+            markers: data.markers,
+            exempt: data.exempt,
+        })
+    }
+}
+
+impl From<ConsecutivePairsConstraint> for ConsecutivePairsData {
+    fn from(constraint: ConsecutivePairsConstraint) -> Self {
+        ConsecutivePairsData {
+            version: CONSECUTIVE_PAIRS_VERSION,
+            markers: constraint.markers,
+            exempt: constraint.exempt,
+        }
+    }
+}
+
+impl ConsecutivePairsConstraint {
+    pub fn new() -> ConsecutivePairsConstraint {
+        ConsecutivePairsConstraint::default()
+    }
+
+    // Marks every edge between consecutive digits of the given solution.
+    pub fn from_solution(solution: &SudokuGrid) -> ConsecutivePairsConstraint {
+        let markers = all_edges(solution.size())
+            .filter(|edge| {
+                let (other_column, other_row) = edge.other_cell();
+                let a = solution.get_cell(edge.column, edge.row).unwrap();
+                let b = solution.get_cell(other_column, other_row).unwrap();
+
+                match (a, b) {
+                    (Some(a), Some(b)) => a.abs_diff(b) == 1,
+                    _ => false,
+                }
+            })
+            .collect();
+
+        ConsecutivePairsConstraint {
+            markers,
+            exempt: BTreeSet::new(),
+        }
+    }
+
+    pub fn mark(&mut self, edge: Edge) -> bool {
+        self.exempt.remove(&edge);
+        self.markers.insert(edge)
+    }
+
+    pub fn unmark(&mut self, edge: Edge) -> bool {
+        self.markers.remove(&edge)
+    }
+
+    pub fn exempt(&mut self, edge: Edge) -> bool {
+        self.markers.remove(&edge);
+        self.exempt.insert(edge)
+    }
+
+    pub fn is_marked(&self, edge: Edge) -> bool {
+        self.markers.contains(&edge)
+    }
+
+    pub fn is_exempt(&self, edge: Edge) -> bool {
+        self.exempt.contains(&edge)
+    }
+
+    pub fn markers(&self) -> impl Iterator<Item = Edge> + '_ {
+        self.markers.iter().copied()
+    }
+
+    pub fn exempt_edges(&self) -> impl Iterator<Item = Edge> + '_ {
+        self.exempt.iter().copied()
+    }
+
+    fn check_edge(&self, edge: Edge, a: usize, b: usize) -> bool {
+        let consecutive = a.abs_diff(b) == 1;
+
+        if self.markers.contains(&edge) {
+            consecutive
+        } else {
+            !consecutive || self.exempt.contains(&edge)
+        }
+    }
+}
+
+impl Constraint for ConsecutivePairsConstraint {
+    type Reduction = Edge;
+    type RevertInfo = bool;
+
+    fn check_number(
+        &self,
+        grid: &dyn PuzzleView,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> bool {
+        let size = grid.size();
+        let neighbors = [
+            (column.wrapping_sub(1), row),
+            (column + 1, row),
+            (column, row.wrapping_sub(1)),
+            (column, row + 1),
+        ];
+
+        neighbors
+            .iter()
+            .filter(|&&(c, r)| c < size && r < size)
+            .all(|&(c, r)| match grid.get_cell(c, r).unwrap() {
+                Some(other) => {
+                    self.check_edge(Edge::between(column, row, c, r).unwrap(), number, other)
+                }
+                None => true,
+            })
+    }
+
+    fn get_groups(&self, _: &dyn PuzzleView) -> Vec<Group> {
+        Vec::new()
+    }
+
+    fn rule_name(&self) -> &'static str {
+        "consecutive_pairs"
+    }
+
+    fn structural_problems(&self, grid: &SudokuGrid) -> Vec<StructuralProblem> {
+        let mut problems = Vec::new();
+
+        for edge in self.markers.iter().chain(self.exempt.iter()) {
+            let (column, row) = edge.other_cell();
+            validation::check_cell_in_bounds(grid, column, row, &mut problems);
+        }
+
+        problems
+    }
+
+    fn list_reductions(&self, solution: &SudokuGrid) -> Vec<Edge> {
+        all_edges(solution.size())
+            .filter(|edge| !self.exempt.contains(edge))
+            .collect()
+    }
+
+    fn reduce(&mut self, _: &SudokuGrid, edge: &Edge) -> Result<bool, ReductionError> {
+        if self.exempt.contains(edge) {
+            return Err(ReductionError::InvalidReduction);
+        }
+
+        let was_marked = self.markers.remove(edge);
+        self.exempt.insert(*edge);
+        Ok(was_marked)
+    }
+
+    fn revert(&mut self, _: &SudokuGrid, edge: &Edge, was_marked: bool) {
+        self.exempt.remove(edge);
+
+        if was_marked {
+            self.markers.insert(*edge);
+        }
+    }
+}
+
+pub struct ConsecutivePairsDeriver;
+
+impl ClueDeriver for ConsecutivePairsDeriver {
+    type Constraint = ConsecutivePairsConstraint;
+
+    fn derive_clues(&self, solution: &SudokuGrid) -> ConsecutivePairsConstraint {
+        ConsecutivePairsConstraint::from_solution(solution)
+    }
+}
+
+// Edges whose cells are no longer adjacent after the transformation are
+// dropped, which can only happen for transformations scramble does not use
+// with this constraint.
+fn transform_edges(edges: &BTreeSet<Edge>, transform: &GridTransform) -> BTreeSet<Edge> {
+    edges
+        .iter()
+        .filter_map(|edge| {
+            let (column_1, row_1) = transform.map_cell(edge.column, edge.row);
+            let (other_column, other_row) = edge.other_cell();
+            let (column_2, row_2) = transform.map_cell(other_column, other_row);
+            Edge::between(column_1, row_1, column_2, row_2)
+        })
+        .collect()
+}
+
+impl TransformableConstraint for ConsecutivePairsConstraint {
+    // Relabeling breaks consecutiveness and permuting rows or columns breaks
+    // adjacency, so only transposition is possible.
+    fn allows_relabeling(&self) -> bool {
+        false
+    }
+
+    fn allows_permutation(&self) -> bool {
+        false
+    }
+
+    fn transform(&mut self, transform: &GridTransform) {
+        self.markers = transform_edges(&self.markers, transform);
+        self.exempt = transform_edges(&self.exempt, transform);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::Sudoku;
+
+    fn solution() -> SudokuGrid {
+        SudokuGrid::parse("2x2;1,3,2,4,2,4,1,3,3,1,4,2,4,2,3,1").unwrap()
+    }
+
+    #[test]
+    fn edge_between() {
+        assert_eq!(Some(Edge::right(1, 2)), Edge::between(2, 2, 1, 2));
+        assert_eq!(Some(Edge::below(0, 0)), Edge::between(0, 0, 0, 1));
+        assert_eq!(None, Edge::between(0, 0, 1, 1));
+        assert_eq!(None, Edge::between(0, 0, 2, 0));
+        assert_eq!((3, 1), Edge::below(3, 0).other_cell());
+    }
+
+    #[test]
+    fn marked_and_unmarked_edges() {
+        let mut constraint = ConsecutivePairsConstraint::new();
+        constraint.mark(Edge::right(0, 0));
+        let sudoku = Sudoku::parse("2x2;2, , , , , , , , , , , , , , , ", constraint).unwrap();
+
+        assert!(sudoku.is_valid_number(1, 0, 3).unwrap());
+        assert!(sudoku.is_valid_number(1, 0, 1).unwrap());
+        assert!(!sudoku.is_valid_number(1, 0, 4).unwrap());
+        assert!(!sudoku.is_valid_number(0, 1, 3).unwrap());
+        assert!(sudoku.is_valid_number(0, 1, 4).unwrap());
+    }
+
+    #[test]
+    fn exempt_edges_are_unconstrained() {
+        let mut constraint = ConsecutivePairsConstraint::new();
+        constraint.exempt(Edge::below(0, 0));
+        let sudoku = Sudoku::parse("2x2;2, , , , , , , , , , , , , , , ", constraint).unwrap();
+
+        assert!(sudoku.is_valid_number(0, 1, 3).unwrap());
+        assert!(sudoku.is_valid_number(0, 1, 4).unwrap());
+    }
+
+    #[test]
+    fn from_solution_is_satisfied_by_solution() {
+        let constraint = ConsecutivePairsConstraint::from_solution(&solution());
+        let sudoku = Sudoku::new_with_grid(solution(), constraint.clone()).unwrap();
+
+        assert!(sudoku.is_valid());
+        assert!(constraint.is_marked(Edge::right(1, 0)));
+        assert!(!constraint.is_marked(Edge::right(0, 0)));
+        assert_eq!(12, constraint.markers().count());
+    }
+
+    #[test]
+    fn reduce_and_revert() {
+        let solution = solution();
+        let mut constraint = ConsecutivePairsConstraint::from_solution(&solution);
+        let original = constraint.clone();
+
+        assert_eq!(24, constraint.list_reductions(&solution).len());
+
+        let marked = Edge::right(1, 0);
+        let revert_info = constraint.reduce(&solution, &marked).unwrap();
+        assert!(revert_info);
+        assert!(constraint.is_exempt(marked));
+        assert!(constraint.reduce(&solution, &marked).is_err());
+        assert_eq!(23, constraint.list_reductions(&solution).len());
+        assert!(Sudoku::new_with_grid(solution.clone(), constraint.clone())
+            .unwrap()
+            .is_valid());
+
+        constraint.revert(&solution, &marked, revert_info);
+        assert_eq!(original, constraint);
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let mut constraint = ConsecutivePairsConstraint::from_solution(&solution());
+        constraint.exempt(Edge::below(2, 2));
+        let json = serde_json::to_string(&constraint).unwrap();
+
+        assert_eq!(constraint, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn unversioned_saves_load() {
+        let json = r#"{"markers":[{"column":0,"row":1,"direction":"Right"}],"exempt":[]}"#;
+        let constraint: ConsecutivePairsConstraint = serde_json::from_str(json).unwrap();
+
+        assert!(constraint.is_marked(Edge::right(0, 1)));
+        assert!(serde_json::to_string(&constraint)
+            .unwrap()
+            .contains(r#""version":1"#));
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let json = r#"{"version":2,"markers":[],"exempt":[]}"#;
+        let error = serde_json::from_str::<ConsecutivePairsConstraint>(json).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("newer than the supported version 1"));
+    }
+
+    #[test]
+    fn transposition_moves_edges() {
+        let mut constraint = ConsecutivePairsConstraint::new();
+        constraint.mark(Edge::right(1, 0));
+        constraint.exempt(Edge::below(2, 1));
+        constraint.transform(&GridTransform::transposition(2, 2));
+
+        assert!(constraint.is_marked(Edge::below(0, 1)));
+        assert!(constraint.is_exempt(Edge::right(1, 2)));
+        assert_eq!(1, constraint.markers().count());
+    }
+}
@@ -1,7 +1,11 @@
+pub mod candidates;
+pub mod consecutive;
 pub mod killer;
 pub mod sandwich;
 pub mod thermo;
 
+pub use candidates::{CandidateRestriction, GivenCandidatesConstraint};
+pub use consecutive::{ConsecutivePairsConstraint, ConsecutivePairsDeriver, Edge, EdgeDirection};
 pub use killer::{KillerCage, KillerConstraint, KillerError};
 pub use sandwich::{SandwichConstraint, SandwichError, SandwichResult};
 pub use thermo::{ThermoConstraint, ThermoError, Thermometer};
@@ -0,0 +1,992 @@
+use crate::constraint::presets::{ArgyleConstraint, WindokuConstraint};
+use crate::constraint::simple::SharedConstraint;
+use crate::constraint::versioning;
+use crate::constraint::{
+    self, AdjacentConsecutiveConstraint, BlockConstraint, ColumnConstraint,
+    ConsecutivePairsConstraint, Constraint, ConstraintRegistry, DefaultConstraint,
+    DiagonallyAdjacentConstraint, DiagonalsConstraint, Edge, GivenCandidatesConstraint, Group,
+    GroupVisitor, IrreducibleConstraint, KingsMoveConstraint, KnightsMoveConstraint,
+    ReductionError, RowConstraint, SimpleConstraint, Violation,
+};
+use crate::error::{RulesError, RulesResult, SudokuParseError, SudokuParseResult};
+use crate::utilities::USizeSet;
+use crate::validation::StructuralProblem;
+use crate::view::PuzzleView;
+use crate::{Sudoku, SudokuGrid};
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::any::Any;
+use std::fmt::{self, Display, Formatter};
+
+// Rule names which are recognized by the specification language, but whose
+// constraints are not available in this crate yet.
+const UNSUPPORTED_RULES: [&str; 3] = ["killer", "sandwich", "thermo"];
+
+// The tags of all variants of Rule, see `Rule::tag`.
+const RULE_TAGS: [&str; 14] = [
+    "default",
+    "row",
+    "column",
+    "block",
+    "diagonals",
+    "knights_move",
+    "kings_move",
+    "diagonally_adjacent",
+    "adjacent_consecutive",
+    "windoku",
+    "argyle",
+    "candidates",
+    "consecutive_pairs",
+    "custom",
+];
+
+// A single rule of a variant description. Rules without parameters are
+// written by their name only, such as `diagonals`, the others take their
+// parameters as a JSON object, such as `consecutive_pairs{"markers": [...]}`.
+// Rules are externally tagged, which unlike internal tagging also works with
+// formats that are not self-describing.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    Default,
+    Row,
+    Column,
+    Block,
+    Diagonals,
+    KnightsMove,
+    KingsMove,
+    DiagonallyAdjacent,
+    AdjacentConsecutive,
+    Windoku,
+    Argyle,
+    Candidates(GivenCandidatesConstraint),
+    ConsecutivePairs(ConsecutivePairsConstraint),
+    Custom(CustomRule),
+}
+
+impl Rule {
+    // Creates a rule for the constraint registered under the given name.
+    pub fn custom(registry: &ConstraintRegistry, name: &str) -> RulesResult<Rule> {
+        Ok(Rule::Custom(CustomRule {
+            name: name.to_owned(),
+            constraint: Some(registry.get(name)?),
+        }))
+    }
+
+    fn irreducible(&self) -> Option<&dyn IrreducibleConstraint> {
+        match self {
+            Rule::Default => Some(&DefaultConstraint),
+            Rule::Row => Some(&RowConstraint),
+            Rule::Column => Some(&ColumnConstraint),
+            Rule::Block => Some(&BlockConstraint),
+            Rule::Diagonals => Some(&DiagonalsConstraint),
+            Rule::KnightsMove => Some(&KnightsMoveConstraint),
+            Rule::KingsMove => Some(&KingsMoveConstraint),
+            Rule::DiagonallyAdjacent => Some(&DiagonallyAdjacentConstraint),
+            Rule::AdjacentConsecutive => Some(&AdjacentConsecutiveConstraint),
+            Rule::Windoku => Some(&WindokuConstraint),
+            Rule::Argyle => Some(&ArgyleConstraint),
+            Rule::Custom(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    // The name of the rule in rule sets, without parameters.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Rule::Default => "default",
+            Rule::Row => "row",
+            Rule::Column => "column",
+            Rule::Block => "block",
+            Rule::Diagonals => "diagonals",
+            Rule::KnightsMove => "knights_move",
+            Rule::KingsMove => "kings_move",
+            Rule::DiagonallyAdjacent => "diagonally_adjacent",
+            Rule::AdjacentConsecutive => "adjacent_consecutive",
+            Rule::Windoku => "windoku",
+            Rule::Argyle => "argyle",
+            Rule::Candidates(_) => "candidates",
+            Rule::ConsecutivePairs(_) => "consecutive_pairs",
+            Rule::Custom(_) => "custom",
+        }
+    }
+}
+
+// Built-in constraints without parameters are serialized as the tag of their
+// rule, e.g. "diagonals", so they can be named the same way in rule sets and
+// in saved sudokus. Constraints with parameters, such as
+// `GivenCandidatesConstraint` and `ConsecutivePairsConstraint`, keep their
+// struct encoding, since a tag has no room for their data.
+macro_rules! rule_tag {
+    ($constraint:ident, $rule:ident) => {
+        impl From<$constraint> for Rule {
+            fn from(_: $constraint) -> Rule {
+                Rule::$rule
+            }
+        }
+
+        impl TryFrom<RuleTag> for $constraint {
+            type Error = String;
+
+            fn try_from(tag: RuleTag) -> Result<$constraint, String> {
+                match tag {
+                    RuleTag::Tag(Rule::$rule) | RuleTag::Unit => Ok($constraint),
+                    RuleTag::Tag(other) => Err(format!(
+                        "expected rule {:?}, found {:?}",
+                        Rule::$rule,
+                        other
+                    )),
+                }
+            }
+        }
+    };
+}
+
+rule_tag!(DefaultConstraint, Default);
+rule_tag!(RowConstraint, Row);
+rule_tag!(ColumnConstraint, Column);
+rule_tag!(BlockConstraint, Block);
+rule_tag!(DiagonalsConstraint, Diagonals);
+rule_tag!(KnightsMoveConstraint, KnightsMove);
+rule_tag!(KingsMoveConstraint, KingsMove);
+rule_tag!(DiagonallyAdjacentConstraint, DiagonallyAdjacent);
+rule_tag!(AdjacentConsecutiveConstraint, AdjacentConsecutive);
+rule_tag!(WindokuConstraint, Windoku);
+rule_tag!(ArgyleConstraint, Argyle);
+
+// A serialized built-in constraint without parameters. Before they were
+// written as tags these constraints were unit values, which JSON stores as
+// `null`. Human-readable formats still accept that form. Binary formats such
+// as postcard cannot tell the two forms apart and only read tags.
+pub enum RuleTag {
+    Tag(Rule),
+    Unit,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RuleTagData {
+    Tag(Rule),
+    Unit(()),
+}
+
+impl<'de> Deserialize<'de> for RuleTag {
+    fn deserialize<D>(deserializer: D) -> Result<RuleTag, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            Ok(match RuleTagData::deserialize(deserializer)? {
+                RuleTagData::Tag(rule) => RuleTag::Tag(rule),
+                RuleTagData::Unit(()) => RuleTag::Unit,
+            })
+        } else {
+            Rule::deserialize(deserializer).map(RuleTag::Tag)
+        }
+    }
+}
+
+// A rule backed by a constraint of a `ConstraintRegistry`, written as
+// `custom{"name": "..."}`. Only the name is stored, so custom rules of a
+// deserialized rule set reject every number until `RuleSet::resolve` looks
+// them up again. A puzzle is never solved without one of its rules.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct CustomRule {
+    name: String,
+    #[serde(skip)]
+    constraint: Option<SharedConstraint>,
+}
+
+impl CustomRule {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.constraint.is_some()
+    }
+}
+
+impl fmt::Debug for CustomRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomRule")
+            .field("name", &self.name)
+            .field("resolved", &self.is_resolved())
+            .finish()
+    }
+}
+
+// Custom rules are identified by their name, like in the serialized form,
+// and whether they are resolved, since unresolved rules behave differently.
+impl PartialEq for CustomRule {
+    fn eq(&self, other: &CustomRule) -> bool {
+        self.name == other.name && self.is_resolved() == other.is_resolved()
+    }
+}
+
+impl Eq for CustomRule {}
+
+impl SimpleConstraint for CustomRule {
+    fn check_number(
+        &self,
+        grid: &dyn PuzzleView,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> bool {
+        self.constraint
+            .as_ref()
+            .is_some_and(|c| c.check_number(grid, column, row, number))
+    }
+
+    fn groups(&self, grid: &dyn PuzzleView) -> Vec<Group> {
+        self.constraint
+            .as_ref()
+            .map_or_else(Vec::new, |c| c.groups(grid))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RuleReduction {
+    Cell(usize, usize),
+    Edge(Edge),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RuleRevertInfo {
+    Candidates(USizeSet),
+    Marker(bool),
+}
+
+// A constraint composed at runtime from a list of rules, so that variants can
+// be stored as data rather than described by nested `CompositeConstraint`
+// types. It can be read from a textual specification such as
+// `rules: [default, diagonals]` or deserialized from the equivalent
+// `{"rules": ["default", "diagonals"]}`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "RuleSetData", into = "RuleSetData")]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+const RULE_SET_VERSION: u32 = 2;
+
+#[derive(Deserialize, Serialize)]
+struct RuleSetData {
+    #[serde(default)]
+    version: u32,
+    rules: Vec<RuleData>,
+}
+
+// A rule as stored in a rule set. Before version 2 rules were internally
+// tagged, e.g. `{"type": "diagonals"}`, which needs a self-describing
+// format. Such formats still accept that layout and migrate it to the
+// externally tagged `Rule`.
+enum RuleData {
+    Tagged(Rule),
+    InternallyTagged(Rule),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RuleLayout {
+    Tagged(Rule),
+    InternallyTagged(serde_json::Map<String, serde_json::Value>),
+}
+
+impl<'de> Deserialize<'de> for RuleData {
+    fn deserialize<D>(deserializer: D) -> Result<RuleData, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if !deserializer.is_human_readable() {
+            return Rule::deserialize(deserializer).map(RuleData::Tagged);
+        }
+
+        match RuleLayout::deserialize(deserializer)? {
+            RuleLayout::Tagged(rule) => Ok(RuleData::Tagged(rule)),
+            RuleLayout::InternallyTagged(object) => migrate_internally_tagged(object)
+                .map(RuleData::InternallyTagged)
+                .map_err(D::Error::custom),
+        }
+    }
+}
+
+impl Serialize for RuleData {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            RuleData::Tagged(rule) | RuleData::InternallyTagged(rule) => rule.serialize(serializer),
+        }
+    }
+}
+
+// Moves the "type" field of an internally tagged rule out into the external
+// tag. Rules without parameters become their bare tag.
+fn migrate_internally_tagged(
+    mut object: serde_json::Map<String, serde_json::Value>,
+) -> Result<Rule, String> {
+    let tag = match object.remove("type") {
+        Some(serde_json::Value::String(tag)) => tag,
+        _ => return Err("rule without a type".to_owned()),
+    };
+    let value = if object.is_empty() {
+        serde_json::Value::String(tag)
+    } else {
+        let mut tagged = serde_json::Map::new();
+        tagged.insert(tag, serde_json::Value::Object(object));
+        serde_json::Value::Object(tagged)
+    };
+
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+impl TryFrom<RuleSetData> for RuleSet {
+    type Error = String;
+
+    fn try_from(data: RuleSetData) -> Result<Self, String> {
+        let version =
+            versioning::check_version(data.version, RULE_SET_VERSION).map_err(|e| e.to_string())?;
+        let rules = data
+            .rules
+            .into_iter()
+            .map(|rule| match rule {
+                RuleData::Tagged(rule) => Ok(rule),
+                RuleData::InternallyTagged(rule) if version < 2 => Ok(rule),
+                RuleData::InternallyTagged(rule) => Err(format!(
+                    "rule {} is internally tagged, which version {} does not support",
+                    rule.tag(),
+                    version
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(RuleSet::new(rules))
+    }
+}
+
+impl From<RuleSet> for RuleSetData {
+    fn from(rule_set: RuleSet) -> Self {
+        RuleSetData {
+            version: RULE_SET_VERSION,
+            rules: rule_set.rules.into_iter().map(RuleData::Tagged).collect(),
+        }
+    }
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<Rule>) -> RuleSet {
+        RuleSet { rules }
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn push(&mut self, rule: Rule) {
+        self.rules.push(rule);
+    }
+
+    // Looks up the constraints of all custom rules in the registry. If any of
+    // them is not registered, no rule is changed.
+    pub fn resolve(&mut self, registry: &ConstraintRegistry) -> RulesResult<()> {
+        let constraints = self
+            .rules
+            .iter()
+            .filter_map(|rule| match rule {
+                Rule::Custom(c) => Some(registry.get(&c.name)),
+                _ => None,
+            })
+            .collect::<RulesResult<Vec<_>>>()?;
+        let customs = self.rules.iter_mut().filter_map(|rule| match rule {
+            Rule::Custom(c) => Some(c),
+            _ => None,
+        });
+
+        for (custom, constraint) in customs.zip(constraints) {
+            custom.constraint = Some(constraint);
+        }
+
+        Ok(())
+    }
+
+    // The names of custom rules which are not resolved yet.
+    pub fn unresolved(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().filter_map(|rule| match rule {
+            Rule::Custom(c) if !c.is_resolved() => Some(c.name()),
+            _ => None,
+        })
+    }
+
+    pub fn parse(spec: &str) -> RulesResult<RuleSet> {
+        let spec = spec.trim();
+        let spec = spec.strip_prefix("rules:").unwrap_or(spec).trim();
+        let list = spec
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or_else(|| RulesError::Syntax("expected a bracketed list of rules".to_owned()))?;
+
+        split_top_level(list)?
+            .into_iter()
+            .filter(|entry| !entry.is_empty())
+            .map(parse_rule)
+            .collect::<RulesResult<Vec<_>>>()
+            .map(RuleSet::new)
+    }
+}
+
+// Writes the rules in the textual specification format, which `parse` reads
+// back.
+impl Display for RuleSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let rules = self
+            .rules
+            .iter()
+            .map(|rule| match serde_json::to_value(rule).unwrap() {
+                serde_json::Value::Object(object) => object
+                    .into_iter()
+                    .map(|(name, parameters)| format!("{}{}", name, parameters))
+                    .collect(),
+                serde_json::Value::String(name) => name,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        write!(f, "rules: [{}]", rules)
+    }
+}
+
+impl Sudoku<RuleSet> {
+    // Parses a sudoku in the format of `SudokuGrid::parse` followed by a
+    // third part specifying the rules, such as
+    // `2x2;1,,,,,,,,,,,,,,,;rules: [default, diagonals]`.
+    pub fn parse_with_rules(code: &str) -> SudokuParseResult<Sudoku<RuleSet>> {
+        let mut parts = code.splitn(3, ';');
+        let dimensions = parts.next().unwrap();
+        let cells = parts.next().ok_or(SudokuParseError::WrongNumberOfParts)?;
+        let rules = parts.next().ok_or(SudokuParseError::WrongNumberOfParts)?;
+        let rules = RuleSet::parse(rules).map_err(SudokuParseError::InvalidRules)?;
+
+        Sudoku::parse(&format!("{};{}", dimensions, cells), rules)
+    }
+
+    pub fn to_parseable_string_with_rules(&self) -> String {
+        format!(
+            "{};{}",
+            self.grid().to_parseable_string(),
+            self.constraint()
+        )
+    }
+}
+
+// Splits at all commas which are not nested inside braces, brackets or
+// strings.
+fn split_top_level(list: &str) -> RulesResult<Vec<&str>> {
+    let mut entries = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (index, c) in list.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| RulesError::Syntax(format!("unbalanced '{}'", c)))?;
+            }
+            ',' if depth == 0 => {
+                entries.push(list[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if depth > 0 || in_string {
+        return Err(RulesError::Syntax(
+            "unterminated rule parameters".to_owned(),
+        ));
+    }
+
+    entries.push(list[start..].trim());
+    Ok(entries)
+}
+
+fn parse_rule(entry: &str) -> RulesResult<Rule> {
+    let (name, parameters) = match entry.find('{') {
+        Some(index) => (entry[..index].trim(), Some(&entry[index..])),
+        None => (entry, None),
+    };
+
+    if UNSUPPORTED_RULES.contains(&name) {
+        return Err(RulesError::UnsupportedRule(name.to_owned()));
+    }
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(RulesError::Syntax(format!("invalid rule name '{}'", name)));
+    }
+
+    if !RULE_TAGS.contains(&name) {
+        return Err(RulesError::UnknownRule(name.to_owned()));
+    }
+
+    let value = match parameters {
+        Some(parameters) => {
+            let parameters =
+                serde_json::from_str(parameters).map_err(|e| RulesError::Syntax(e.to_string()))?;
+            let mut object = serde_json::Map::new();
+            object.insert(name.to_owned(), parameters);
+            serde_json::Value::Object(object)
+        }
+        None => serde_json::Value::String(name.to_owned()),
+    };
+
+    serde_json::from_value(value).map_err(|e| RulesError::InvalidParameters {
+        rule: name.to_owned(),
+        message: e.to_string(),
+    })
+}
+
+impl Constraint for RuleSet {
+    type Reduction = (usize, RuleReduction);
+    type RevertInfo = RuleRevertInfo;
+
+    fn check_number(
+        &self,
+        grid: &dyn PuzzleView,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> bool {
+        self.rules.iter().all(|rule| match rule {
+            Rule::Candidates(c) => c.check_number(grid, column, row, number),
+            Rule::ConsecutivePairs(c) => c.check_number(grid, column, row, number),
+            _ => rule
+                .irreducible()
+                .unwrap()
+                .check_number(grid, column, row, number),
+        })
+    }
+
+    // Violations of irreducible rules are reported under the tag of their
+    // rule, which also names the built-in constraints without a name.
+    fn check_detailed(&self, grid: &dyn PuzzleView) -> Vec<Violation> {
+        self.rules
+            .iter()
+            .flat_map(|rule| match rule {
+                Rule::Candidates(c) => c.check_detailed(grid),
+                Rule::ConsecutivePairs(c) => c.check_detailed(grid),
+                _ => constraint::default_check_detailed(
+                    rule.irreducible().unwrap(),
+                    grid,
+                    rule.tag(),
+                ),
+            })
+            .collect()
+    }
+
+    fn get_groups(&self, grid: &dyn PuzzleView) -> Vec<Group> {
+        self.rules
+            .iter()
+            .flat_map(|rule| match rule {
+                Rule::Candidates(c) => c.get_groups(grid),
+                Rule::ConsecutivePairs(c) => c.get_groups(grid),
+                _ => rule.irreducible().unwrap().get_groups(grid),
+            })
+            .collect()
+    }
+
+    fn for_each_group(&self, grid: &dyn PuzzleView, f: &mut GroupVisitor<'_>) {
+        for rule in self.rules.iter() {
+            match rule {
+                Rule::Candidates(c) => c.for_each_group(grid, f),
+                Rule::ConsecutivePairs(c) => c.for_each_group(grid, f),
+                _ => rule.irreducible().unwrap().for_each_group(grid, f),
+            }
+        }
+    }
+
+    fn structural_problems(&self, grid: &SudokuGrid) -> Vec<StructuralProblem> {
+        self.rules
+            .iter()
+            .flat_map(|rule| match rule {
+                Rule::Candidates(c) => c.structural_problems(grid),
+                Rule::ConsecutivePairs(c) => c.structural_problems(grid),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    fn list_reductions(&self, solution: &SudokuGrid) -> Vec<(usize, RuleReduction)> {
+        let mut reductions = Vec::new();
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            match rule {
+                Rule::Candidates(c) => reductions.extend(
+                    c.list_reductions(solution)
+                        .into_iter()
+                        .map(|(column, row)| (index, RuleReduction::Cell(column, row))),
+                ),
+                Rule::ConsecutivePairs(c) => reductions.extend(
+                    c.list_reductions(solution)
+                        .into_iter()
+                        .map(|edge| (index, RuleReduction::Edge(edge))),
+                ),
+                _ => {}
+            }
+        }
+
+        reductions
+    }
+
+    fn reduce(
+        &mut self,
+        solution: &SudokuGrid,
+        reduction: &(usize, RuleReduction),
+    ) -> Result<RuleRevertInfo, ReductionError> {
+        let (index, reduction) = reduction;
+
+        match (self.rules.get_mut(*index), reduction) {
+            (Some(Rule::Candidates(c)), RuleReduction::Cell(column, row)) => c
+                .reduce(solution, &(*column, *row))
+                .map(RuleRevertInfo::Candidates),
+            (Some(Rule::ConsecutivePairs(c)), RuleReduction::Edge(edge)) => {
+                c.reduce(solution, edge).map(RuleRevertInfo::Marker)
+            }
+            _ => Err(ReductionError::InvalidReduction),
+        }
+    }
+
+    fn revert(
+        &mut self,
+        solution: &SudokuGrid,
+        reduction: &(usize, RuleReduction),
+        revert_info: RuleRevertInfo,
+    ) {
+        let (index, reduction) = reduction;
+
+        match (&mut self.rules[*index], reduction, revert_info) {
+            (
+                Rule::Candidates(c),
+                RuleReduction::Cell(column, row),
+                RuleRevertInfo::Candidates(info),
+            ) => c.revert(solution, &(*column, *row), info),
+            (
+                Rule::ConsecutivePairs(c),
+                RuleReduction::Edge(edge),
+                RuleRevertInfo::Marker(info),
+            ) => c.revert(solution, edge, info),
+            _ => panic!("revert info does not match the reduced rule"),
+        }
+    }
+
+    fn to_objects(&self) -> Vec<&dyn Any>
+    where
+        Self: Sized + 'static,
+    {
+        self.rules
+            .iter()
+            .map(|rule| -> &dyn Any {
+                match rule {
+                    Rule::Default => &DefaultConstraint,
+                    Rule::Row => &RowConstraint,
+                    Rule::Column => &ColumnConstraint,
+                    Rule::Block => &BlockConstraint,
+                    Rule::Diagonals => &DiagonalsConstraint,
+                    Rule::KnightsMove => &KnightsMoveConstraint,
+                    Rule::KingsMove => &KingsMoveConstraint,
+                    Rule::DiagonallyAdjacent => &DiagonallyAdjacentConstraint,
+                    Rule::AdjacentConsecutive => &AdjacentConsecutiveConstraint,
+                    Rule::Windoku => &WindokuConstraint,
+                    Rule::Argyle => &ArgyleConstraint,
+                    Rule::Candidates(c) => c,
+                    Rule::ConsecutivePairs(c) => c,
+                    Rule::Custom(c) => c,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::Subconstraint;
+    use crate::Sudoku;
+
+    #[test]
+    fn parses_rules_without_parameters() {
+        let rules = RuleSet::parse("rules: [default, diagonals]").unwrap();
+        assert_eq!(&[Rule::Default, Rule::Diagonals], rules.rules());
+        assert_eq!(rules, RuleSet::parse("[default,diagonals,]").unwrap());
+    }
+
+    #[test]
+    fn parses_rules_with_parameters() {
+        let rules = RuleSet::parse(
+            "rules: [default, consecutive_pairs{\"markers\": \
+            [{\"column\": 0, \"row\": 0, \"direction\": \"Right\"}]}]",
+        )
+        .unwrap();
+        let mut consecutive = ConsecutivePairsConstraint::new();
+        consecutive.mark(Edge::right(0, 0));
+
+        assert_eq!(
+            &[Rule::Default, Rule::ConsecutivePairs(consecutive)],
+            rules.rules()
+        );
+    }
+
+    #[test]
+    fn reports_bad_specifications() {
+        assert_eq!(
+            Err(RulesError::UnknownRule("anti_king".to_owned())),
+            RuleSet::parse("rules: [default, anti_king]")
+        );
+        assert_eq!(
+            Err(RulesError::UnsupportedRule("killer".to_owned())),
+            RuleSet::parse("rules: [killer{\"cages\": []}]")
+        );
+        assert!(matches!(
+            RuleSet::parse("rules: [default, diagonals"),
+            Err(RulesError::Syntax(_))
+        ));
+        assert!(matches!(
+            RuleSet::parse("rules: [consecutive_pairs{\"markers\": 3}]"),
+            Err(RulesError::InvalidParameters { .. })
+        ));
+        assert_eq!(
+            Err(RulesError::UnknownRule("anti_king".to_owned())),
+            RuleSet::parse("rules: [anti_king{\"cells\": []}]")
+        );
+        assert!(matches!(
+            RuleSet::parse("rules: [diagonals{\"cells\": []}]"),
+            Err(RulesError::InvalidParameters { .. })
+        ));
+
+        for tag in RULE_TAGS {
+            assert!(!matches!(
+                RuleSet::parse(&format!("rules: [{}]", tag)),
+                Err(RulesError::UnknownRule(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn violations_name_their_rule() {
+        let rules = RuleSet::parse("rules: [default, diagonals, knights_move]").unwrap();
+        let mut sudoku = Sudoku::new_empty(2, 2, rules).unwrap();
+
+        for (column, row, number) in [
+            (0, 0, 1),
+            (1, 2, 1),
+            (1, 1, 3),
+            (2, 2, 3),
+            (0, 3, 4),
+            (3, 3, 4),
+        ] {
+            sudoku.grid_mut().set_cell(column, row, number).unwrap();
+        }
+
+        let rules: Vec<&str> = sudoku.violations().iter().map(Violation::rule).collect();
+        assert_eq!(
+            vec!["row", "diagonals", "knights_move", "knights_move"],
+            rules
+        );
+    }
+
+    #[test]
+    fn checks_all_rules() {
+        let rules = RuleSet::parse("rules: [default, diagonals]").unwrap();
+        let mut sudoku = Sudoku::new_empty(2, 2, rules).unwrap();
+
+        sudoku.grid_mut().set_cell(0, 0, 1).unwrap();
+        assert!(sudoku.is_valid_number(1, 0, 2).unwrap());
+        assert!(!sudoku.is_valid_number(1, 1, 1).unwrap());
+        assert!(!sudoku.is_valid_number(3, 0, 1).unwrap());
+        assert!(sudoku
+            .constraint()
+            .has_subconstraints::<DiagonalsConstraint>());
+    }
+
+    #[test]
+    fn visits_the_groups_of_all_rules() {
+        let rules = RuleSet::parse("rules: [row, diagonals]").unwrap();
+        let grid = SudokuGrid::new(2, 2).unwrap();
+        let mut groups = Vec::new();
+        rules.for_each_group(&grid, &mut |group| groups.push(group.to_vec()));
+
+        assert_eq!(rules.get_groups(&grid), groups);
+        assert_eq!(6, groups.len());
+    }
+
+    #[test]
+    fn rules_round_trip_through_text() {
+        let mut consecutive = ConsecutivePairsConstraint::new();
+        consecutive.mark(Edge::below(1, 2));
+        let rules = RuleSet::new(vec![Rule::Default, Rule::ConsecutivePairs(consecutive)]);
+        let sudoku = Sudoku::parse("2x2;1,,,,,,,,,,,,,,,4", rules).unwrap();
+        let code = sudoku.to_parseable_string_with_rules();
+        let parsed = Sudoku::parse_with_rules(&code).unwrap();
+
+        assert!(code.starts_with("2x2;1,,,,,,,,,,,,,,,4;rules: [default, consecutive_pairs{"));
+        assert_eq!(sudoku.grid(), parsed.grid());
+        assert_eq!(sudoku.constraint(), parsed.constraint());
+    }
+
+    #[test]
+    fn parse_with_rules_reports_errors() {
+        assert_eq!(
+            Err(SudokuParseError::WrongNumberOfParts),
+            Sudoku::parse_with_rules("2x2;1,,,,,,,,,,,,,,,4").map(|_| ())
+        );
+        assert_eq!(
+            Err(SudokuParseError::InvalidRules(RulesError::UnsupportedRule(
+                "killer".to_owned()
+            ))),
+            Sudoku::parse_with_rules("2x2;1,,,,,,,,,,,,,,,4;rules: [default, killer]").map(|_| ())
+        );
+    }
+
+    #[test]
+    fn custom_rules_are_resolved_by_name() {
+        let mut registry = ConstraintRegistry::new();
+        registry.register_fn("no_ones", |_: &dyn PuzzleView, _, _, number| number != 1);
+        let rules = RuleSet::new(vec![
+            Rule::KingsMove,
+            Rule::custom(&registry, "no_ones").unwrap(),
+        ]);
+        let grid = SudokuGrid::new(2, 2).unwrap();
+
+        assert!(!rules.check_number(&grid, 0, 0, 1));
+        assert_eq!(
+            Err(RulesError::UnknownRule("other".to_owned())),
+            Rule::custom(&registry, "other")
+        );
+
+        let code = rules.to_string();
+        let mut parsed = RuleSet::parse(&code).unwrap();
+
+        assert_eq!("rules: [kings_move, custom{\"name\":\"no_ones\"}]", code);
+        assert_ne!(rules, parsed);
+        assert_eq!(vec!["no_ones"], parsed.unresolved().collect::<Vec<_>>());
+        assert!(!parsed.check_number(&grid, 0, 0, 2));
+
+        parsed.resolve(&registry).unwrap();
+
+        assert_eq!(rules, parsed);
+        assert_eq!(0, parsed.unresolved().count());
+        assert!(!parsed.check_number(&grid, 0, 0, 1));
+        assert!(parsed.check_number(&grid, 0, 0, 2));
+    }
+
+    fn assert_round_trip<C>(constraint: C, tag: &str)
+    where
+        C: Constraint + Clone + Serialize + for<'de> Deserialize<'de> + 'static,
+    {
+        let json = serde_json::to_string(&constraint).unwrap();
+        let from_json: C = serde_json::from_str(&json).unwrap();
+        let bytes = postcard::to_allocvec(&constraint).unwrap();
+        let from_bytes: C = postcard::from_bytes(&bytes).unwrap();
+
+        assert_eq!(format!("\"{}\"", tag), json);
+        assert!(from_json.to_objects()[0].is::<C>());
+        assert!(from_bytes.to_objects()[0].is::<C>());
+    }
+
+    #[test]
+    fn built_in_constraints_round_trip_as_tags() {
+        assert_round_trip(DefaultConstraint, "default");
+        assert_round_trip(RowConstraint, "row");
+        assert_round_trip(ColumnConstraint, "column");
+        assert_round_trip(BlockConstraint, "block");
+        assert_round_trip(DiagonalsConstraint, "diagonals");
+        assert_round_trip(KnightsMoveConstraint, "knights_move");
+        assert_round_trip(KingsMoveConstraint, "kings_move");
+        assert_round_trip(DiagonallyAdjacentConstraint, "diagonally_adjacent");
+        assert_round_trip(AdjacentConsecutiveConstraint, "adjacent_consecutive");
+        assert_round_trip(WindokuConstraint, "windoku");
+        assert_round_trip(ArgyleConstraint, "argyle");
+
+        assert!(serde_json::from_str::<RowConstraint>("\"windoku\"").is_err());
+        assert!(serde_json::from_str::<RowConstraint>("\"nonsense\"").is_err());
+
+        // Before they were serialized as tags, these constraints were unit
+        // values.
+        let legacy: RowConstraint = serde_json::from_str("null").unwrap();
+        assert_eq!("\"row\"", serde_json::to_string(&legacy).unwrap());
+        let legacy: DiagonalsConstraint = serde_json::from_str("null").unwrap();
+        assert_eq!("\"diagonals\"", serde_json::to_string(&legacy).unwrap());
+    }
+
+    #[test]
+    fn migrates_internally_tagged_rules() {
+        let mut consecutive = ConsecutivePairsConstraint::new();
+        consecutive.mark(Edge::below(1, 2));
+        let mut parameters = serde_json::to_value(&consecutive).unwrap();
+        parameters["type"] = serde_json::Value::from("consecutive_pairs");
+        let old = format!(
+            r#"{{"rules": [{{"type": "default"}}, {{"type": "knights_move"}}, {}]}}"#,
+            parameters
+        );
+        let migrated: RuleSet = serde_json::from_str(&old).unwrap();
+        let expected = RuleSet::new(vec![
+            Rule::Default,
+            Rule::KnightsMove,
+            Rule::ConsecutivePairs(consecutive),
+        ]);
+
+        assert_eq!(expected, migrated);
+        assert!(serde_json::to_string(&migrated)
+            .unwrap()
+            .starts_with(r#"{"version":2,"rules":["default","knights_move","#));
+        assert!(
+            serde_json::from_str::<RuleSet>(r#"{"version": 1, "rules": [{"type": "row"}]}"#)
+                .is_ok()
+        );
+        assert!(
+            serde_json::from_str::<RuleSet>(r#"{"version": 2, "rules": [{"type": "row"}]}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn postcard_round_trip() {
+        let mut consecutive = ConsecutivePairsConstraint::new();
+        consecutive.mark(Edge::below(1, 2));
+        let mut candidates = GivenCandidatesConstraint::new();
+        candidates.restrict(0, 0, USizeSet::new(1, 4).unwrap());
+        let rules = RuleSet::new(vec![
+            Rule::Default,
+            Rule::ConsecutivePairs(consecutive),
+            Rule::Candidates(candidates),
+        ]);
+        let sudoku = Sudoku::new_empty(2, 2, rules).unwrap();
+        let bytes = postcard::to_allocvec(&sudoku).unwrap();
+        let deserialized: Sudoku<RuleSet> = postcard::from_bytes(&bytes).unwrap();
+
+        assert_eq!(sudoku.grid(), deserialized.grid());
+        assert_eq!(sudoku.constraint(), deserialized.constraint());
+    }
+}
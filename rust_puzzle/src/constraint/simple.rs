@@ -0,0 +1,218 @@
+use crate::constraint::{Group, IrreducibleConstraint};
+use crate::error::{RulesError, RulesResult};
+use crate::view::PuzzleView;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// A reduced form of IrreducibleConstraint for custom rules, which only have
+// to decide whether a number may go into a cell. Rules which require the
+// numbers in some cells to be distinct can also report those cells as
+// groups. Every SimpleConstraint is a Constraint.
+pub trait SimpleConstraint {
+    fn check_number(&self, grid: &dyn PuzzleView, column: usize, row: usize, number: usize)
+        -> bool;
+
+    fn groups(&self, _grid: &dyn PuzzleView) -> Vec<Group> {
+        Vec::new()
+    }
+}
+
+impl<S: SimpleConstraint> IrreducibleConstraint for S {
+    fn check_number(
+        &self,
+        grid: &dyn PuzzleView,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> bool {
+        <S as SimpleConstraint>::check_number(self, grid, column, row, number)
+    }
+
+    fn get_groups(&self, grid: &dyn PuzzleView) -> Vec<Group> {
+        self.groups(grid)
+    }
+}
+
+struct FnConstraint<F>(F);
+
+impl<F> SimpleConstraint for FnConstraint<F>
+where
+    F: Fn(&dyn PuzzleView, usize, usize, usize) -> bool,
+{
+    fn check_number(
+        &self,
+        grid: &dyn PuzzleView,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> bool {
+        (self.0)(grid, column, row, number)
+    }
+}
+
+pub(crate) type SharedConstraint = Arc<dyn SimpleConstraint + Send + Sync>;
+
+// Custom rules registered by name, which can be combined at runtime, e.g.
+// from a list of rule names stored with a puzzle.
+#[derive(Clone, Default)]
+pub struct ConstraintRegistry {
+    constraints: HashMap<String, SharedConstraint>,
+}
+
+impl ConstraintRegistry {
+    pub fn new() -> ConstraintRegistry {
+        ConstraintRegistry::default()
+    }
+
+    // Registers the constraint under the given name, replacing any
+    // constraint previously registered under it.
+    pub fn register<S>(&mut self, name: impl Into<String>, constraint: S)
+    where
+        S: SimpleConstraint + Send + Sync + 'static,
+    {
+        self.constraints.insert(name.into(), Arc::new(constraint));
+    }
+
+    // Registers a rule given by its check_number function.
+    pub fn register_fn<F>(&mut self, name: impl Into<String>, check_number: F)
+    where
+        F: Fn(&dyn PuzzleView, usize, usize, usize) -> bool + Send + Sync + 'static,
+    {
+        self.register(name, FnConstraint(check_number));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.constraints.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.constraints.keys().map(String::as_str)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> RulesResult<SharedConstraint> {
+        self.constraints
+            .get(name)
+            .cloned()
+            .ok_or_else(|| RulesError::UnknownRule(name.to_owned()))
+    }
+
+    // Combines the constraints registered under the given names.
+    pub fn combine<'a, I>(&self, names: I) -> RulesResult<RegisteredRules>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        names
+            .into_iter()
+            .map(|name| Ok((name.to_owned(), self.get(name)?)))
+            .collect::<RulesResult<Vec<_>>>()
+            .map(|rules| RegisteredRules { rules })
+    }
+}
+
+// A combination of registered constraints, which must all be satisfied.
+#[derive(Clone)]
+pub struct RegisteredRules {
+    rules: Vec<(String, SharedConstraint)>,
+}
+
+impl RegisteredRules {
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|(name, _)| name.as_str())
+    }
+}
+
+impl SimpleConstraint for RegisteredRules {
+    fn check_number(
+        &self,
+        grid: &dyn PuzzleView,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> bool {
+        self.rules
+            .iter()
+            .all(|(_, rule)| rule.check_number(grid, column, row, number))
+    }
+
+    fn groups(&self, grid: &dyn PuzzleView) -> Vec<Group> {
+        self.rules
+            .iter()
+            .flat_map(|(_, rule)| rule.groups(grid))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{CompositeConstraint, DefaultConstraint};
+    use crate::Sudoku;
+
+    struct MainDiagonal;
+
+    impl SimpleConstraint for MainDiagonal {
+        fn check_number(
+            &self,
+            grid: &dyn PuzzleView,
+            column: usize,
+            row: usize,
+            number: usize,
+        ) -> bool {
+            column != row
+                || (0..grid.size()).all(|i| i == column || !grid.has_number(i, i, number).unwrap())
+        }
+
+        fn groups(&self, grid: &dyn PuzzleView) -> Vec<Group> {
+            vec![(0..grid.size()).map(|i| (i, i)).collect()]
+        }
+    }
+
+    fn registry() -> ConstraintRegistry {
+        let mut registry = ConstraintRegistry::new();
+        registry.register("main diagonal", MainDiagonal);
+        registry.register_fn(
+            "no 4 in corners",
+            |grid: &dyn PuzzleView, column, row, number| {
+                let last = grid.size() - 1;
+                number != 4 || !((column == 0 || column == last) && (row == 0 || row == last))
+            },
+        );
+        registry
+    }
+
+    #[test]
+    fn registered_rules_restrict_numbers() {
+        let rules = registry()
+            .combine(["main diagonal", "no 4 in corners"])
+            .unwrap();
+        let mut sudoku = Sudoku::new_empty(2, 2, rules).unwrap();
+        sudoku.grid_mut().set_cell(0, 0, 1).unwrap();
+
+        assert!(!sudoku.is_valid_number(2, 2, 1).unwrap());
+        assert!(sudoku.is_valid_number(2, 1, 1).unwrap());
+        assert!(!sudoku.is_valid_number(3, 0, 4).unwrap());
+        assert_eq!(1, sudoku.constraint().get_groups(sudoku.grid()).len());
+    }
+
+    #[test]
+    fn unknown_names_are_reported() {
+        assert_eq!(
+            Some(RulesError::UnknownRule("anti king".to_owned())),
+            registry().combine(["main diagonal", "anti king"]).err()
+        );
+    }
+
+    #[test]
+    fn registered_rules_compose_with_builtin_constraints() {
+        let rules = registry().combine(["no 4 in corners"]).unwrap();
+        let constraint = CompositeConstraint::new(DefaultConstraint, rules);
+        let valid = Sudoku::parse("2x2;1,2,3,,,,,,,,,,,,,", constraint.clone()).unwrap();
+        let invalid = Sudoku::parse("2x2;1,2,3,4,,,,,,,,,,,,", constraint).unwrap();
+
+        assert!(valid.is_valid());
+        assert!(!invalid.is_valid());
+    }
+}
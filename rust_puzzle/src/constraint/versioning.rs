@@ -0,0 +1,48 @@
+use std::fmt::{self, Display, Formatter};
+
+// Serializable constraints store the version of their representation next to
+// their data and are deserialized through a separate data type, whose
+// conversion into the constraint is where older versions get migrated.
+// Version 0 denotes saves from before versioning was introduced, which is
+// what a missing version field deserializes to.
+//
+// Version 1 of every representation is the layout those saves already used,
+// so version 0 loads without migration. A representation gets a new version
+// whenever a field is renamed or changes its meaning, and its conversion
+// migrates everything older, as RuleSet does for version 2.
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UnsupportedVersion {
+    found: u32,
+    supported: u32,
+}
+
+impl UnsupportedVersion {
+    pub fn found(&self) -> u32 {
+        self.found
+    }
+
+    pub fn supported(&self) -> u32 {
+        self.supported
+    }
+}
+
+impl Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "representation version {} is newer than the supported version {}",
+            self.found, self.supported
+        )
+    }
+}
+
+// Rejects versions written by a newer release of the crate and returns the
+// version otherwise, so callers can match on it to migrate.
+pub(crate) fn check_version(found: u32, supported: u32) -> Result<u32, UnsupportedVersion> {
+    if found > supported {
+        Err(UnsupportedVersion { found, supported })
+    } else {
+        Ok(found)
+    }
+}
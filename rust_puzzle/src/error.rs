@@ -1,12 +1,24 @@
 use std::fmt::{self, Display, Formatter};
+use std::io;
 use std::num::ParseIntError;
 
+use crate::sudoku_generator::FillStatistics;
+use crate::validation::StructuralProblem;
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum SudokuError{
     InvalidDimensions,
     InvalidNumber,
     OutOfBounds,
-    UnsatisfiableConstraint
+    UnsatisfiableConstraint,
+    InvalidSolution,
+    GivenCell,
+    FillExhausted(FillStatistics),
+    IncompatibleConstraint(StructuralProblem),
+    InvalidPlacement { index: usize, error: Box<SudokuError> },
+    InvalidJitter,
+    NoAttempts,
+    InvalidCalibration
 }
 
 impl Display for SudokuError{
@@ -15,7 +27,20 @@ impl Display for SudokuError{
             SudokuError::InvalidDimensions => write!(f, "invalid dimensions"),
             SudokuError::InvalidNumber => write!(f, "invalid number"),
             SudokuError::OutOfBounds => write!(f, "out of bounds"),
-            SudokuError::UnsatisfiableConstraint => write!(f, "unsatisfiable constraint")
+            SudokuError::UnsatisfiableConstraint => write!(f, "unsatisfiable constraint"),
+            SudokuError::InvalidSolution => write!(f, "invalid solution"),
+            SudokuError::GivenCell => write!(f, "given cells cannot be changed"),
+            SudokuError::FillExhausted(statistics) =>
+                write!(f, "gave up filling after {} attempts with {} backtracks",
+                    statistics.attempts(), statistics.backtracks()),
+            SudokuError::IncompatibleConstraint(problem) =>
+                write!(f, "incompatible constraint: {}", problem),
+            SudokuError::InvalidPlacement { index, error } =>
+                write!(f, "invalid placement {}: {}", index, error),
+            SudokuError::InvalidJitter => write!(f, "invalid jitter scale"),
+            SudokuError::NoAttempts => write!(f, "no attempts to search with"),
+            SudokuError::InvalidCalibration =>
+                write!(f, "difficulty thresholds are not in ascending order")
         }
     }
 }
@@ -29,7 +54,10 @@ pub enum SudokuParseError{
     MalformedDimensions,
     InvalidDimensions,
     NumberFormatError,
-    InvalidNumber
+    InvalidNumber,
+    IncompatibleConstraint(StructuralProblem),
+    AmbiguousDimensions(Vec<(usize, usize)>),
+    InvalidRules(RulesError)
 }
 
 impl From<ParseIntError> for SudokuParseError {
@@ -38,4 +66,104 @@ impl From<ParseIntError> for SudokuParseError {
     }
 }
 
-pub type SudokuParseResult<V> = Result<V, SudokuParseError>;
\ No newline at end of file
+pub type SudokuParseResult<V> = Result<V, SudokuParseError>;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SudokuBuildError{
+    NoRows,
+    WrongRowLength { row: usize, expected: usize, found: usize },
+    WrongNumberOfRows { expected: usize, found: usize },
+    CannotInferDimensions(usize),
+    InvalidDimensions,
+    InvalidNumber { column: usize, row: usize }
+}
+
+impl Display for SudokuBuildError{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SudokuBuildError::NoRows => write!(f, "no rows"),
+            SudokuBuildError::WrongRowLength { row, expected, found } =>
+                write!(f, "row {} has {} cells, expected {}", row, found, expected),
+            SudokuBuildError::WrongNumberOfRows { expected, found } =>
+                write!(f, "{} rows, expected {}", found, expected),
+            SudokuBuildError::CannotInferDimensions(size) =>
+                write!(f, "cannot infer block dimensions for size {}", size),
+            SudokuBuildError::InvalidDimensions => write!(f, "invalid dimensions"),
+            SudokuBuildError::InvalidNumber { column, row } =>
+                write!(f, "invalid number in column {}, row {}", column, row)
+        }
+    }
+}
+
+pub type SudokuBuildResult<V> = Result<V, SudokuBuildError>;
+
+#[derive(Debug)]
+pub enum ArchiveError{
+    Io(io::Error),
+    Parse { line: usize, error: SudokuParseError }
+}
+
+impl From<io::Error> for ArchiveError {
+    fn from(error: io::Error) -> Self {
+        ArchiveError::Io(error)
+    }
+}
+
+impl Display for ArchiveError{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::Io(error) => write!(f, "{}", error),
+            ArchiveError::Parse { line, error } =>
+                write!(f, "malformed puzzle in line {}: {:?}", line, error)
+        }
+    }
+}
+
+pub type ArchiveResult<V> = Result<V, ArchiveError>;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RulesError{
+    Syntax(String),
+    UnknownRule(String),
+    UnsupportedRule(String),
+    InvalidParameters { rule: String, message: String }
+}
+
+impl Display for RulesError{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesError::Syntax(message) => write!(f, "syntax error: {}", message),
+            RulesError::UnknownRule(rule) => write!(f, "unknown rule '{}'", rule),
+            RulesError::UnsupportedRule(rule) => write!(f, "rule '{}' is not supported", rule),
+            RulesError::InvalidParameters { rule, message } =>
+                write!(f, "invalid parameters for rule '{}': {}", rule, message)
+        }
+    }
+}
+
+pub type RulesResult<V> = Result<V, RulesError>;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ReplayError{
+    InvalidPuzzle(SudokuError),
+    Tampered,
+    OutOfOrder { event: usize },
+    InvalidMove { event: usize, error: SudokuError },
+    NoEffect { event: usize }
+}
+
+impl Display for ReplayError{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::InvalidPuzzle(error) => write!(f, "invalid puzzle: {}", error),
+            ReplayError::Tampered => write!(f, "the replay does not match its digest"),
+            ReplayError::OutOfOrder { event } =>
+                write!(f, "event {} happened before its predecessor", event),
+            ReplayError::InvalidMove { event, error } =>
+                write!(f, "event {} is invalid: {}", event, error),
+            ReplayError::NoEffect { event } => write!(f, "event {} has no effect", event)
+        }
+    }
+}
+
+pub type ReplayResult<V> = Result<V, ReplayError>;
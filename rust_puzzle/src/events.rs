@@ -0,0 +1,122 @@
+use crate::SudokuGrid;
+
+use serde::{Deserialize, Serialize};
+
+// Milestones reached by entering a number. Rows, columns and blocks are
+// complete once all their cells are filled, a digit once it has been entered
+// `size` times, whether or not the entries are correct. Blocks are numbered
+// in reading order.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum CompletionEvent {
+    DigitCompleted(usize),
+    RowCompleted(usize),
+    ColumnCompleted(usize),
+    BlockCompleted(usize),
+    GridCompleted,
+}
+
+fn is_filled(grid: &SudokuGrid, mut cells: impl Iterator<Item = (usize, usize)>) -> bool {
+    cells.all(|(column, row)| grid.get_cell(column, row).unwrap().is_some())
+}
+
+// Returns the events caused by the cell at (column, row), whose content was
+// `previous` before the last change. Only the houses containing the cell are
+// inspected, so this is cheap enough to call after every move.
+pub fn completion_events(
+    grid: &SudokuGrid,
+    column: usize,
+    row: usize,
+    previous: Option<usize>,
+) -> Vec<CompletionEvent> {
+    let mut events = Vec::new();
+    let number = match grid.get_cell(column, row) {
+        Ok(Some(number)) if previous != Some(number) => number,
+        _ => return events,
+    };
+    let size = grid.size();
+
+    if grid
+        .cells()
+        .iter()
+        .filter(|&&cell| cell == Some(number))
+        .count()
+        == size
+    {
+        events.push(CompletionEvent::DigitCompleted(number));
+    }
+
+    // Filling a cell which already held a number cannot complete a house.
+    if previous.is_some() {
+        return events;
+    }
+
+    if is_filled(grid, (0..size).map(|c| (c, row))) {
+        events.push(CompletionEvent::RowCompleted(row));
+    }
+
+    if is_filled(grid, (0..size).map(|r| (column, r))) {
+        events.push(CompletionEvent::ColumnCompleted(column));
+    }
+
+    let (block_width, block_height) = (grid.block_width(), grid.block_height());
+    let (left, top) = (
+        column / block_width * block_width,
+        row / block_height * block_height,
+    );
+
+    let block_cells =
+        (top..top + block_height).flat_map(|r| (left..left + block_width).map(move |c| (c, r)));
+
+    if is_filled(grid, block_cells) {
+        let block = row / block_height * (size / block_width) + column / block_width;
+        events.push(CompletionEvent::BlockCompleted(block));
+    }
+
+    if grid.is_full() {
+        events.push(CompletionEvent::GridCompleted);
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn events_of_the_last_cell() {
+        let mut grid = SudokuGrid::parse("2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,1").unwrap();
+        grid.clear_cell(3, 2).unwrap();
+
+        assert!(completion_events(&grid, 3, 2, None).is_empty());
+
+        grid.set_cell(3, 2, 3).unwrap();
+
+        assert_eq!(
+            vec![
+                CompletionEvent::DigitCompleted(3),
+                CompletionEvent::RowCompleted(2),
+                CompletionEvent::ColumnCompleted(3),
+                CompletionEvent::BlockCompleted(3),
+                CompletionEvent::GridCompleted,
+            ],
+            completion_events(&grid, 3, 2, None)
+        );
+    }
+
+    #[test]
+    fn overwriting_only_completes_digits() {
+        let mut grid = SudokuGrid::parse("2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,1").unwrap();
+        grid.set_cell(3, 2, 4).unwrap();
+
+        assert!(completion_events(&grid, 3, 2, Some(3)).is_empty());
+
+        grid.set_cell(3, 2, 3).unwrap();
+
+        assert_eq!(
+            vec![CompletionEvent::DigitCompleted(3)],
+            completion_events(&grid, 3, 2, Some(4))
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use crate::constraint::Constraint;
+use crate::{Sudoku, SudokuGrid};
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+// A number in the cell with the given index.
+pub(crate) type Placement = (usize, usize);
+
+// The placements a constraint rules out on its own and the pairs of
+// placements it rules out together, found by probing it on an empty grid and
+// on grids with a single number. Pairs are listed once, with the lower cell
+// index first. This captures every rule which only relates two cells at a
+// time, such as rows, blocks or knight moves, but not rules over more cells,
+// e.g. cage sums.
+pub(crate) struct Exclusions {
+    pub(crate) placements: Vec<Placement>,
+    pub(crate) pairs: Vec<(Placement, Placement)>,
+}
+
+pub(crate) fn exclusions<C>(sudoku: &Sudoku<C>) -> Exclusions
+where
+    C: Constraint + Clone,
+{
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let cells = size * size;
+    let constraint = sudoku.constraint();
+    let empty = SudokuGrid::new(grid.block_width(), grid.block_height()).unwrap();
+    let mut exclusions = Exclusions {
+        placements: Vec::new(),
+        pairs: Vec::new(),
+    };
+
+    for index in 0..cells {
+        let (column, row) = (index % size, index / size);
+
+        for number in 1..=size {
+            if !constraint.check_number(&empty, column, row, number) {
+                exclusions.placements.push((index, number));
+                continue;
+            }
+
+            let mut probe = empty.clone();
+            probe.set_cell(column, row, number).unwrap();
+
+            for other in index + 1..cells {
+                for other_number in 1..=size {
+                    if !constraint.check_number(&probe, other % size, other / size, other_number) {
+                        exclusions
+                            .pairs
+                            .push(((index, number), (other, other_number)));
+                    }
+                }
+            }
+        }
+    }
+
+    exclusions
+}
+
+fn groups<C>(sudoku: &Sudoku<C>) -> Vec<Vec<usize>>
+where
+    C: Constraint + Clone,
+{
+    let size = sudoku.grid().size();
+    let mut groups = Vec::new();
+    sudoku
+        .constraint()
+        .for_each_group(sudoku.grid(), &mut |group| {
+            groups.push(group.iter().map(|&(c, r)| r * size + c).collect())
+        });
+    groups
+}
+
+// Pairs of equal numbers in cells which share a group are already covered by
+// the group constraints, so they are left out of the exported models.
+fn group_mates(groups: &[Vec<usize>]) -> HashSet<(usize, usize)> {
+    let mut mates = HashSet::new();
+
+    for group in groups {
+        for &a in group {
+            for &b in group {
+                mates.insert((a, b));
+            }
+        }
+    }
+
+    mates
+}
+
+fn cell_name(size: usize, index: usize) -> String {
+    format!("r{}c{}", index / size + 1, index % size + 1)
+}
+
+// Writes the puzzle as an SMT-LIB2 script in the QF_LIA logic, with one
+// integer per cell, named r1c1 for the top left cell. Groups become
+// `distinct` assertions, other rules which relate two cells are asserted
+// pair by pair. Rules over more cells are not exported.
+pub fn to_smt_lib<C>(sudoku: &Sudoku<C>) -> String
+where
+    C: Constraint + Clone,
+{
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let groups = groups(sudoku);
+    let mates = group_mates(&groups);
+    let exclusions = exclusions(sudoku);
+    let mut smt = format!("; {}\n(set-logic QF_LIA)\n", grid.to_parseable_string());
+
+    for index in 0..size * size {
+        let name = cell_name(size, index);
+        writeln!(smt, "(declare-const {} Int)", name).unwrap();
+        writeln!(smt, "(assert (and (<= 1 {0}) (<= {0} {1})))", name, size).unwrap();
+
+        if let Some(number) = grid.cells()[index] {
+            writeln!(smt, "(assert (= {} {}))", name, number).unwrap();
+        }
+    }
+
+    for group in groups.iter() {
+        let names: Vec<String> = group.iter().map(|&i| cell_name(size, i)).collect();
+        writeln!(smt, "(assert (distinct {}))", names.join(" ")).unwrap();
+    }
+
+    for &(index, number) in exclusions.placements.iter() {
+        writeln!(
+            smt,
+            "(assert (not (= {} {})))",
+            cell_name(size, index),
+            number
+        )
+        .unwrap();
+    }
+
+    for &((a, n), (b, m)) in exclusions.pairs.iter() {
+        if n == m && mates.contains(&(a, b)) {
+            continue;
+        }
+
+        writeln!(
+            smt,
+            "(assert (not (and (= {} {}) (= {} {}))))",
+            cell_name(size, a),
+            n,
+            cell_name(size, b),
+            m
+        )
+        .unwrap();
+    }
+
+    smt.push_str("(check-sat)\n(get-model)\n");
+    smt
+}
+
+fn lp_variable(size: usize, index: usize, number: usize) -> String {
+    format!("x_{}_{}", cell_name(size, index), number)
+}
+
+// Writes the puzzle as a feasibility problem in the CPLEX LP format, with a
+// binary variable x_r1c1_5 for every number in every cell. Each cell holds
+// exactly one number, each group holds every number at most once and other
+// rules which relate two cells are excluded pair by pair. Rules over more
+// cells are not exported.
+pub fn to_lp<C>(sudoku: &Sudoku<C>) -> String
+where
+    C: Constraint + Clone,
+{
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let cells = size * size;
+    let groups = groups(sudoku);
+    let mates = group_mates(&groups);
+    let exclusions = exclusions(sudoku);
+    let mut lp = format!(
+        "\\ {}\nMinimize\n obj:\nSubject To\n",
+        grid.to_parseable_string()
+    );
+
+    for index in 0..cells {
+        let terms: Vec<String> = (1..=size).map(|n| lp_variable(size, index, n)).collect();
+        writeln!(
+            lp,
+            " cell_{}: {} = 1",
+            cell_name(size, index),
+            terms.join(" + ")
+        )
+        .unwrap();
+
+        if let Some(number) = grid.cells()[index] {
+            writeln!(
+                lp,
+                " given_{}: {} = 1",
+                cell_name(size, index),
+                lp_variable(size, index, number)
+            )
+            .unwrap();
+        }
+    }
+
+    for (g, group) in groups.iter().enumerate() {
+        for number in 1..=size {
+            let terms: Vec<String> = group
+                .iter()
+                .map(|&i| lp_variable(size, i, number))
+                .collect();
+            writeln!(lp, " group{}_{}: {} <= 1", g, number, terms.join(" + ")).unwrap();
+        }
+    }
+
+    for &(index, number) in exclusions.placements.iter() {
+        let variable = lp_variable(size, index, number);
+        writeln!(lp, " ruled_out_{0}: {0} = 0", variable).unwrap();
+    }
+
+    for (k, &((a, n), (b, m))) in exclusions.pairs.iter().enumerate() {
+        if n == m && mates.contains(&(a, b)) {
+            continue;
+        }
+
+        writeln!(
+            lp,
+            " pair{}: {} + {} <= 1",
+            k,
+            lp_variable(size, a, n),
+            lp_variable(size, b, m)
+        )
+        .unwrap();
+    }
+
+    lp.push_str("Binary\n");
+
+    for index in 0..cells {
+        for number in 1..=size {
+            writeln!(lp, " {}", lp_variable(size, index, number)).unwrap();
+        }
+    }
+
+    lp.push_str("End\n");
+    lp
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{CompositeConstraint, DefaultConstraint, KnightsMoveConstraint};
+
+    #[test]
+    fn smt_lib_contains_groups_and_givens() {
+        let sudoku = Sudoku::parse("2x2;1,,,,,,,,,,,,,,,4", DefaultConstraint).unwrap();
+        let smt = to_smt_lib(&sudoku);
+
+        assert!(smt.contains("(declare-const r4c4 Int)"));
+        assert!(smt.contains("(assert (= r1c1 1))"));
+        assert!(smt.contains("(assert (= r4c4 4))"));
+        assert!(smt.contains("(assert (distinct r1c1 r1c2 r1c3 r1c4))"));
+        assert!(!smt.contains("(assert (not"));
+        assert!(smt.ends_with("(check-sat)\n(get-model)\n"));
+    }
+
+    #[test]
+    fn pairwise_rules_are_exported() {
+        let constraint = CompositeConstraint::new(DefaultConstraint, KnightsMoveConstraint);
+        let sudoku = Sudoku::parse("2x2;,,,,,,,,,,,,,,,", constraint).unwrap();
+        let smt = to_smt_lib(&sudoku);
+        let lp = to_lp(&sudoku);
+
+        assert!(smt.contains("(assert (not (and (= r1c1 3) (= r2c3 3))))"));
+        assert!(!smt.contains("(= r1c1 3) (= r2c3 2)"));
+        assert!(lp.contains(": x_r1c1_3 + x_r2c3_3 <= 1\n"));
+        assert!(lp.contains(" cell_r1c1: x_r1c1_1 + x_r1c1_2 + x_r1c1_3 + x_r1c1_4 = 1\n"));
+        assert!(lp.contains("Binary\n x_r1c1_1\n"));
+        assert!(lp.ends_with(" x_r4c4_4\nEnd\n"));
+    }
+}
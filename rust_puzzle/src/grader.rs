@@ -0,0 +1,596 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::constraint::{Constraint, Group};
+use crate::error::{SudokuError, SudokuResult};
+use crate::Sudoku;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct SearchStatistics {
+    nodes: usize,
+    decisions: usize,
+    branches: usize,
+    max_depth: usize,
+    solutions: usize,
+}
+
+impl SearchStatistics {
+    // Number of digits entered during the search.
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    // Number of cells at which the search had more than one option.
+    pub fn decisions(&self) -> usize {
+        self.decisions
+    }
+
+    pub fn branches(&self) -> usize {
+        self.branches
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    // Number of solutions found, the search stops after the second one.
+    pub fn solutions(&self) -> usize {
+        self.solutions
+    }
+
+    pub fn average_branching_factor(&self) -> f64 {
+        if self.decisions == 0 {
+            1.0
+        } else {
+            self.branches as f64 / self.decisions as f64
+        }
+    }
+}
+
+// Score thresholds from which on a puzzle is rated Medium, Hard and Expert.
+// Deserialization checks the thresholds like `new`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "SearchCalibrationData")]
+pub struct SearchCalibration {
+    medium: f64,
+    hard: f64,
+    expert: f64,
+}
+
+#[derive(Deserialize)]
+struct SearchCalibrationData {
+    medium: f64,
+    hard: f64,
+    expert: f64,
+}
+
+impl TryFrom<SearchCalibrationData> for SearchCalibration {
+    type Error = SudokuError;
+
+    fn try_from(data: SearchCalibrationData) -> SudokuResult<SearchCalibration> {
+        SearchCalibration::new(data.medium, data.hard, data.expert)
+    }
+}
+
+impl SearchCalibration {
+    // Fails with InvalidCalibration unless medium <= hard <= expert.
+    pub fn new(medium: f64, hard: f64, expert: f64) -> SudokuResult<SearchCalibration> {
+        if medium.is_nan() || !(medium <= hard && hard <= expert) {
+            return Err(SudokuError::InvalidCalibration);
+        }
+
+        Ok(SearchCalibration {
+            medium,
+            hard,
+            expert,
+        })
+    }
+
+    pub fn medium(&self) -> f64 {
+        self.medium
+    }
+
+    pub fn hard(&self) -> f64 {
+        self.hard
+    }
+
+    pub fn expert(&self) -> f64 {
+        self.expert
+    }
+
+    pub fn classify(&self, score: f64) -> Difficulty {
+        if score >= self.expert {
+            Difficulty::Expert
+        } else if score >= self.hard {
+            Difficulty::Hard
+        } else if score >= self.medium {
+            Difficulty::Medium
+        } else {
+            Difficulty::Easy
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SearchEstimate {
+    statistics: SearchStatistics,
+    score: f64,
+    difficulty: Difficulty,
+}
+
+impl SearchEstimate {
+    pub fn statistics(&self) -> &SearchStatistics {
+        &self.statistics
+    }
+
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.statistics.solutions == 1
+    }
+}
+
+// The result of grading with a time limit. A timed out search reports the
+// statistics gathered until it was stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum GradeOutcome {
+    Graded(SearchEstimate),
+    TimedOut { partial: SearchStatistics },
+}
+
+// Counts of the outcomes of a batch, where failed puzzles are the impossible
+// ones.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BatchSummary {
+    difficulties: BTreeMap<Difficulty, usize>,
+    ambiguous: usize,
+    timed_out: usize,
+    failed: usize,
+}
+
+impl BatchSummary {
+    pub fn new(outcomes: &[SudokuResult<GradeOutcome>]) -> BatchSummary {
+        let mut summary = BatchSummary::default();
+
+        for outcome in outcomes {
+            match outcome {
+                Ok(GradeOutcome::Graded(estimate)) if estimate.is_unique() => {
+                    *summary
+                        .difficulties
+                        .entry(estimate.difficulty())
+                        .or_insert(0) += 1
+                }
+                Ok(GradeOutcome::Graded(_)) => summary.ambiguous += 1,
+                Ok(GradeOutcome::TimedOut { .. }) => summary.timed_out += 1,
+                Err(_) => summary.failed += 1,
+            }
+        }
+
+        summary
+    }
+
+    // Number of unique puzzles of the given difficulty.
+    pub fn count(&self, difficulty: Difficulty) -> usize {
+        self.difficulties.get(&difficulty).copied().unwrap_or(0)
+    }
+
+    pub fn ambiguous(&self) -> usize {
+        self.ambiguous
+    }
+
+    pub fn timed_out(&self) -> usize {
+        self.timed_out
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+}
+
+pub struct Grader {
+    calibrations: HashMap<usize, SearchCalibration>,
+    fallback: SearchCalibration,
+    time_limit: Option<Duration>,
+}
+
+impl Default for Grader {
+    fn default() -> Grader {
+        Grader::new()
+    }
+}
+
+impl Grader {
+    pub fn new() -> Grader {
+        // Roughly splits minimal puzzles generated by the default Reducer into
+        // quartiles.
+        let mut calibrations = HashMap::new();
+        calibrations.insert(4, SearchCalibration::new(0.3, 0.8, 1.3).unwrap());
+        calibrations.insert(6, SearchCalibration::new(0.3, 0.8, 1.3).unwrap());
+        calibrations.insert(9, SearchCalibration::new(1.5, 3.0, 4.5).unwrap());
+
+        Grader {
+            calibrations,
+            fallback: SearchCalibration::new(2.0, 4.0, 6.0).unwrap(),
+            time_limit: None,
+        }
+    }
+
+    pub fn calibration(&self, size: usize) -> SearchCalibration {
+        self.calibrations
+            .get(&size)
+            .copied()
+            .unwrap_or(self.fallback)
+    }
+
+    pub fn set_calibration(&mut self, size: usize, calibration: SearchCalibration) {
+        self.calibrations.insert(size, calibration);
+    }
+
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+
+    // Limits the time grade spends on a single puzzle. estimate_by_search is
+    // not affected.
+    pub fn set_time_limit(&mut self, time_limit: Option<Duration>) {
+        self.time_limit = time_limit;
+    }
+
+    // Rates the puzzle by how much a plain backtracking search (always
+    // branching on the cell with the fewest options) has to guess. This works
+    // for any constraint, since it only needs check_number. The score is the
+    // base-2 logarithm of the number of entered digits per empty cell, so
+    // puzzles which never require a guess score 0.
+    pub fn estimate_by_search<C>(&self, sudoku: &Sudoku<C>) -> SudokuResult<SearchEstimate>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        match self.estimate_until(sudoku, None)? {
+            GradeOutcome::Graded(estimate) => Ok(estimate),
+            GradeOutcome::TimedOut { .. } => unreachable!(),
+        }
+    }
+
+    // Like estimate_by_search, but gives up once the time limit is exceeded.
+    pub fn grade<C>(&self, sudoku: &Sudoku<C>) -> SudokuResult<GradeOutcome>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        self.estimate_until(sudoku, deadline)
+    }
+
+    // The groups of the constraint which only repeat the distinctness demanded
+    // by another group, e.g. a cage within a block. They do not make the
+    // puzzle any harder and point to a redundant constraint.
+    pub fn redundant_groups<C>(&self, sudoku: &Sudoku<C>) -> Vec<Group>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let report = sudoku.overlap_report();
+
+        report
+            .redundant_groups()
+            .map(|group| report.groups()[group].clone())
+            .collect()
+    }
+
+    // Grades the puzzles on the given number of worker threads and returns
+    // the outcomes in the order of the puzzles.
+    pub fn grade_batch<C>(
+        &self,
+        sudokus: &[Sudoku<C>],
+        workers: usize,
+    ) -> Vec<SudokuResult<GradeOutcome>>
+    where
+        C: Constraint + Clone + Send + Sync + 'static,
+    {
+        let next = AtomicUsize::new(0);
+        let outcomes = Mutex::new(sudokus.iter().map(|_| None).collect::<Vec<_>>());
+
+        thread::scope(|scope| {
+            for _ in 0..workers.clamp(1, sudokus.len().max(1)) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+
+                    if index >= sudokus.len() {
+                        return;
+                    }
+
+                    let outcome = self.grade(&sudokus[index]);
+                    outcomes.lock().unwrap()[index] = Some(outcome);
+                });
+            }
+        });
+
+        outcomes
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(Option::unwrap)
+            .collect()
+    }
+
+    fn estimate_until<C>(
+        &self,
+        sudoku: &Sudoku<C>,
+        deadline: Option<Instant>,
+    ) -> SudokuResult<GradeOutcome>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        if !sudoku.is_valid() {
+            return Err(SudokuError::UnsatisfiableConstraint);
+        }
+
+        let mut clone = sudoku.clone();
+        let mut statistics = SearchStatistics::default();
+
+        if !search(&mut clone, 0, &mut statistics, deadline) {
+            return Ok(GradeOutcome::TimedOut {
+                partial: statistics,
+            });
+        }
+
+        if statistics.solutions == 0 {
+            return Err(SudokuError::UnsatisfiableConstraint);
+        }
+
+        let grid = sudoku.grid();
+        let empty_cells = grid.size() * grid.size() - grid.count_clues();
+        let score = if empty_cells == 0 {
+            0.0
+        } else {
+            (statistics.nodes as f64 / empty_cells as f64)
+                .log2()
+                .max(0.0)
+        };
+
+        Ok(GradeOutcome::Graded(SearchEstimate {
+            statistics,
+            score,
+            difficulty: self.calibration(grid.size()).classify(score),
+        }))
+    }
+}
+
+fn options<C>(sudoku: &Sudoku<C>, column: usize, row: usize) -> Vec<usize>
+where
+    C: Constraint + Clone + 'static,
+{
+    let size = sudoku.grid().size();
+    (1..=size)
+        .filter(|&number| sudoku.is_valid_number(column, row, number).unwrap())
+        .collect()
+}
+
+fn most_constrained_cell<C>(sudoku: &Sudoku<C>) -> Option<(usize, usize, Vec<usize>)>
+where
+    C: Constraint + Clone + 'static,
+{
+    let mut best: Option<(usize, usize, Vec<usize>)> = None;
+
+    for (column, row) in sudoku.grid().empty_cells() {
+        let cell_options = options(sudoku, column, row);
+
+        if best
+            .as_ref()
+            .is_none_or(|(_, _, o)| cell_options.len() < o.len())
+        {
+            let dead_end = cell_options.len() <= 1;
+            best = Some((column, row, cell_options));
+
+            if dead_end {
+                return best;
+            }
+        }
+    }
+
+    best
+}
+
+// How many digits are entered between two looks at the clock.
+const DEADLINE_CHECK_INTERVAL: usize = 256;
+
+// Returns false if the search was stopped at the deadline.
+fn search<C>(
+    sudoku: &mut Sudoku<C>,
+    depth: usize,
+    statistics: &mut SearchStatistics,
+    deadline: Option<Instant>,
+) -> bool
+where
+    C: Constraint + Clone + 'static,
+{
+    statistics.max_depth = statistics.max_depth.max(depth);
+
+    if statistics.nodes.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+        && deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    {
+        return false;
+    }
+
+    let (column, row, options) = match most_constrained_cell(sudoku) {
+        Some(cell) => cell,
+        None => {
+            statistics.solutions += 1;
+            return true;
+        }
+    };
+
+    if options.len() > 1 {
+        statistics.decisions += 1;
+        statistics.branches += options.len();
+    }
+
+    for number in options {
+        statistics.nodes += 1;
+        sudoku.grid_mut().set_cell(column, row, number).unwrap();
+        let finished = search(sudoku, depth + 1, statistics, deadline);
+        sudoku.grid_mut().clear_cell(column, row).unwrap();
+
+        if !finished {
+            return false;
+        }
+
+        if statistics.solutions > 1 {
+            break;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{CompositeConstraint, DefaultConstraint, RowConstraint};
+
+    #[test]
+    fn singles_only_puzzle_is_easy() {
+        let sudoku = Sudoku::parse(
+            "2x2;\
+            1, , ,4,\
+             ,4,1, ,\
+            2, , ,3,\
+             ,3,2, ",
+            DefaultConstraint,
+        )
+        .unwrap();
+        let estimate = Grader::new().estimate_by_search(&sudoku).unwrap();
+
+        assert_eq!(0, estimate.statistics().decisions());
+        assert_eq!(8, estimate.statistics().nodes());
+        assert_eq!(0.0, estimate.score());
+        assert_eq!(Difficulty::Easy, estimate.difficulty());
+        assert!(estimate.is_unique());
+    }
+
+    #[test]
+    fn empty_grid_is_ambiguous() {
+        let sudoku = Sudoku::new_empty(2, 2, DefaultConstraint).unwrap();
+        let estimate = Grader::new().estimate_by_search(&sudoku).unwrap();
+
+        assert_eq!(2, estimate.statistics().solutions());
+        assert!(!estimate.is_unique());
+        assert!(estimate.statistics().average_branching_factor() > 1.0);
+    }
+
+    #[test]
+    fn impossible_puzzle_is_an_error() {
+        let sudoku = Sudoku::parse("2x2;1,2,3,,,,,4,,,,,,,,", DefaultConstraint).unwrap();
+
+        assert_eq!(
+            Err(SudokuError::UnsatisfiableConstraint),
+            Grader::new().estimate_by_search(&sudoku)
+        );
+    }
+
+    #[test]
+    fn grading_stops_at_the_time_limit() {
+        let sudoku = Sudoku::new_empty(3, 3, DefaultConstraint).unwrap();
+        let mut grader = Grader::new();
+
+        assert!(matches!(grader.grade(&sudoku), Ok(GradeOutcome::Graded(_))));
+
+        grader.set_time_limit(Some(Duration::ZERO));
+
+        assert_eq!(
+            Ok(GradeOutcome::TimedOut {
+                partial: SearchStatistics::default()
+            }),
+            grader.grade(&sudoku)
+        );
+        assert!(grader.estimate_by_search(&sudoku).is_ok());
+    }
+
+    #[test]
+    fn batch_grading_keeps_the_order() {
+        let sudokus = vec![
+            Sudoku::parse("2x2;1,,,4,,4,1,,2,,,3,,3,2,", DefaultConstraint).unwrap(),
+            Sudoku::new_empty(2, 2, DefaultConstraint).unwrap(),
+            Sudoku::parse("2x2;1,2,3,,,,,4,,,,,,,,", DefaultConstraint).unwrap(),
+        ];
+        let grader = Grader::new();
+        let outcomes = grader.grade_batch(&sudokus, 2);
+        let summary = BatchSummary::new(&outcomes);
+
+        assert_eq!(
+            Ok(GradeOutcome::Graded(
+                grader.estimate_by_search(&sudokus[0]).unwrap()
+            )),
+            outcomes[0]
+        );
+        assert_eq!(1, summary.count(Difficulty::Easy));
+        assert_eq!(1, summary.ambiguous());
+        assert_eq!(1, summary.failed());
+        assert_eq!(0, summary.timed_out());
+        assert!(grader.grade_batch::<DefaultConstraint>(&[], 4).is_empty());
+    }
+
+    #[test]
+    fn repeated_rules_are_redundant() {
+        let grader = Grader::new();
+        let classic = Sudoku::new_empty(2, 2, DefaultConstraint).unwrap();
+        let repeated = Sudoku::new_empty(
+            2,
+            2,
+            CompositeConstraint::new(DefaultConstraint, RowConstraint),
+        )
+        .unwrap();
+        let rows: Vec<Group> = (0..4)
+            .map(|row| (0..4).map(|column| (column, row)).collect())
+            .collect();
+
+        assert!(grader.redundant_groups(&classic).is_empty());
+        assert_eq!(rows, grader.redundant_groups(&repeated));
+    }
+
+    #[test]
+    fn calibration_classifies_by_thresholds() {
+        let calibration = SearchCalibration::new(1.0, 2.0, 3.0).unwrap();
+
+        assert_eq!(Difficulty::Easy, calibration.classify(0.5));
+        assert_eq!(Difficulty::Medium, calibration.classify(1.0));
+        assert_eq!(Difficulty::Hard, calibration.classify(2.5));
+        assert_eq!(Difficulty::Expert, calibration.classify(10.0));
+        assert_eq!(
+            Err(SudokuError::InvalidCalibration),
+            SearchCalibration::new(2.0, 1.0, 3.0)
+        );
+        assert!(SearchCalibration::new(f64::NAN, 1.0, 3.0).is_err());
+    }
+
+    #[test]
+    fn deserialized_calibration_is_checked() {
+        let calibration = SearchCalibration::new(1.0, 2.0, 3.0).unwrap();
+        let json = serde_json::to_string(&calibration).unwrap();
+
+        assert_eq!(calibration, serde_json::from_str(&json).unwrap());
+        assert!(serde_json::from_str::<SearchCalibration>(
+            r#"{"medium": 3.0, "hard": 2.0, "expert": 1.0}"#
+        )
+        .is_err());
+    }
+}
@@ -14,11 +14,33 @@
 // #![warn(rustdoc::missing_crate_level_docs)]
 // #![warn(rustdoc::invalid_codeblock_attributes)]
 
+pub mod alphabet; //file
+pub mod annotations; //file
+pub mod archive; //file
+pub mod builder; //file
+pub mod collab; //file
 pub mod constraint; //folder
 pub mod error; //file
+pub mod events; //file
+pub mod export; //file
+pub mod grader; //file
+pub mod miner; //file
+pub mod overlap; //file
+pub mod recognition; //file
+pub mod render; //folder
+pub mod replay; //file
+pub mod seeds; //file
+pub mod session; //file
+pub mod share; //file
 pub mod solver; //folder
+pub mod stats; //file
 pub mod sudoku_generator; //file
+pub mod symmetry; //file
+pub mod transform; //file
+pub mod twin; //file
 pub mod utilities; //file
+pub mod validation; //file
+pub mod view; //file
 
 #[cfg(test)]
 mod tests_bugfix;
@@ -26,22 +48,165 @@ mod tests_bugfix;
 #[cfg(test)]
 mod random_tests;
 
-use constraint::Constraint;
+use alphabet::Alphabet;
+use constraint::{Constraint, Violation};
 use error::{SudokuError, SudokuParseError, SudokuParseResult, SudokuResult};
+use overlap::OverlapReport;
+use utilities::USizeSet;
+use validation::ValidationReport;
 
 use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
 use serde::{Deserialize, Serialize};
 
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashSet;
 use std::fmt::{self, Display, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::iter::Enumerate;
+use std::mem;
+use std::ops::Deref;
+use std::slice::Iter;
 
 // main grid struct
-#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 pub struct SudokuGrid {
     block_width: usize,
     block_height: usize,
     #[serde(skip_serializing)]
     size: usize,
     cells: Vec<Option<usize>>,
+    // Zobrist hash, number of filled cells and how often every digit occurs
+    // in every house, updated by all checked modifications. Raw access
+    // through cells_mut_unchecked invalidates them. The count of a digit in
+    // house h is at index `h * size + digit - 1`, see house_index.
+    #[serde(skip_serializing)]
+    zobrist: u64,
+    #[serde(skip_serializing)]
+    filled: usize,
+    #[serde(skip_serializing)]
+    digit_counts: Vec<usize>,
+    #[serde(skip_serializing)]
+    cache_valid: bool,
+}
+
+// A set of cells in which the grid keeps track of how often every digit
+// occurs. Blocks are numbered in row-major order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum House {
+    Row(usize),
+    Column(usize),
+    Block(usize),
+    Grid,
+}
+
+// Pseudo-random key of a digit in the cell with the given index, using the
+// SplitMix64 finalizer. The Zobrist hash of a grid is the XOR of the keys of
+// all its digits.
+pub(crate) fn zobrist_key(index: usize, number: usize) -> u64 {
+    let mut z = ((index as u64) << 32 | number as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn compute_zobrist(cells: &[Option<usize>]) -> u64 {
+    cells
+        .iter()
+        .enumerate()
+        .filter_map(|(index, cell)| cell.map(|number| zobrist_key(index, number)))
+        .fold(0, |hash, key| hash ^ key)
+}
+
+impl PartialEq for SudokuGrid {
+    fn eq(&self, other: &SudokuGrid) -> bool {
+        self.block_width == other.block_width
+            && self.block_height == other.block_height
+            && self.cells == other.cells
+    }
+}
+
+impl Eq for SudokuGrid {}
+
+// Lexicographic over the dimensions, then the cells in row-major order, where
+// empty cells come before all digits.
+impl Ord for SudokuGrid {
+    fn cmp(&self, other: &SudokuGrid) -> Ordering {
+        self.block_width
+            .cmp(&other.block_width)
+            .then(self.block_height.cmp(&other.block_height))
+            .then_with(|| self.cells.cmp(&other.cells))
+    }
+}
+
+impl PartialOrd for SudokuGrid {
+    fn partial_cmp(&self, other: &SudokuGrid) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for SudokuGrid {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.block_width.hash(state);
+        self.block_height.hash(state);
+        self.zobrist_hash().hash(state);
+    }
+}
+
+// (block width, block height, cells in row-major order)
+impl TryFrom<(usize, usize, Vec<Option<usize>>)> for SudokuGrid {
+    type Error = SudokuError;
+
+    fn try_from(
+        (block_width, block_height, cells): (usize, usize, Vec<Option<usize>>),
+    ) -> SudokuResult<SudokuGrid> {
+        let mut grid = SudokuGrid::new(block_width, block_height)?;
+        let size = grid.size();
+
+        if cells.len() != size * size {
+            return Err(SudokuError::InvalidDimensions);
+        }
+
+        if cells
+            .iter()
+            .flatten()
+            .any(|&number| number == 0 || number > size)
+        {
+            return Err(SudokuError::InvalidNumber);
+        }
+
+        grid.cells = cells;
+        grid.refresh_zobrist();
+        Ok(grid)
+    }
+}
+
+// Classic 9x9 grid given as rows, where 0 marks an empty cell.
+impl TryFrom<[[u8; 9]; 9]> for SudokuGrid {
+    type Error = SudokuError;
+
+    fn try_from(rows: [[u8; 9]; 9]) -> SudokuResult<SudokuGrid> {
+        let cells = rows
+            .iter()
+            .flat_map(|row| row.iter())
+            .map(|&number| {
+                if number == 0 {
+                    None
+                } else {
+                    Some(number as usize)
+                }
+            })
+            .collect();
+        SudokuGrid::try_from((3, 3, cells))
+    }
+}
+
+impl From<&SudokuGrid> for Vec<Vec<Option<usize>>> {
+    fn from(grid: &SudokuGrid) -> Vec<Vec<Option<usize>>> {
+        grid.cells
+            .chunks(grid.size)
+            .map(|row| row.to_vec())
+            .collect()
+    }
 }
 
 // create grid or throw error
@@ -50,18 +215,7 @@ fn build_sudoku_grid<E: de::Error>(
     block_height: usize,
     cells: Vec<Option<usize>>,
 ) -> Result<SudokuGrid, E> {
-    let mut grid = match SudokuGrid::new(block_width, block_height) {
-        Ok(grid) => grid,
-        Err(e) => return Err(E::custom(e)),
-    };
-    let size = grid.size();
-
-    if cells.len() != size * size {
-        return Err(E::custom("invalid number of cells"));
-    }
-
-    grid.cells = cells;
-    Ok(grid)
+    SudokuGrid::try_from((block_width, block_height, cells)).map_err(E::custom)
 }
 
 fn read_field<'de, M, V>(
@@ -270,14 +424,14 @@ fn to_string(cell: &Option<usize>) -> String {
 }
 
 pub(crate) fn index(column: usize, row: usize, size: usize) -> SudokuResult<usize> {
-    if column < size || row < size {
+    if column < size && row < size {
         Ok(row * size + column)
     } else {
         Err(SudokuError::OutOfBounds)
     }
 }
 
-fn parse_dimensions(code: &str) -> Result<(usize, usize), SudokuParseError> {
+pub(crate) fn parse_dimensions(code: &str) -> Result<(usize, usize), SudokuParseError> {
     let parts: Vec<&str> = code.split('x').collect();
 
     if parts.len() != 2 {
@@ -287,7 +441,157 @@ fn parse_dimensions(code: &str) -> Result<(usize, usize), SudokuParseError> {
     Ok((parts[0].parse()?, parts[1].parse()?))
 }
 
+// Decides between several block dimensions that fit a grid size, e.g. 3x2
+// and 2x3 for 6x6, when a format only conveys the size.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DimensionPolicy {
+    // Blocks as close to square as possible, the wider one on ties.
+    #[default]
+    PreferSquare,
+    // The widest blocks.
+    PreferWide,
+}
+
+impl DimensionPolicy {
+    pub fn choose(self, candidates: &[(usize, usize)]) -> Option<(usize, usize)> {
+        let candidates = candidates.iter().cloned();
+
+        match self {
+            DimensionPolicy::PreferSquare => {
+                candidates.min_by_key(|&(block_width, block_height)| {
+                    (block_width.abs_diff(block_height), Reverse(block_width))
+                })
+            }
+            DimensionPolicy::PreferWide => candidates.max_by_key(|&(block_width, _)| block_width),
+        }
+    }
+
+    pub fn block_dims(self, size: usize) -> Option<(usize, usize)> {
+        self.choose(&SudokuGrid::infer_block_dims(size))
+    }
+}
+
+fn is_empty_token(token: &str) -> bool {
+    token == "." || token == "0" || token == "_"
+}
+
+fn parse_cell_token(token: &str, size: usize) -> SudokuParseResult<Option<usize>> {
+    if is_empty_token(token) {
+        return Ok(None);
+    }
+
+    let number = token.parse::<usize>()?;
+
+    if number == 0 || number > size {
+        return Err(SudokuParseError::InvalidNumber);
+    }
+
+    Ok(Some(number))
+}
+
+fn is_vertical(c: char) -> bool {
+    matches!(c, '║' | '┃' | '│' | '|')
+}
+
+fn is_thick_vertical(c: char) -> bool {
+    matches!(c, '║' | '┃')
+}
+
+fn is_thick_horizontal(c: char) -> bool {
+    matches!(c, '═' | '━' | '=')
+}
+
+// Lines of a frame consist only of box-drawing or ASCII border characters.
+fn is_separator_line(line: &str) -> bool {
+    line.chars()
+        .all(|c| !c.is_alphanumeric() && !c.is_whitespace() && c != '.' && c != '_')
+}
+
+// Returns the cells of a framed content row and the indices of the cells
+// preceded by a block border. A segment between two borders holds either a
+// single empty cell (only spaces) or whitespace separated tokens.
+fn pretty_row(
+    line: &str,
+    thick_columns_marked: bool,
+) -> SudokuParseResult<(Vec<Option<usize>>, Vec<usize>)> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut thick = false;
+
+    for (i, c) in line.char_indices() {
+        if is_vertical(c) {
+            segments.push((thick, &line[start..i]));
+            thick = !thick_columns_marked || is_thick_vertical(c);
+            start = i + c.len_utf8();
+        }
+    }
+
+    segments.push((thick, &line[start..]));
+
+    if segments
+        .last()
+        .is_some_and(|(_, segment)| segment.is_empty())
+    {
+        segments.pop();
+    }
+
+    if segments
+        .first()
+        .is_some_and(|(_, segment)| segment.is_empty())
+    {
+        segments.remove(0);
+    }
+
+    let mut cells = Vec::new();
+    let mut breaks = Vec::new();
+
+    for (thick, segment) in segments {
+        if thick {
+            breaks.push(cells.len());
+        }
+
+        let tokens: Vec<&str> = segment.split_whitespace().collect();
+
+        if tokens.is_empty() {
+            cells.push(None);
+        }
+
+        for token in tokens {
+            // The size is not known yet, parse_pretty checks the range.
+            cells.push(parse_cell_token(token, usize::MAX)?);
+        }
+    }
+
+    Ok((cells, breaks))
+}
+
+// Splits a line into cell tokens. A single token as long as the grid is
+// high is read one character per cell, as in "53..7....".
+fn lenient_tokens(line: &str, size: usize) -> Vec<String> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+
+    if tokens.len() == 1 && size > 1 && tokens[0].chars().count() == size {
+        tokens[0].chars().map(String::from).collect()
+    } else {
+        tokens.into_iter().map(String::from).collect()
+    }
+}
+
 impl SudokuGrid {
+    // All block dimensions (width, height) for the given size with both sides
+    // greater than 1, widest first. Size 1 only allows 1x1.
+    pub fn infer_block_dims(size: usize) -> Vec<(usize, usize)> {
+        if size == 1 {
+            return vec![(1, 1)];
+        }
+
+        (2..size)
+            .rev()
+            .filter(|width| size.is_multiple_of(*width))
+            .map(|width| (width, size / width))
+            .collect()
+    }
+
     pub fn new(block_width: usize, block_height: usize) -> SudokuResult<SudokuGrid> {
         if block_width == 0 || block_height == 0 {
             return Err(SudokuError::InvalidDimensions);
@@ -301,10 +605,17 @@ impl SudokuGrid {
             block_height,
             size,
             cells,
+            zobrist: 0,
+            filled: 0,
+            digit_counts: vec![0; (3 * size + 1) * size],
+            cache_valid: true,
         })
     }
 
-    pub fn parse(code: &str) -> SudokuParseResult<SudokuGrid> {
+    fn parse_with<F>(code: &str, parse_number: F) -> SudokuParseResult<SudokuGrid>
+    where
+        F: Fn(&str) -> SudokuParseResult<usize>,
+    {
         let parts: Vec<&str> = code.split(';').collect();
 
         if parts.len() != 2 {
@@ -328,7 +639,7 @@ impl SudokuGrid {
                     continue;
                 }
 
-                let number = number_str.parse::<usize>()?;
+                let number = parse_number(number_str)?;
 
                 if number == 0 || number > size {
                     return Err(SudokuParseError::InvalidNumber);
@@ -337,14 +648,201 @@ impl SudokuGrid {
                 grid.cells[i] = Some(number);
             }
 
+            grid.refresh_zobrist();
             Ok(grid)
         } else {
             Err(SudokuParseError::InvalidDimensions)
         }
     }
 
+    pub fn parse(code: &str) -> SudokuParseResult<SudokuGrid> {
+        SudokuGrid::parse_with(code, |number_str| Ok(number_str.parse::<usize>()?))
+    }
+
+    // Parses a code whose cells are single symbols of the default alphabet
+    // (1-9, then A-G), e.g. for 16x16 hex sudokus.
+    pub fn parse_alphanumeric(code: &str) -> SudokuParseResult<SudokuGrid> {
+        SudokuGrid::parse_with_alphabet(code, &Alphabet::default())
+    }
+
+    pub fn parse_with_alphabet(code: &str, alphabet: &Alphabet) -> SudokuParseResult<SudokuGrid> {
+        SudokuGrid::parse_with(code, |number_str| {
+            let mut chars = number_str.chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(symbol), None) => alphabet
+                    .number(symbol)
+                    .ok_or(SudokuParseError::NumberFormatError),
+                _ => Err(SudokuParseError::NumberFormatError),
+            }
+        })
+    }
+
+    // Parses a grid laid out as one line per row, with cells separated by
+    // whitespace and ".", "0" or "_" marking empty cells. The block
+    // dimensions are chosen among those under which no clue repeats within a
+    // block; if several remain, AmbiguousDimensions lists them.
+    pub fn parse_lenient(text: &str) -> SudokuParseResult<SudokuGrid> {
+        SudokuGrid::parse_lenient_impl(text, None)
+    }
+
+    // Like parse_lenient, but resolves ambiguous dimensions with the policy.
+    pub fn parse_lenient_with_policy(
+        text: &str,
+        policy: DimensionPolicy,
+    ) -> SudokuParseResult<SudokuGrid> {
+        SudokuGrid::parse_lenient_impl(text, Some(policy))
+    }
+
+    fn parse_lenient_impl(
+        text: &str,
+        policy: Option<DimensionPolicy>,
+    ) -> SudokuParseResult<SudokuGrid> {
+        let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+        let size = lines.len();
+        let mut cells = Vec::with_capacity(size * size);
+
+        for line in lines {
+            let tokens = lenient_tokens(line, size);
+
+            if tokens.len() != size {
+                return Err(SudokuParseError::WrongNumberOfCells);
+            }
+
+            for token in tokens {
+                cells.push(parse_cell_token(&token, size)?);
+            }
+        }
+
+        let candidates = SudokuGrid::infer_block_dims(size);
+        SudokuGrid::from_inferred_cells(size, cells, candidates, policy)
+    }
+
+    // Chooses among the candidate block dimensions those under which no clue
+    // repeats within a block, resolving ties with the policy if given.
+    fn from_inferred_cells(
+        size: usize,
+        cells: Vec<Option<usize>>,
+        candidates: Vec<(usize, usize)>,
+        policy: Option<DimensionPolicy>,
+    ) -> SudokuParseResult<SudokuGrid> {
+        let plausible: Vec<(usize, usize)> = candidates
+            .into_iter()
+            .filter(|&(block_width, block_height)| {
+                let mut seen = HashSet::new();
+
+                cells.iter().enumerate().all(|(index, cell)| match cell {
+                    Some(number) => {
+                        let (column, row) = (index % size, index / size);
+                        seen.insert((column / block_width, row / block_height, *number))
+                    }
+                    None => true,
+                })
+            })
+            .collect();
+
+        let chosen = match (plausible.as_slice(), policy) {
+            ([], _) => return Err(SudokuParseError::InvalidDimensions),
+            (&[dimensions], _) => dimensions,
+            (_, Some(policy)) => policy.choose(&plausible).unwrap(),
+            (_, None) => return Err(SudokuParseError::AmbiguousDimensions(plausible)),
+        };
+        SudokuGrid::from_parsed_cells(chosen, cells)
+    }
+
+    fn from_parsed_cells(
+        (block_width, block_height): (usize, usize),
+        cells: Vec<Option<usize>>,
+    ) -> SudokuParseResult<SudokuGrid> {
+        let mut grid = SudokuGrid::new(block_width, block_height)
+            .map_err(|_| SudokuParseError::InvalidDimensions)?;
+
+        if cells.len() != grid.cells.len() {
+            return Err(SudokuParseError::WrongNumberOfCells);
+        }
+
+        if cells.iter().flatten().any(|&n| n == 0 || n > grid.size) {
+            return Err(SudokuParseError::InvalidNumber);
+        }
+
+        grid.cells = cells;
+        grid.refresh_zobrist();
+        Ok(grid)
+    }
+
+    // Reads back the output of Display, or a similar frame drawn with ASCII
+    // characters such as "+-------+" and "| 5 3 . | . 7 . |". Block borders
+    // are taken from thick lines ("║", "═" or "=") if there are any, otherwise
+    // from all lines. If the frame has no inner borders, the dimensions are
+    // inferred as in parse_lenient.
+    pub fn parse_pretty(text: &str) -> SudokuParseResult<SudokuGrid> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+        let thick_rows_marked = lines
+            .iter()
+            .any(|l| is_separator_line(l) && l.chars().any(is_thick_horizontal));
+        let thick_columns_marked = text.chars().any(is_thick_vertical);
+        let mut rows: Vec<Vec<Option<usize>>> = Vec::new();
+        let mut row_breaks = Vec::new();
+        let mut column_breaks = None;
+
+        for line in lines {
+            if is_separator_line(line) {
+                if !thick_rows_marked || line.chars().any(is_thick_horizontal) {
+                    row_breaks.push(rows.len());
+                }
+
+                continue;
+            }
+
+            let (cells, breaks) = pretty_row(line, thick_columns_marked)?;
+            column_breaks.get_or_insert(breaks);
+            rows.push(cells);
+        }
+
+        let size = rows.len();
+
+        if rows.iter().any(|row| row.len() != size) {
+            return Err(SudokuParseError::WrongNumberOfCells);
+        }
+
+        if rows
+            .iter()
+            .flatten()
+            .any(|cell| cell.is_some_and(|n| n == 0 || n > size))
+        {
+            return Err(SudokuParseError::InvalidNumber);
+        }
+
+        let first_inner = |breaks: &[usize]| breaks.iter().cloned().find(|&b| b > 0 && b < size);
+        let block_width = first_inner(&column_breaks.unwrap_or_default());
+        let block_height = first_inner(&row_breaks);
+        let cells: Vec<Option<usize>> = rows.into_iter().flatten().collect();
+
+        match (block_width, block_height) {
+            (Some(block_width), Some(block_height)) => {
+                if block_width * block_height != size {
+                    return Err(SudokuParseError::InvalidDimensions);
+                }
+
+                SudokuGrid::from_parsed_cells((block_width, block_height), cells)
+            }
+            (block_width, block_height) => {
+                let candidates = SudokuGrid::infer_block_dims(size)
+                    .into_iter()
+                    .filter(|&(w, _)| block_width.is_none_or(|b| b == w))
+                    .filter(|&(_, h)| block_height.is_none_or(|b| b == h))
+                    .collect();
+                SudokuGrid::from_inferred_cells(size, cells, candidates, None)
+            }
+        }
+    }
+
     pub fn to_parseable_string(&self) -> String {
-        let mut s = format!("{}x{}", self.block_width, self.block_height);
+        let mut s = format!("{}x{};", self.block_width, self.block_height);
         let cells = self
             .cells
             .iter()
@@ -355,6 +853,99 @@ impl SudokuGrid {
         s
     }
 
+    // Draws the grid with plain ASCII characters, e.g. "+-------+" and
+    // "| 5 3 . |", for terminals without box-drawing characters. Unlike
+    // Display, this also works for grids larger than 9x9. The result can be
+    // read back with parse_pretty.
+    pub fn to_ascii_string(&self) -> String {
+        let size = self.size();
+        let width = size.to_string().len();
+        let mut border = String::new();
+
+        for x in 0..size {
+            if x == 0 {
+                border.push('+');
+            } else if x % self.block_width == 0 {
+                border.push_str("-+");
+            }
+
+            border.push_str(&"-".repeat(width + 1));
+        }
+
+        border.push_str("-+\n");
+        let mut result = String::new();
+
+        for y in 0..size {
+            if y % self.block_height == 0 {
+                result.push_str(&border);
+            }
+
+            for x in 0..size {
+                if x == 0 {
+                    result.push('|');
+                } else if x % self.block_width == 0 {
+                    result.push_str(" |");
+                }
+
+                let cell = match self.get_cell(x, y).unwrap() {
+                    Some(number) => number.to_string(),
+                    None => String::from("."),
+                };
+                result.push_str(&format!(" {:>width$}", cell));
+            }
+
+            result.push_str(" |\n");
+        }
+
+        result.push_str(&border);
+        result
+    }
+
+    // Describes the grid row by row in words, e.g. "Row 1: 5, blank, 3", for
+    // screen readers.
+    pub fn describe(&self) -> String {
+        let size = self.size();
+
+        (0..size)
+            .map(|y| {
+                let cells: Vec<String> = (0..size)
+                    .map(|x| match self.get_cell(x, y).unwrap() {
+                        Some(number) => number.to_string(),
+                        None => String::from("blank"),
+                    })
+                    .collect();
+                format!("Row {}: {}\n", y + 1, cells.join(", "))
+            })
+            .collect()
+    }
+
+    pub fn to_alphanumeric_string(&self) -> SudokuResult<String> {
+        self.to_string_with_alphabet(&Alphabet::default())
+    }
+
+    // Fails with InvalidNumber if the alphabet has too few symbols for the
+    // grid size.
+    pub fn to_string_with_alphabet(&self, alphabet: &Alphabet) -> SudokuResult<String> {
+        if alphabet.len() < self.size {
+            return Err(SudokuError::InvalidNumber);
+        }
+
+        let cells = self
+            .cells
+            .iter()
+            .map(|cell| {
+                cell.and_then(|number| alphabet.symbol(number))
+                    .map(String::from)
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+        Ok(format!(
+            "{}x{};{}",
+            self.block_width, self.block_height, cells
+        ))
+    }
+
     pub fn block_width(&self) -> usize {
         self.block_width
     }
@@ -388,16 +979,135 @@ impl SudokuGrid {
             return Err(SudokuError::InvalidNumber);
         }
 
-        self.cells[index] = Some(number);
+        self.set_content(index, Some(number));
+        Ok(())
+    }
+
+    // Sets the cells given as (column, row, number). All placements are
+    // checked before any is applied, so on failure the grid is unchanged and
+    // the error names the index of the first invalid placement.
+    pub fn apply_bulk(&mut self, placements: &[(usize, usize, usize)]) -> SudokuResult<()> {
+        let size = self.size();
+
+        for (i, &(column, row, number)) in placements.iter().enumerate() {
+            let error = if column >= size || row >= size {
+                SudokuError::OutOfBounds
+            } else if number == 0 || number > size {
+                SudokuError::InvalidNumber
+            } else {
+                continue;
+            };
+
+            return Err(SudokuError::InvalidPlacement {
+                index: i,
+                error: Box::new(error),
+            });
+        }
+
+        for &(column, row, number) in placements {
+            self.set_content(row * size + column, Some(number));
+        }
+
         Ok(())
     }
 
     pub fn clear_cell(&mut self, column: usize, row: usize) -> SudokuResult<()> {
         let index = index(column, row, self.size())?;
-        self.cells[index] = None;
+        self.set_content(index, None);
         Ok(())
     }
 
+    fn set_content(&mut self, index: usize, content: Option<usize>) {
+        let old = mem::replace(&mut self.cells[index], content);
+
+        // Once invalidated, the cache is only rebuilt by refresh_zobrist.
+        if !self.cache_valid {
+            return;
+        }
+
+        if let Some(number) = old {
+            self.zobrist ^= zobrist_key(index, number);
+            self.filled -= 1;
+            self.count_digit(index, number, false);
+        }
+
+        if let Some(number) = content {
+            self.zobrist ^= zobrist_key(index, number);
+            self.filled += 1;
+            self.count_digit(index, number, true);
+        }
+    }
+
+    // Adds or removes one occurrence of the number in every house of the
+    // cell. Numbers out of range can only be left over from unchecked edits
+    // and are not counted.
+    fn count_digit(&mut self, index: usize, number: usize, added: bool) {
+        let size = self.size;
+
+        if number == 0 || number > size {
+            return;
+        }
+
+        let (column, row) = (index % size, index / size);
+        // The house indices of the row, column and block and of the grid.
+        let houses = [
+            row,
+            size + column,
+            2 * size + self.block_of(column, row),
+            3 * size,
+        ];
+
+        for house in houses {
+            let count = &mut self.digit_counts[house * size + number - 1];
+
+            if added {
+                *count += 1;
+            } else {
+                *count -= 1;
+            }
+        }
+    }
+
+    fn block_of(&self, column: usize, row: usize) -> usize {
+        row / self.block_height * self.block_height + column / self.block_width
+    }
+
+    fn house_index(&self, house: House) -> usize {
+        match house {
+            House::Row(row) => row,
+            House::Column(column) => self.size + column,
+            House::Block(block) => 2 * self.size + block,
+            House::Grid => 3 * self.size,
+        }
+    }
+
+    fn house_contains(&self, house: House, index: usize) -> bool {
+        let (column, row) = (index % self.size, index / self.size);
+
+        match house {
+            House::Row(r) => row == r,
+            House::Column(c) => column == c,
+            House::Block(b) => self.block_of(column, row) == b,
+            House::Grid => true,
+        }
+    }
+
+    // How often the number occurs in the house, which must be within the
+    // grid. Numbers outside the range of the grid never occur.
+    pub(crate) fn count_in(&self, house: House, number: usize) -> usize {
+        if number == 0 || number > self.size {
+            0
+        } else if self.cache_valid {
+            self.digit_counts[self.house_index(house) * self.size + number - 1]
+        } else {
+            self.cells
+                .iter()
+                .enumerate()
+                .filter(|&(index, &cell)| cell == Some(number) && self.house_contains(house, index))
+                .count()
+        }
+    }
+
     fn verify_dimensions(&self, other: &SudokuGrid) -> SudokuResult<()> {
         if self.block_width != other.block_width || self.block_height != other.block_height {
             Err(SudokuError::InvalidDimensions)
@@ -409,30 +1119,45 @@ impl SudokuGrid {
     pub fn assign(&mut self, other: &SudokuGrid) -> SudokuResult<()> {
         self.verify_dimensions(other)?;
         self.cells.copy_from_slice(&other.cells);
+        self.zobrist = other.zobrist;
+        self.filled = other.filled;
+        self.digit_counts.copy_from_slice(&other.digit_counts);
+        self.cache_valid = other.cache_valid;
         Ok(())
     }
 
     pub fn count_clues(&self) -> usize {
-        let size = self.size();
-        let mut clues = 0usize;
+        if self.cache_valid {
+            self.filled
+        } else {
+            self.cells.iter().flatten().count()
+        }
+    }
 
-        for row in 0..size {
-            for column in 0..size {
-                if self.get_cell(column, row).unwrap().is_some() {
-                    clues += 1;
-                }
-            }
+    // The filled cells as (column, row, number) in row-major order.
+    pub fn filled_cells(&self) -> FilledCells<'_> {
+        FilledCells {
+            cells: self.cells.iter().enumerate(),
+            size: self.size,
+            remaining: self.count_clues(),
         }
+    }
 
-        clues
+    // The empty cells as (column, row) in row-major order.
+    pub fn empty_cells(&self) -> EmptyCells<'_> {
+        EmptyCells {
+            cells: self.cells.iter().enumerate(),
+            size: self.size,
+            remaining: self.cells.len() - self.count_clues(),
+        }
     }
 
     pub fn is_full(&self) -> bool {
-        !self.cells.iter().any(|c| c == &None)
+        self.count_clues() == self.cells.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.cells.iter().all(|c| c == &None)
+        self.count_clues() == 0
     }
 
     pub fn is_subset(&self, other: &SudokuGrid) -> SudokuResult<bool> {
@@ -452,39 +1177,233 @@ impl SudokuGrid {
         other.is_subset(self)
     }
 
+    // Compares only the cells, as Ord does for grids of equal dimensions.
+    pub fn cmp_cells(&self, other: &SudokuGrid) -> SudokuResult<Ordering> {
+        self.verify_dimensions(other)?;
+        Ok(self.cells.cmp(&other.cells))
+    }
+
     pub fn cells(&self) -> &Vec<Option<usize>> {
         &self.cells
     }
 
-    pub fn cells_mut(&mut self) -> &mut Vec<Option<usize>> {
-        &mut self.cells
+    pub fn cells_mut(&mut self) -> CellsViewMut<'_> {
+        CellsViewMut { grid: self }
     }
-}
 
-#[derive(Clone, Deserialize, Serialize)]
-pub struct Sudoku<C: Constraint + Clone> {
-    grid: SudokuGrid,
-    constraint: C,
-}
+    // Raw access to the cells, which neither checks the numbers nor prevents
+    // resizing. Only meant for tests which need a corrupted grid.
+    #[doc(hidden)]
+    pub fn cells_mut_unchecked(&mut self) -> &mut Vec<Option<usize>> {
+        self.cache_valid = false;
+        &mut self.cells
+    }
 
-impl<C: Constraint + Clone> Sudoku<C> {
+    // Hash of the cells which is updated incrementally when cells change.
+    // Equal cells always have an equal hash, regardless of the dimensions.
+    pub fn zobrist_hash(&self) -> u64 {
+        if self.cache_valid {
+            self.zobrist
+        } else {
+            compute_zobrist(&self.cells)
+        }
+    }
+
+    // Recomputes the hash, the number of filled cells and the digit counts
+    // after the cells were modified through cells_mut_unchecked, so
+    // zobrist_hash and count_clues do not have to do so every time.
+    pub fn refresh_zobrist(&mut self) {
+        self.zobrist = compute_zobrist(&self.cells);
+        self.filled = self.cells.iter().flatten().count();
+        self.digit_counts.fill(0);
+
+        for index in 0..self.cells.len() {
+            if let Some(number) = self.cells[index] {
+                self.count_digit(index, number, true);
+            }
+        }
+
+        self.cache_valid = true;
+    }
+}
+
+// Mutable access to the cells of a grid by their row-major index. Unlike the
+// underlying vector, it cannot change the number of cells or store numbers
+// outside the range of the grid.
+pub struct CellsViewMut<'a> {
+    grid: &'a mut SudokuGrid,
+}
+
+impl CellsViewMut<'_> {
+    pub fn len(&self) -> usize {
+        self.grid.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.grid.cells.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> SudokuResult<Option<usize>> {
+        self.grid
+            .cells
+            .get(index)
+            .copied()
+            .ok_or(SudokuError::OutOfBounds)
+    }
+
+    fn check(&self, content: Option<usize>) -> SudokuResult<()> {
+        match content {
+            Some(number) if number == 0 || number > self.grid.size() => {
+                Err(SudokuError::InvalidNumber)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set(&mut self, index: usize, content: Option<usize>) -> SudokuResult<()> {
+        if index >= self.len() {
+            return Err(SudokuError::OutOfBounds);
+        }
+
+        self.check(content)?;
+        self.grid.set_content(index, content);
+        Ok(())
+    }
+
+    pub fn swap(&mut self, a: usize, b: usize) -> SudokuResult<()> {
+        let content_a = self.get(a)?;
+        let content_b = self.get(b)?;
+        self.grid.set_content(a, content_b);
+        self.grid.set_content(b, content_a);
+        Ok(())
+    }
+
+    // Replaces all cells by the given contents in row-major order. Nothing
+    // is changed unless there is exactly one valid content for every cell.
+    pub fn fill_from_iter<I>(&mut self, contents: I) -> SudokuResult<()>
+    where
+        I: IntoIterator<Item = Option<usize>>,
+    {
+        let contents: Vec<Option<usize>> = contents.into_iter().collect();
+
+        if contents.len() != self.len() {
+            return Err(SudokuError::InvalidDimensions);
+        }
+
+        for &content in contents.iter() {
+            self.check(content)?;
+        }
+
+        for (index, content) in contents.into_iter().enumerate() {
+            self.grid.set_content(index, content);
+        }
+
+        Ok(())
+    }
+}
+
+pub struct FilledCells<'a> {
+    cells: Enumerate<Iter<'a, Option<usize>>>,
+    size: usize,
+    remaining: usize,
+}
+
+impl Iterator for FilledCells<'_> {
+    type Item = (usize, usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize, usize)> {
+        let size = self.size;
+        let cell = self
+            .cells
+            .find_map(|(index, cell)| cell.map(|number| (index % size, index / size, number)));
+
+        if cell.is_some() {
+            self.remaining -= 1;
+        }
+
+        cell
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for FilledCells<'_> {}
+
+pub struct EmptyCells<'a> {
+    cells: Enumerate<Iter<'a, Option<usize>>>,
+    size: usize,
+    remaining: usize,
+}
+
+impl Iterator for EmptyCells<'_> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let size = self.size;
+        let cell = self
+            .cells
+            .find(|(_, cell)| cell.is_none())
+            .map(|(index, _)| (index % size, index / size));
+
+        if cell.is_some() {
+            self.remaining -= 1;
+        }
+
+        cell
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for EmptyCells<'_> {}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(try_from = "SudokuData<C>")]
+pub struct Sudoku<C: Constraint + Clone> {
+    grid: SudokuGrid,
+    constraint: C,
+}
+
+#[derive(Deserialize)]
+struct SudokuData<C> {
+    grid: SudokuGrid,
+    constraint: C,
+}
+
+impl<C: Constraint + Clone> TryFrom<SudokuData<C>> for Sudoku<C> {
+    type Error = SudokuError;
+
+    fn try_from(data: SudokuData<C>) -> SudokuResult<Sudoku<C>> {
+        Sudoku::new_with_grid(data.grid, data.constraint)
+    }
+}
+
+impl<C: Constraint + Clone> Sudoku<C> {
     pub fn new_empty(
         block_width: usize,
         block_height: usize,
         constraint: C,
     ) -> SudokuResult<Sudoku<C>> {
-        Ok(Sudoku {
-            grid: SudokuGrid::new(block_width, block_height)?,
-            constraint,
-        })
+        Sudoku::new_with_grid(SudokuGrid::new(block_width, block_height)?, constraint)
     }
 
-    pub fn new_with_grid(grid: SudokuGrid, constraint: C) -> Sudoku<C> {
-        Sudoku { grid, constraint }
+    pub fn new_with_grid(grid: SudokuGrid, constraint: C) -> SudokuResult<Sudoku<C>> {
+        constraint
+            .compatible_with(&grid)
+            .map_err(SudokuError::IncompatibleConstraint)?;
+        Ok(Sudoku { grid, constraint })
     }
 
     pub fn parse(code: &str, constraint: C) -> SudokuParseResult<Sudoku<C>> {
-        Ok(Sudoku::new_with_grid(SudokuGrid::parse(code)?, constraint))
+        let grid = SudokuGrid::parse(code)?;
+        constraint
+            .compatible_with(&grid)
+            .map_err(SudokuParseError::IncompatibleConstraint)?;
+        Ok(Sudoku { grid, constraint })
     }
 
     pub fn grid(&self) -> &SudokuGrid {
@@ -503,10 +1422,46 @@ impl<C: Constraint + Clone> Sudoku<C> {
         &mut self.constraint
     }
 
+    // Clears every cell, including those a session would treat as givens.
+    // To restart a game, use GameSession::reset_to_givens instead.
+    pub fn clear_all(&mut self) {
+        self.grid = SudokuGrid::new(self.grid.block_width(), self.grid.block_height()).unwrap();
+    }
+
+    pub fn clone_empty(&self) -> Sudoku<C> {
+        let mut empty = self.clone();
+        empty.clear_all();
+        empty
+    }
+
+    // Pairs a copy of the constraint with the given grid, which must be
+    // compatible with it.
+    pub fn clone_with_grid(&self, grid: SudokuGrid) -> SudokuResult<Sudoku<C>> {
+        Sudoku::new_with_grid(grid, self.constraint.clone())
+    }
+
     pub fn is_valid(&self) -> bool {
         self.constraint.check(&self.grid)
     }
 
+    pub fn validate(&self) -> ValidationReport {
+        let mut problems = validation::grid_structural_problems(&self.grid);
+
+        if problems.is_empty() {
+            problems = self.constraint.structural_problems(&self.grid);
+        }
+
+        if problems.is_empty() {
+            ValidationReport::new(problems, self.violations())
+        } else {
+            ValidationReport::new(problems, Vec::new())
+        }
+    }
+
+    pub fn violations(&self) -> Vec<Violation> {
+        self.constraint.check_detailed(&self.grid)
+    }
+
     pub fn is_valid_cell(&self, column: usize, row: usize) -> SudokuResult<bool> {
         let size = self.grid.size();
 
@@ -538,13 +1493,235 @@ impl<C: Constraint + Clone> Sudoku<C> {
     pub fn into_raw_parts(self) -> (SudokuGrid, C) {
         (self.grid, self.constraint)
     }
+
+    // Swaps the constraint for the one returned by `f`, keeping the grid, and
+    // returns the violations of the new constraint by the digits already
+    // entered. Fails with IncompatibleConstraint if the new constraint does
+    // not fit the grid. This sudoku is left untouched either way.
+    pub fn map_constraint<D, F>(&self, f: F) -> SudokuResult<(Sudoku<D>, Vec<Violation>)>
+    where
+        D: Constraint + Clone,
+        F: FnOnce(&C) -> D,
+    {
+        let sudoku = Sudoku::new_with_grid(self.grid.clone(), f(&self.constraint))?;
+        let violations = sudoku.violations();
+        Ok((sudoku, violations))
+    }
+
+    pub fn replace_constraint<D>(&self, constraint: D) -> SudokuResult<(Sudoku<D>, Vec<Violation>)>
+    where
+        D: Constraint + Clone,
+    {
+        self.map_constraint(|_| constraint)
+    }
+
+    // Enters the given number and returns a guard which reverts the cell to
+    // its previous content when dropped, unless it is committed.
+    pub fn trial_move(
+        &mut self,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> SudokuResult<TrialGuard<'_, C>> {
+        let previous = self.grid.get_cell(column, row)?;
+        self.grid.set_cell(column, row, number)?;
+
+        Ok(TrialGuard {
+            sudoku: self,
+            column,
+            row,
+            previous,
+            committed: false,
+        })
+    }
+
+    pub fn with_move<R, F>(
+        &mut self,
+        column: usize,
+        row: usize,
+        number: usize,
+        f: F,
+    ) -> SudokuResult<R>
+    where
+        F: FnOnce(&Sudoku<C>) -> R,
+    {
+        let guard = self.trial_move(column, row, number)?;
+        Ok(f(&guard))
+    }
+
+    pub fn progress(&self) -> Progress {
+        let size = self.grid.size();
+
+        Progress {
+            filled: self.grid.count_clues(),
+            total: size * size,
+        }
+    }
+
+    // All other cells which share a group of the constraint with the given
+    // cell, in row-major order.
+    pub fn peers(
+        &self,
+        column: usize,
+        row: usize,
+    ) -> SudokuResult<impl Iterator<Item = (usize, usize)>> {
+        let size = self.grid.size();
+        let target = index(column, row, size)?;
+        let mut is_peer = vec![false; size * size];
+
+        self.constraint.for_each_group(&self.grid, &mut |group| {
+            if group.contains(&(column, row)) {
+                for &(peer_column, peer_row) in group {
+                    is_peer[peer_row * size + peer_column] = true;
+                }
+            }
+        });
+
+        is_peer[target] = false;
+
+        Ok(is_peer
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, is_peer)| is_peer)
+            .map(move |(index, _)| (index % size, index / size)))
+    }
+
+    // How the groups of the constraint overlap, see OverlapReport.
+    pub fn overlap_report(&self) -> OverlapReport {
+        OverlapReport::new(self.constraint.get_groups(&self.grid))
+    }
+
+    fn missing_in(&self, house: House) -> USizeSet {
+        let mut missing = USizeSet::new(1, self.grid.size()).unwrap();
+
+        for number in 1..=self.grid.size() {
+            if self.grid.count_in(house, number) == 0 {
+                missing.insert(number).unwrap();
+            }
+        }
+
+        missing
+    }
+
+    // The digits which are not yet placed in the given row.
+    pub fn missing_in_row(&self, row: usize) -> SudokuResult<USizeSet> {
+        index(0, row, self.grid.size())?;
+        Ok(self.missing_in(House::Row(row)))
+    }
+
+    pub fn missing_in_column(&self, column: usize) -> SudokuResult<USizeSet> {
+        index(column, 0, self.grid.size())?;
+        Ok(self.missing_in(House::Column(column)))
+    }
+
+    // The digits which are not yet placed in the block in the given block
+    // column and block row, counted in blocks from the top left.
+    pub fn missing_in_block(
+        &self,
+        block_column: usize,
+        block_row: usize,
+    ) -> SudokuResult<USizeSet> {
+        let block_width = self.grid.block_width();
+        let block_height = self.grid.block_height();
+
+        if block_column >= block_height || block_row >= block_width {
+            return Err(SudokuError::OutOfBounds);
+        }
+
+        Ok(self.missing_in(House::Block(block_row * block_height + block_column)))
+    }
+
+    // Returns how many more times every digit has to be placed, at index
+    // `digit - 1`. A digit entered too often counts as zero remaining, and
+    // numbers outside the range of the grid are skipped.
+    pub fn remaining_digits(&self) -> Vec<usize> {
+        let size = self.grid.size();
+
+        (1..=size)
+            .map(|number| size.saturating_sub(self.grid.count_in(House::Grid, number)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Progress {
+    filled: usize,
+    total: usize,
+}
+
+impl Progress {
+    pub fn filled(&self) -> usize {
+        self.filled
+    }
+
+    pub fn empty(&self) -> usize {
+        self.total - self.filled
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn percentage(&self) -> f64 {
+        100.0 * self.filled as f64 / self.total as f64
+    }
+}
+
+pub struct TrialGuard<'a, C: Constraint + Clone> {
+    sudoku: &'a mut Sudoku<C>,
+    column: usize,
+    row: usize,
+    previous: Option<usize>,
+    committed: bool,
+}
+
+impl<'a, C: Constraint + Clone> TrialGuard<'a, C> {
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn previous(&self) -> Option<usize> {
+        self.previous
+    }
+
+    // Keeps the move instead of reverting it.
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+}
+
+impl<'a, C: Constraint + Clone> Deref for TrialGuard<'a, C> {
+    type Target = Sudoku<C>;
+
+    fn deref(&self) -> &Sudoku<C> {
+        self.sudoku
+    }
+}
+
+impl<'a, C: Constraint + Clone> Drop for TrialGuard<'a, C> {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+
+        let index = self.row * self.sudoku.grid.size() + self.column;
+        self.sudoku.grid.set_content(index, self.previous);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::constraint::DefaultConstraint;
+    use crate::constraint::{
+        CompositeConstraint, DefaultConstraint, DiagonalsConstraint, GivenCandidatesConstraint,
+    };
+    use crate::set;
+    use crate::validation::StructuralProblem;
 
     #[test]
     fn parse_ok() {
@@ -684,6 +1861,148 @@ mod tests {
         assert!(full.is_full());
     }
 
+    #[test]
+    fn peers_share_a_group() {
+        let sudoku = Sudoku::new_empty(2, 2, DefaultConstraint).unwrap();
+        let peers: Vec<(usize, usize)> = sudoku.peers(1, 1).unwrap().collect();
+
+        assert_eq!(
+            vec![(0, 0), (1, 0), (0, 1), (2, 1), (3, 1), (1, 2), (1, 3)],
+            peers
+        );
+        assert!(sudoku.peers(4, 0).is_err());
+    }
+
+    #[test]
+    fn clue_count_is_maintained() {
+        use rand::{Rng, SeedableRng};
+        use rand_chacha::ChaCha8Rng;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut grid = SudokuGrid::new(2, 2).unwrap();
+        let scan = |grid: &SudokuGrid| grid.cells().iter().flatten().count();
+
+        for _ in 0..200 {
+            let (column, row) = (rng.gen_range(0..4), rng.gen_range(0..4));
+
+            match rng.gen_range(0..3) {
+                0 => grid.set_cell(column, row, rng.gen_range(1..=4)).unwrap(),
+                1 => grid.clear_cell(column, row).unwrap(),
+                _ => grid
+                    .cells_mut()
+                    .swap(row * 4 + column, rng.gen_range(0..16))
+                    .unwrap(),
+            }
+
+            assert_eq!(scan(&grid), grid.count_clues());
+        }
+
+        let mut other = SudokuGrid::new(2, 2).unwrap();
+        other.assign(&grid).unwrap();
+        assert_eq!(scan(&grid), other.count_clues());
+
+        grid.cells_mut_unchecked().fill(Some(1));
+        assert!(grid.is_full());
+        grid.refresh_zobrist();
+        grid.clear_cell(0, 0).unwrap();
+        assert_eq!(15, grid.count_clues());
+
+        let mut grid = SudokuGrid::new(2, 2).unwrap();
+        grid.cells_mut_unchecked()[0] = Some(1);
+        grid.clear_cell(0, 0).unwrap();
+        grid.set_cell(1, 0, 2).unwrap();
+        assert_eq!(1, grid.count_clues());
+        assert_eq!(compute_zobrist(grid.cells()), grid.zobrist_hash());
+        grid.refresh_zobrist();
+        grid.clear_cell(1, 0).unwrap();
+        assert_eq!(0, grid.count_clues());
+        assert_eq!(0, grid.zobrist_hash());
+    }
+
+    #[test]
+    fn clearing_and_cloning_with_grids() {
+        let mut sudoku = Sudoku::parse("2x2;1,,,,,,1,,,1,,,,,,1", DefaultConstraint).unwrap();
+        let empty = sudoku.clone_empty();
+
+        assert!(empty.grid().is_empty());
+        assert_eq!(4, sudoku.grid().count_clues());
+
+        let grid = SudokuGrid::parse("2x2;,2,,,,,,,,,,,,,,").unwrap();
+        let other = sudoku.clone_with_grid(grid.clone()).unwrap();
+
+        assert_eq!(&grid, other.grid());
+        assert!(sudoku
+            .clone_with_grid(SudokuGrid::new(3, 3).unwrap())
+            .is_ok());
+
+        sudoku.clear_all();
+        assert_eq!(empty.grid(), sudoku.grid());
+    }
+
+    #[test]
+    fn missing_digits_per_house() {
+        let sudoku = Sudoku::parse(
+            "3x2;\
+            1,2, , , , ,\
+             , , ,6, , ,\
+             ,3, , , , ,\
+             , , , , ,5,\
+             , , , , , ,\
+             , , , , , ",
+            DefaultConstraint,
+        )
+        .unwrap();
+
+        assert_eq!(set!(1, 6; 3, 4, 5, 6), sudoku.missing_in_row(0).unwrap());
+        assert_eq!(set!(1, 6; 1, 4, 5, 6), sudoku.missing_in_column(1).unwrap());
+        assert_eq!(set!(1, 6; 3, 4, 5, 6), sudoku.missing_in_block(0, 0).unwrap());
+        assert_eq!(set!(1, 6; 1, 2, 3, 4, 6), sudoku.missing_in_block(1, 1).unwrap());
+        assert_eq!(Err(SudokuError::OutOfBounds), sudoku.missing_in_row(6));
+        assert_eq!(Err(SudokuError::OutOfBounds), sudoku.missing_in_column(6));
+        assert_eq!(Err(SudokuError::OutOfBounds), sudoku.missing_in_block(2, 0));
+        assert!(sudoku.missing_in_block(1, 2).is_ok());
+
+        let mut sudoku = sudoku;
+        sudoku.grid_mut().set_cell(4, 0, 3).unwrap();
+        sudoku.grid_mut().clear_cell(0, 0).unwrap();
+
+        assert_eq!(set!(1, 6; 1, 4, 5, 6), sudoku.missing_in_row(0).unwrap());
+        assert_eq!(set!(1, 6; 1, 2, 3, 4, 5, 6), sudoku.missing_in_column(0).unwrap());
+        assert_eq!(set!(1, 6; 1, 2, 4, 5), sudoku.missing_in_block(1, 0).unwrap());
+
+        sudoku.grid_mut().cells_mut_unchecked()[5] = Some(4);
+        assert_eq!(set!(1, 6; 1, 5, 6), sudoku.missing_in_row(0).unwrap());
+        sudoku.grid_mut().refresh_zobrist();
+        assert_eq!(set!(1, 6; 1, 5, 6), sudoku.missing_in_row(0).unwrap());
+    }
+
+    #[test]
+    fn apply_bulk_is_atomic() {
+        let mut grid = SudokuGrid::new(2, 2).unwrap();
+        grid.apply_bulk(&[(0, 0, 1), (1, 0, 2), (3, 3, 4)]).unwrap();
+
+        assert_eq!(Some(2), grid.get_cell(1, 0).unwrap());
+        assert_eq!(3, grid.count_clues());
+
+        let before = grid.clone();
+
+        assert_eq!(
+            Err(SudokuError::InvalidPlacement {
+                index: 1,
+                error: Box::new(SudokuError::InvalidNumber)
+            }),
+            grid.apply_bulk(&[(2, 0, 3), (2, 1, 5), (4, 0, 1)])
+        );
+        assert_eq!(
+            Err(SudokuError::InvalidPlacement {
+                index: 0,
+                error: Box::new(SudokuError::OutOfBounds)
+            }),
+            grid.apply_bulk(&[(0, 4, 1)])
+        );
+        assert_eq!(before, grid);
+    }
+
     fn assert_subset_relation(a: &SudokuGrid, b: &SudokuGrid, a_subset_b: bool, b_subset_a: bool) {
         assert!(a.is_subset(b).unwrap() == a_subset_b);
         assert!(a.is_superset(b).unwrap() == b_subset_a);
@@ -820,4 +2139,579 @@ mod tests {
 
         assert_eq!(grid, reconstructed_grid);
     }
+
+    #[test]
+    fn sudoku_grid_postcard_round_trip() {
+        let grid = SudokuGrid::parse("2x2;1,,,2,,3,,,4,,,1,,,2,").unwrap();
+        let bytes = postcard::to_allocvec(&grid).unwrap();
+        let reconstructed_grid: SudokuGrid = postcard::from_bytes(&bytes).unwrap();
+
+        assert_eq!(grid, reconstructed_grid);
+        assert!(bytes.len() < serde_json::to_string(&grid).unwrap().len());
+        assert!(postcard::from_bytes::<SudokuGrid>(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn with_move_reverts() {
+        let mut sudoku = solution_example_sudoku();
+        let original = sudoku.grid().clone();
+        let valid = sudoku
+            .with_move(1, 0, 2, |s| {
+                assert_eq!(Some(2), s.grid().get_cell(1, 0).unwrap());
+                s.is_valid_cell(1, 0).unwrap()
+            })
+            .unwrap();
+
+        assert!(!valid);
+        assert_eq!(&original, sudoku.grid());
+    }
+
+    #[test]
+    fn trial_guard_reverts_overwritten_cell() {
+        let mut sudoku = solution_example_sudoku();
+        let original = sudoku.grid().clone();
+
+        {
+            let guard = sudoku.trial_move(0, 0, 1).unwrap();
+            assert_eq!(Some(2), guard.previous());
+            assert_eq!(Some(1), guard.grid().get_cell(0, 0).unwrap());
+        }
+
+        assert_eq!(&original, sudoku.grid());
+    }
+
+    #[test]
+    fn trial_guard_commit_keeps_move() {
+        let mut sudoku = solution_example_sudoku();
+        sudoku.trial_move(1, 0, 3).unwrap().commit();
+
+        assert_eq!(Some(3), sudoku.grid().get_cell(1, 0).unwrap());
+    }
+
+    #[test]
+    fn trial_move_rejects_invalid_input() {
+        let mut sudoku = solution_example_sudoku();
+
+        assert_eq!(
+            Err(SudokuError::OutOfBounds),
+            sudoku.trial_move(4, 0, 1).map(|_| ())
+        );
+        assert_eq!(
+            Err(SudokuError::InvalidNumber),
+            sudoku.trial_move(0, 0, 5).map(|_| ())
+        );
+        assert_eq!(Some(2), sudoku.grid().get_cell(0, 0).unwrap());
+    }
+
+    #[test]
+    fn zobrist_hash_is_incremental() {
+        let mut grid = SudokuGrid::parse("2x2;1, , ,2, ,3, , , , ,4, ,3, , ,1").unwrap();
+        let original = grid.zobrist_hash();
+        grid.set_cell(1, 0, 4).unwrap();
+        grid.set_cell(1, 0, 2).unwrap();
+        grid.clear_cell(0, 0).unwrap();
+
+        assert_eq!(compute_zobrist(grid.cells()), grid.zobrist_hash());
+        assert_ne!(original, grid.zobrist_hash());
+
+        grid.clear_cell(1, 0).unwrap();
+        grid.set_cell(0, 0, 1).unwrap();
+        assert_eq!(original, grid.zobrist_hash());
+        assert_eq!(0, SudokuGrid::new(2, 2).unwrap().zobrist_hash());
+    }
+
+    #[test]
+    fn iterates_filled_and_empty_cells() {
+        let grid = SudokuGrid::parse("2x2;1,,,,,,2,,,,,,,,,4").unwrap();
+        let mut filled = grid.filled_cells();
+
+        assert_eq!(3, filled.len());
+        assert_eq!(Some((0, 0, 1)), filled.next());
+        assert_eq!(2, filled.len());
+        assert_eq!(vec![(2, 1, 2), (3, 3, 4)], filled.collect::<Vec<_>>());
+        assert_eq!(13, grid.empty_cells().len());
+        assert_eq!(
+            vec![(1, 0), (2, 0), (3, 0), (0, 1)],
+            grid.empty_cells().take(4).collect::<Vec<_>>()
+        );
+        assert!(grid
+            .empty_cells()
+            .all(|(column, row)| grid.get_cell(column, row).unwrap().is_none()));
+    }
+
+    #[test]
+    fn cells_mut_keeps_the_grid_valid() {
+        let mut grid = SudokuGrid::new(2, 2).unwrap();
+        let mut cells = grid.cells_mut();
+        cells.set(5, Some(3)).unwrap();
+        cells.swap(5, 6).unwrap();
+
+        assert_eq!(Err(SudokuError::InvalidNumber), cells.set(0, Some(5)));
+        assert_eq!(Err(SudokuError::OutOfBounds), cells.set(16, Some(1)));
+        assert_eq!(
+            Err(SudokuError::InvalidDimensions),
+            cells.fill_from_iter(vec![Some(1); 15])
+        );
+        assert_eq!(
+            Err(SudokuError::InvalidNumber),
+            cells.fill_from_iter((0..16).map(Some))
+        );
+        assert_eq!(Some(3), grid.get_cell(2, 1).unwrap());
+        assert_eq!(None, grid.get_cell(1, 1).unwrap());
+        assert_eq!(compute_zobrist(grid.cells()), grid.zobrist_hash());
+
+        grid.cells_mut()
+            .fill_from_iter((0..16).map(|i| Some(i % 4 + 1)))
+            .unwrap();
+
+        assert_eq!(16, grid.count_clues());
+        assert_eq!(compute_zobrist(grid.cells()), grid.zobrist_hash());
+    }
+
+    #[test]
+    fn zobrist_hash_after_cells_mut_unchecked() {
+        let mut grid = SudokuGrid::new(2, 2).unwrap();
+        grid.cells_mut_unchecked()[5] = Some(3);
+        let expected = SudokuGrid::parse("2x2;,,,,,3,,,,,,,,,,").unwrap();
+
+        assert_eq!(expected.zobrist_hash(), grid.zobrist_hash());
+        grid.refresh_zobrist();
+        grid.clear_cell(1, 1).unwrap();
+        assert_eq!(0, grid.zobrist_hash());
+    }
+
+    #[test]
+    fn equal_grids_deduplicate() {
+        use std::collections::HashSet;
+
+        let a = SudokuGrid::parse("2x2;1,,,,,,,,,,,,,,,").unwrap();
+        let mut b = SudokuGrid::new(2, 2).unwrap();
+        b.set_cell(0, 0, 1).unwrap();
+        let json = serde_json::to_string(&a).unwrap();
+        let c: SudokuGrid = serde_json::from_str(&json).unwrap();
+        let set: HashSet<SudokuGrid> = vec![a, b, c, SudokuGrid::new(2, 2).unwrap()]
+            .into_iter()
+            .collect();
+
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn trial_guard_restores_zobrist_hash() {
+        let mut sudoku = solution_example_sudoku();
+        let original = sudoku.grid().zobrist_hash();
+        sudoku.with_move(0, 0, 3, |_| ()).unwrap();
+
+        assert_eq!(original, sudoku.grid().zobrist_hash());
+    }
+
+    #[test]
+    fn grids_are_ordered() {
+        let empty = SudokuGrid::new(2, 2).unwrap();
+        let one = SudokuGrid::parse("2x2;1,,,,,,,,,,,,,,,").unwrap();
+        let two = SudokuGrid::parse("2x2;2,,,,,,,,,,,,,,,").unwrap();
+        let late = SudokuGrid::parse("2x2;,,,,,,,,,,,,,,,4").unwrap();
+        let wide = SudokuGrid::new(3, 2).unwrap();
+        let mut grids = vec![
+            wide.clone(),
+            two.clone(),
+            late.clone(),
+            one.clone(),
+            empty.clone(),
+        ];
+        grids.sort();
+
+        assert_eq!(
+            vec![empty, late, one.clone(), two.clone(), wide.clone()],
+            grids
+        );
+        assert_eq!(Ok(Ordering::Less), one.cmp_cells(&two));
+        assert_eq!(Ok(Ordering::Equal), one.cmp_cells(&one));
+        assert_eq!(Err(SudokuError::InvalidDimensions), one.cmp_cells(&wide));
+    }
+
+    #[test]
+    fn try_from_tuple() {
+        let cells = vec![
+            Some(1),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(4),
+        ];
+        let grid = SudokuGrid::try_from((2, 2, cells.clone())).unwrap();
+
+        assert_eq!(SudokuGrid::parse("2x2;1,,,,,,,,,,,,,,,4").unwrap(), grid);
+        assert_eq!(
+            Err(SudokuError::InvalidDimensions),
+            SudokuGrid::try_from((3, 2, cells.clone()))
+        );
+        assert_eq!(
+            Err(SudokuError::InvalidDimensions),
+            SudokuGrid::try_from((0, 2, Vec::new()))
+        );
+
+        let mut invalid = cells;
+        invalid[3] = Some(5);
+        assert_eq!(
+            Err(SudokuError::InvalidNumber),
+            SudokuGrid::try_from((2, 2, invalid))
+        );
+    }
+
+    #[test]
+    fn try_from_array_and_into_rows() {
+        let mut rows = [[0u8; 9]; 9];
+        rows[0][1] = 9;
+        rows[8][8] = 1;
+        let grid = SudokuGrid::try_from(rows).unwrap();
+
+        assert_eq!(Some(9), grid.get_cell(1, 0).unwrap());
+        assert_eq!(Some(1), grid.get_cell(8, 8).unwrap());
+        assert_eq!(2, grid.count_clues());
+
+        let vec_rows: Vec<Vec<Option<usize>>> = (&grid).into();
+        assert_eq!(9, vec_rows.len());
+        assert_eq!(Some(9), vec_rows[0][1]);
+        assert_eq!(None, vec_rows[4][4]);
+
+        rows[4][4] = 10;
+        assert_eq!(Err(SudokuError::InvalidNumber), SudokuGrid::try_from(rows));
+    }
+
+    #[test]
+    fn deserialize_rejects_invalid_number() {
+        let json = r#"{"block_width":2,"block_height":2,"cells":[5,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null]}"#;
+
+        assert!(serde_json::from_str::<SudokuGrid>(json).is_err());
+    }
+
+    fn off_grid_candidates() -> GivenCandidatesConstraint {
+        let mut constraint = GivenCandidatesConstraint::new();
+        constraint.restrict(4, 0, USizeSet::new(1, 4).unwrap());
+        constraint
+    }
+
+    #[test]
+    fn incompatible_constraint_is_rejected() {
+        let grid = SudokuGrid::new(2, 2).unwrap();
+        let problem = StructuralProblem::CellOutOfBounds { column: 4, row: 0 };
+
+        assert_eq!(
+            Err(SudokuError::IncompatibleConstraint(problem.clone())),
+            Sudoku::new_with_grid(grid, off_grid_candidates()).map(|_| ())
+        );
+        assert_eq!(
+            Err(SudokuParseError::IncompatibleConstraint(problem)),
+            Sudoku::parse("2x2;,,,,,,,,,,,,,,,", off_grid_candidates()).map(|_| ())
+        );
+        assert!(Sudoku::new_empty(2, 2, off_grid_candidates()).is_err());
+    }
+
+    #[test]
+    fn replace_constraint_revalidates_grid() {
+        let sudoku = Sudoku::parse("2x2;1,,,,,,,,,,1,,,,,", DefaultConstraint).unwrap();
+        let (sudoku, violations) = sudoku
+            .map_constraint(|c| CompositeConstraint::new(c.clone(), DiagonalsConstraint))
+            .unwrap();
+
+        assert!(!sudoku.is_valid());
+        assert!(violations
+            .iter()
+            .all(|v| v.cells().contains(&(0, 0)) && v.cells().contains(&(2, 2))));
+        assert!(!violations.is_empty());
+
+        let (sudoku, violations) = sudoku.replace_constraint(DefaultConstraint).unwrap();
+
+        assert!(sudoku.is_valid());
+        assert!(violations.is_empty());
+        assert_eq!(
+            Err(SudokuError::IncompatibleConstraint(
+                StructuralProblem::CellOutOfBounds { column: 4, row: 0 }
+            )),
+            sudoku.replace_constraint(off_grid_candidates()).map(|_| ())
+        );
+        assert!(sudoku.is_valid());
+        assert_eq!(Some(1), sudoku.grid().get_cell(0, 0).unwrap());
+    }
+
+    #[test]
+    fn progress_and_remaining_digits() {
+        let sudoku = Sudoku::parse("2x2;1,,,,,,1,,,1,,,,,2,1", DefaultConstraint).unwrap();
+        let progress = sudoku.progress();
+
+        assert_eq!(5, progress.filled());
+        assert_eq!(11, progress.empty());
+        assert_eq!(31.25, progress.percentage());
+        assert_eq!(vec![0, 3, 4, 4], sudoku.remaining_digits());
+
+        let mut corrupt = sudoku.clone();
+        corrupt.grid_mut().cells_mut_unchecked()[1] = Some(0);
+        corrupt.grid_mut().cells_mut_unchecked()[2] = Some(5);
+        assert_eq!(vec![0, 3, 4, 4], corrupt.remaining_digits());
+        corrupt.grid_mut().refresh_zobrist();
+        assert_eq!(vec![0, 3, 4, 4], corrupt.remaining_digits());
+        corrupt.grid_mut().set_cell(1, 0, 2).unwrap();
+        assert_eq!(vec![0, 2, 4, 4], corrupt.remaining_digits());
+
+        let mut sudoku = sudoku;
+        sudoku.grid_mut().set_cell(1, 0, 2).unwrap();
+        sudoku.grid_mut().set_cell(0, 0, 3).unwrap();
+        assert_eq!(vec![1, 2, 3, 4], sudoku.remaining_digits());
+        sudoku.grid_mut().clear_cell(1, 0).unwrap();
+        assert_eq!(vec![1, 3, 3, 4], sudoku.remaining_digits());
+    }
+
+    #[test]
+    fn deserialize_rejects_incompatible_constraint() {
+        let grid = serde_json::to_string(&SudokuGrid::new(2, 2).unwrap()).unwrap();
+        let constraint = serde_json::to_string(&off_grid_candidates()).unwrap();
+        let json = format!(r#"{{"grid":{},"constraint":{}}}"#, grid, constraint);
+        let valid = format!(r#"{{"grid":{},"constraint":{{"restrictions":[]}}}}"#, grid);
+
+        assert!(serde_json::from_str::<Sudoku<GivenCandidatesConstraint>>(&json).is_err());
+        assert!(serde_json::from_str::<Sudoku<GivenCandidatesConstraint>>(&valid).is_ok());
+    }
+
+    #[test]
+    fn parse_alphanumeric_hex_grid() {
+        let mut cells = vec![String::new(); 256];
+        cells[0] = String::from("1");
+        cells[1] = String::from("a");
+        cells[255] = String::from("G");
+        let code = format!("4x4;{}", cells.join(","));
+        let grid = SudokuGrid::parse_alphanumeric(&code).unwrap();
+
+        assert_eq!(Some(1), grid.get_cell(0, 0).unwrap());
+        assert_eq!(Some(10), grid.get_cell(1, 0).unwrap());
+        assert_eq!(Some(16), grid.get_cell(15, 15).unwrap());
+        assert_eq!(
+            code.replace('a', "A"),
+            grid.to_alphanumeric_string().unwrap()
+        );
+        assert_eq!(
+            Ok(grid.clone()),
+            SudokuGrid::parse_alphanumeric(&grid.to_alphanumeric_string().unwrap())
+        );
+    }
+
+    #[test]
+    fn parse_with_custom_alphabet() {
+        let alphabet = Alphabet::new("abcd").unwrap();
+        let grid = SudokuGrid::parse_with_alphabet("2x2;a,,,b,,,,,,,,,,,,D", &alphabet).unwrap();
+
+        assert_eq!(SudokuGrid::parse("2x2;1,,,2,,,,,,,,,,,,4").unwrap(), grid);
+        assert_eq!(
+            "2x2;a,,,b,,,,,,,,,,,,d",
+            grid.to_string_with_alphabet(&alphabet).unwrap()
+        );
+        assert_eq!(
+            Err(SudokuParseError::NumberFormatError),
+            SudokuGrid::parse_with_alphabet("2x2;e,,,,,,,,,,,,,,,", &alphabet)
+        );
+        assert_eq!(
+            Err(SudokuParseError::InvalidNumber),
+            SudokuGrid::parse_alphanumeric("2x2;5,,,,,,,,,,,,,,,")
+        );
+        assert_eq!(
+            Err(SudokuError::InvalidNumber),
+            SudokuGrid::new(3, 3)
+                .unwrap()
+                .to_string_with_alphabet(&alphabet)
+        );
+    }
+
+    #[test]
+    fn parse_lenient_classic() {
+        let text = "
+            5 3 . . 7 . . . .
+            6 . . 1 9 5 . . .
+            . 9 8 . . . . 6 .
+            8 . . . 6 . . . 3
+            4 . . 8 . 3 . . 1
+            7 . . . 2 . . . 6
+            . 6 . . . . 2 8 .
+            . . . 4 1 9 . . 5
+            . . . . 8 . . 7 9
+        ";
+        let compact = "53..7....\n6..195...\n.98....6.\n8...6...3\n4..8.3..1\n\
+            7...2...6\n.6....28.\n...419..5\n....8..79\n";
+        let grid = SudokuGrid::parse_lenient(text).unwrap();
+
+        assert_eq!(3, grid.block_width());
+        assert_eq!(3, grid.block_height());
+        assert_eq!(Some(5), grid.get_cell(0, 0).unwrap());
+        assert_eq!(Some(9), grid.get_cell(8, 8).unwrap());
+        assert_eq!(30, grid.count_clues());
+        assert_eq!(Ok(grid), SudokuGrid::parse_lenient(compact));
+    }
+
+    #[test]
+    fn parse_lenient_infers_dimensions_from_clues() {
+        // (0, 0) and (2, 1) share a 3x2 block, so only 2x3 remains.
+        let text = "1 _ 0 . . .\n. . 1 . . .\n. . . . . .\n. . . . . .\n. . . . . .\n. . . . . .";
+        let grid = SudokuGrid::parse_lenient(text).unwrap();
+
+        assert_eq!((2, 3), (grid.block_width(), grid.block_height()));
+    }
+
+    #[test]
+    fn parse_lenient_errors() {
+        let empty_6x6 = ". . . . . .\n".repeat(6);
+        let ragged = "1 . . .\n. . .\n. . . .\n. . . .";
+
+        assert_eq!(
+            Err(SudokuParseError::AmbiguousDimensions(vec![(3, 2), (2, 3)])),
+            SudokuGrid::parse_lenient(&empty_6x6)
+        );
+        assert_eq!(
+            Err(SudokuParseError::WrongNumberOfCells),
+            SudokuGrid::parse_lenient(ragged)
+        );
+        assert_eq!(
+            Err(SudokuParseError::InvalidDimensions),
+            SudokuGrid::parse_lenient(&". . . . .\n".repeat(5))
+        );
+        assert_eq!(
+            Err(SudokuParseError::NumberFormatError),
+            SudokuGrid::parse_lenient("1 x\n. .")
+        );
+        assert_eq!(
+            Err(SudokuParseError::InvalidNumber),
+            SudokuGrid::parse_lenient("1 . . .\n. 00 . .\n. . . .\n. . . .")
+        );
+    }
+
+    #[test]
+    fn infer_block_dims() {
+        assert_eq!(vec![(1, 1)], SudokuGrid::infer_block_dims(1));
+        assert_eq!(vec![(3, 3)], SudokuGrid::infer_block_dims(9));
+        assert_eq!(
+            vec![(6, 2), (4, 3), (3, 4), (2, 6)],
+            SudokuGrid::infer_block_dims(12)
+        );
+        assert!(SudokuGrid::infer_block_dims(7).is_empty());
+    }
+
+    #[test]
+    fn dimension_policies() {
+        assert_eq!(Some((3, 2)), DimensionPolicy::PreferSquare.block_dims(6));
+        assert_eq!(Some((4, 3)), DimensionPolicy::PreferSquare.block_dims(12));
+        assert_eq!(Some((4, 4)), DimensionPolicy::PreferSquare.block_dims(16));
+        assert_eq!(Some((6, 2)), DimensionPolicy::PreferWide.block_dims(12));
+        assert_eq!(Some((8, 2)), DimensionPolicy::PreferWide.block_dims(16));
+        assert_eq!(None, DimensionPolicy::PreferWide.block_dims(7));
+        assert_eq!(
+            Some((2, 3)),
+            DimensionPolicy::PreferWide.choose(&[(2, 3), (1, 6)])
+        );
+    }
+
+    #[test]
+    fn parse_lenient_with_policy() {
+        let text = ". . . . . .\n".repeat(6);
+        let square = SudokuGrid::parse_lenient_with_policy(&text, DimensionPolicy::PreferSquare);
+
+        assert_eq!(
+            Ok((3, 2)),
+            square.map(|g| (g.block_width(), g.block_height()))
+        );
+    }
+
+    #[test]
+    fn parse_pretty_reads_display_output() {
+        let grid = SudokuGrid::parse("3x2;1,,,,,2,,3,,,,,,,,4,,,,,5,,,,,,,,6,,,,,,,").unwrap();
+        let tall = SudokuGrid::parse("2x3;1,,,,,2,,3,,,,,,,,4,,,,,5,,,,,,,,6,,,,,,,").unwrap();
+
+        assert_eq!(
+            Ok(grid.clone()),
+            SudokuGrid::parse_pretty(&grid.to_string())
+        );
+        assert_eq!(
+            Ok(tall.clone()),
+            SudokuGrid::parse_pretty(&tall.to_string())
+        );
+    }
+
+    #[test]
+    fn parse_pretty_ascii() {
+        let framed = "
+            +-------+-------+
+            | 1 . . | . . 2 |
+            | . 3 . | . . . |
+            +-------+-------+
+            | . . . | 4 . . |
+            | . . 5 | . . . |
+            +-------+-------+
+            | . . . | . 6 . |
+            | . . . | . . . |
+            +-------+-------+
+        ";
+        let unframed = "
+            1 . . | . . 2
+            . 3 . | . . .
+            . . . | 4 . .
+            . . 5 | . . .
+            . . . | . 6 .
+            . . . | . . .
+        ";
+        let expected = SudokuGrid::parse("3x2;1,,,,,2,,3,,,,,,,,4,,,,,5,,,,,,,,6,,,,,,,").unwrap();
+
+        assert_eq!(Ok(expected.clone()), SudokuGrid::parse_pretty(framed));
+        assert_eq!(Ok(expected), SudokuGrid::parse_pretty(unframed));
+    }
+
+    #[test]
+    fn to_ascii_string() {
+        let grid = SudokuGrid::parse("3x2;1,,,,,2,,3,,,,,,,,4,,,,,5,,,,,,,,6,,,,,,,").unwrap();
+        let ascii = grid.to_ascii_string();
+
+        assert!(ascii.starts_with("+-------+-------+\n| 1 . . | . . 2 |\n"));
+        assert!(ascii.is_ascii());
+        assert_eq!(Ok(grid), SudokuGrid::parse_pretty(&ascii));
+    }
+
+    #[test]
+    fn describe() {
+        let grid = SudokuGrid::parse("2x2;1,,3,,,,,,,,,,,,,4").unwrap();
+        let description = grid.describe();
+
+        assert!(description.starts_with("Row 1: 1, blank, 3, blank\nRow 2: "));
+        assert!(description.ends_with("Row 4: blank, blank, blank, 4\n"));
+    }
+
+    #[test]
+    fn parse_pretty_errors() {
+        assert_eq!(
+            Err(SudokuParseError::WrongNumberOfCells),
+            SudokuGrid::parse_pretty("| 1 . | . . |\n| . . | . |")
+        );
+        assert_eq!(
+            Err(SudokuParseError::InvalidDimensions),
+            SudokuGrid::parse_pretty("| 1 . . | . |\n| . . . | . |\n| . . . | . |\n| . . . | . |")
+        );
+        assert_eq!(
+            Err(SudokuParseError::InvalidNumber),
+            SudokuGrid::parse_pretty(
+                "| 5 . | . . |\n| . . | . . |\n+---+---+\n| . . | . . |\n| . . | . . |"
+            )
+        );
+        assert_eq!(
+            Err(SudokuParseError::InvalidNumber),
+            SudokuGrid::parse_pretty(
+                "| 00 . | . . |\n| . . | . . |\n+---+---+\n| . . | . . |\n| . . | . . |"
+            )
+        );
+    }
 }
@@ -0,0 +1,410 @@
+use crate::constraint::Constraint;
+use crate::error::{SudokuError, SudokuResult};
+use crate::grader::{Grader, SearchEstimate};
+use crate::solver::{BacktrackingSolver, Solution, Solver};
+use crate::sudoku_generator::{Generator, Reducer};
+use crate::utilities::shuffle_iter;
+use crate::{Sudoku, SudokuGrid};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+pub struct MinedPuzzle<C: Constraint + Clone> {
+    sudoku: Sudoku<C>,
+    estimate: SearchEstimate,
+    seed: u64,
+}
+
+impl<C: Constraint + Clone> MinedPuzzle<C> {
+    pub fn sudoku(&self) -> &Sudoku<C> {
+        &self.sudoku
+    }
+
+    pub fn estimate(&self) -> &SearchEstimate {
+        &self.estimate
+    }
+
+    // Passing this seed to Miner::climb reproduces the puzzle.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn into_sudoku(self) -> Sudoku<C> {
+        self.sudoku
+    }
+}
+
+// Searches for puzzles the grader rates as hard as possible. Each attempt
+// generates and reduces a random puzzle, then hill-climbs on its clue
+// pattern: a removed clue is put back, other clues are removed again as long
+// as the solution stays unique, and the result replaces the current puzzle
+// if it scores at least as high.
+pub struct Miner {
+    attempts: usize,
+    steps: usize,
+    top: usize,
+}
+
+// Generates and reduces the random puzzle an attempt starts from. Returns it
+// with its solution and the RNG for the rest of the attempt.
+fn start_attempt<C>(
+    block_width: usize,
+    block_height: usize,
+    constraint: C,
+    seed: u64,
+) -> SudokuResult<(Sudoku<C>, SudokuGrid, ChaCha8Rng)>
+where
+    C: Constraint + Clone + 'static,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut generator = Generator::new(ChaCha8Rng::seed_from_u64(rng.gen()));
+    let mut sudoku = generator.generate(block_width, block_height, constraint)?;
+    let solution = sudoku.grid().clone();
+    Reducer::new(BacktrackingSolver, ChaCha8Rng::seed_from_u64(rng.gen())).reduce(&mut sudoku);
+    Ok((sudoku, solution, rng))
+}
+
+fn is_unique<C>(sudoku: &Sudoku<C>) -> bool
+where
+    C: Constraint + Clone + 'static,
+{
+    matches!(BacktrackingSolver.solve(sudoku), Solution::Unique(_))
+}
+
+// Puts back the clue of a random empty cell, then removes all other clues
+// in random order as long as the solution stays unique.
+fn mutate<C, R>(sudoku: &Sudoku<C>, solution: &SudokuGrid, rng: &mut R) -> Sudoku<C>
+where
+    C: Constraint + Clone + 'static,
+    R: Rng,
+{
+    let mut mutated = sudoku.clone();
+    let size = solution.size();
+    let cells = (0..size).flat_map(|row| (0..size).map(move |column| (column, row)));
+    let (empty, filled): (Vec<_>, Vec<_>) =
+        cells.partition(|&(column, row)| sudoku.grid().get_cell(column, row).unwrap().is_none());
+
+    if empty.is_empty() {
+        return mutated;
+    }
+
+    let (column, row) = empty[rng.gen_range(0..empty.len())];
+    let number = solution.get_cell(column, row).unwrap().unwrap();
+    mutated.grid_mut().set_cell(column, row, number).unwrap();
+
+    for (column, row) in shuffle_iter(rng, filled) {
+        let number = mutated.grid().get_cell(column, row).unwrap().unwrap();
+        mutated.grid_mut().clear_cell(column, row).unwrap();
+
+        if !is_unique(&mutated) {
+            mutated.grid_mut().set_cell(column, row, number).unwrap();
+        }
+    }
+
+    mutated
+}
+
+impl Miner {
+    pub fn new(attempts: usize, steps: usize, top: usize) -> Miner {
+        Miner {
+            attempts,
+            steps,
+            top,
+        }
+    }
+
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn top(&self) -> usize {
+        self.top
+    }
+
+    // Runs a single attempt, which is fully determined by the seed. ChaCha8
+    // streams are stable across platforms and rand versions, so published
+    // seeds keep reproducing their puzzles.
+    pub fn climb<C>(
+        &self,
+        block_width: usize,
+        block_height: usize,
+        constraint: C,
+        grader: &Grader,
+        seed: u64,
+    ) -> SudokuResult<MinedPuzzle<C>>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let (mut sudoku, solution, mut rng) =
+            start_attempt(block_width, block_height, constraint, seed)?;
+        let mut estimate = grader.estimate_by_search(&sudoku)?;
+
+        for _ in 0..self.steps {
+            let candidate = mutate(&sudoku, &solution, &mut rng);
+            let candidate_estimate = grader.estimate_by_search(&candidate)?;
+
+            if candidate_estimate.score() >= estimate.score() {
+                sudoku = candidate;
+                estimate = candidate_estimate;
+            }
+        }
+
+        Ok(MinedPuzzle {
+            sudoku,
+            estimate,
+            seed,
+        })
+    }
+
+    // Runs all attempts with the seeds seed, seed + 1, ... and returns the
+    // best puzzles found, hardest first.
+    pub fn mine<C>(
+        &self,
+        block_width: usize,
+        block_height: usize,
+        constraint: C,
+        grader: &Grader,
+        seed: u64,
+    ) -> SudokuResult<Vec<MinedPuzzle<C>>>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let mut found = Vec::with_capacity(self.attempts);
+
+        for attempt in 0..self.attempts {
+            let attempt_seed = seed.wrapping_add(attempt as u64);
+            found.push(self.climb(
+                block_width,
+                block_height,
+                constraint.clone(),
+                grader,
+                attempt_seed,
+            )?);
+        }
+
+        found.sort_by(|a, b| b.estimate.score().total_cmp(&a.estimate.score()));
+        found.truncate(self.top);
+        Ok(found)
+    }
+}
+
+// The effort spent by minimum_clues_search: the number of independently
+// generated puzzles and the number of mutation steps for each of them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ClueSearchBudget {
+    attempts: usize,
+    steps: usize,
+}
+
+impl ClueSearchBudget {
+    pub fn new(attempts: usize, steps: usize) -> ClueSearchBudget {
+        ClueSearchBudget { attempts, steps }
+    }
+
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ClueSearchStatistics {
+    clue_counts: Vec<usize>,
+    accepted_steps: usize,
+}
+
+impl ClueSearchStatistics {
+    // The number of clues each attempt ended with, in the order of attempts.
+    pub fn clue_counts(&self) -> &[usize] {
+        &self.clue_counts
+    }
+
+    // The number of mutations which did not increase the clue count.
+    pub fn accepted_steps(&self) -> usize {
+        self.accepted_steps
+    }
+
+    pub fn average_clues(&self) -> f64 {
+        self.clue_counts.iter().sum::<usize>() as f64 / self.clue_counts.len() as f64
+    }
+}
+
+pub struct ClueSearchResult<C: Constraint + Clone> {
+    best: Sudoku<C>,
+    estimate: SearchEstimate,
+    seed: u64,
+    statistics: ClueSearchStatistics,
+}
+
+impl<C: Constraint + Clone> ClueSearchResult<C> {
+    // The unique puzzle with the fewest clues found, from the earliest
+    // attempt on ties.
+    pub fn best(&self) -> &Sudoku<C> {
+        &self.best
+    }
+
+    pub fn estimate(&self) -> &SearchEstimate {
+        &self.estimate
+    }
+
+    // The seed of the attempt which found the best puzzle. Searching again
+    // with this seed, one attempt and the same number of steps reproduces it.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn statistics(&self) -> &ClueSearchStatistics {
+        &self.statistics
+    }
+
+    pub fn into_best(self) -> Sudoku<C> {
+        self.best
+    }
+}
+
+// Searches for unique puzzles with as few clues as possible under the given
+// rules. Each attempt generates and reduces a random puzzle, then mutates it
+// as in Miner::climb, keeping mutations which do not add clues, so it can
+// drift across plateaus of minimal puzzles. Attempt `i` uses the seed
+// `seed + i`. Fails with NoAttempts if the budget has no attempts.
+pub fn minimum_clues_search<C>(
+    block_width: usize,
+    block_height: usize,
+    constraint: C,
+    grader: &Grader,
+    budget: ClueSearchBudget,
+    seed: u64,
+) -> SudokuResult<ClueSearchResult<C>>
+where
+    C: Constraint + Clone + 'static,
+{
+    let mut statistics = ClueSearchStatistics::default();
+    let mut best: Option<(Sudoku<C>, u64)> = None;
+
+    for attempt in 0..budget.attempts {
+        let attempt_seed = seed.wrapping_add(attempt as u64);
+        let (mut sudoku, solution, mut rng) =
+            start_attempt(block_width, block_height, constraint.clone(), attempt_seed)?;
+
+        for _ in 0..budget.steps {
+            let candidate = mutate(&sudoku, &solution, &mut rng);
+
+            if candidate.grid().count_clues() <= sudoku.grid().count_clues() {
+                sudoku = candidate;
+                statistics.accepted_steps += 1;
+            }
+        }
+
+        let clues = sudoku.grid().count_clues();
+        statistics.clue_counts.push(clues);
+
+        if best
+            .as_ref()
+            .is_none_or(|(best, _)| clues < best.grid().count_clues())
+        {
+            best = Some((sudoku, attempt_seed));
+        }
+    }
+
+    let (best, seed) = best.ok_or(SudokuError::NoAttempts)?;
+    let estimate = grader.estimate_by_search(&best)?;
+
+    Ok(ClueSearchResult {
+        best,
+        estimate,
+        seed,
+        statistics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+
+    #[test]
+    fn mined_puzzles_are_sorted_and_unique() {
+        let grader = Grader::new();
+        let miner = Miner::new(4, 5, 2);
+        let found = miner.mine(3, 2, DefaultConstraint, &grader, 17).unwrap();
+
+        assert_eq!(2, found.len());
+        assert!(found[0].estimate().score() >= found[1].estimate().score());
+
+        for puzzle in found.iter() {
+            assert!(is_unique(puzzle.sudoku()));
+            assert!(puzzle.estimate().is_unique());
+            assert!((17..21).contains(&puzzle.seed()));
+        }
+    }
+
+    #[test]
+    fn seeds_reproduce_puzzles() {
+        let grader = Grader::new();
+        let miner = Miner::new(3, 4, 1);
+        let best = miner.mine(2, 2, DefaultConstraint, &grader, 5).unwrap();
+        let best = &best[0];
+        let again = miner
+            .climb(2, 2, DefaultConstraint, &grader, best.seed())
+            .unwrap();
+
+        assert_eq!(best.sudoku().grid(), again.sudoku().grid());
+    }
+
+    #[test]
+    fn climbing_never_gets_easier() {
+        let grader = Grader::new();
+        let start = Miner::new(1, 0, 1)
+            .climb(3, 2, DefaultConstraint, &grader, 3)
+            .unwrap();
+        let climbed = Miner::new(1, 10, 1)
+            .climb(3, 2, DefaultConstraint, &grader, 3)
+            .unwrap();
+
+        assert!(climbed.estimate().score() >= start.estimate().score());
+    }
+
+    #[test]
+    fn minimum_clue_search_keeps_the_fewest_clues() {
+        let grader = Grader::new();
+        let budget = ClueSearchBudget::new(3, 4);
+        let result = minimum_clues_search(2, 2, DefaultConstraint, &grader, budget, 9).unwrap();
+        let best = result.best();
+        let clues = best.grid().count_clues();
+        let again = minimum_clues_search(
+            2,
+            2,
+            DefaultConstraint,
+            &grader,
+            ClueSearchBudget::new(1, 4),
+            result.seed(),
+        )
+        .unwrap();
+
+        assert!(is_unique(best));
+        assert_eq!(3, result.statistics().clue_counts().len());
+        assert_eq!(Some(&clues), result.statistics().clue_counts().iter().min());
+        assert_eq!(best.grid(), again.best().grid());
+        assert_eq!(
+            Some(SudokuError::NoAttempts),
+            minimum_clues_search(
+                2,
+                2,
+                DefaultConstraint,
+                &grader,
+                ClueSearchBudget::new(0, 4),
+                9
+            )
+            .err()
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::constraint::Group;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct GroupOverlap {
+    first: usize,
+    second: usize,
+    shared: usize,
+}
+
+impl GroupOverlap {
+    pub fn first(&self) -> usize {
+        self.first
+    }
+
+    pub fn second(&self) -> usize {
+        self.second
+    }
+
+    pub fn shared(&self) -> usize {
+        self.shared
+    }
+}
+
+// How the groups of a constraint overlap. Every pair of groups which shares
+// at least one cell is listed once, with the lower index first. A group is
+// dominated by another if all of its cells lie in the other one, so the
+// distinctness it demands is already implied. Of two equal groups, the later
+// one is dominated by the earlier one. Rules other than distinctness, such as
+// the sum of a killer cage, are not considered.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct OverlapReport {
+    groups: Vec<Group>,
+    overlaps: Vec<GroupOverlap>,
+    dominated_by: Vec<Option<usize>>,
+}
+
+impl OverlapReport {
+    pub fn new(groups: Vec<Group>) -> OverlapReport {
+        let sets: Vec<HashSet<(usize, usize)>> = groups
+            .iter()
+            .map(|group| group.iter().cloned().collect())
+            .collect();
+        let mut overlaps = Vec::new();
+        let mut dominated_by = vec![None; groups.len()];
+
+        for (first, first_set) in sets.iter().enumerate() {
+            for (second, second_set) in sets.iter().enumerate().skip(first + 1) {
+                let shared = first_set.intersection(second_set).count();
+
+                if shared == 0 {
+                    continue;
+                }
+
+                overlaps.push(GroupOverlap {
+                    first,
+                    second,
+                    shared,
+                });
+
+                if shared == second_set.len() && dominated_by[second].is_none() {
+                    dominated_by[second] = Some(first);
+                } else if shared == first_set.len() && dominated_by[first].is_none() {
+                    dominated_by[first] = Some(second);
+                }
+            }
+        }
+
+        OverlapReport {
+            groups,
+            overlaps,
+            dominated_by,
+        }
+    }
+
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    pub fn overlaps(&self) -> &[GroupOverlap] {
+        &self.overlaps
+    }
+
+    // The number of cells the groups at the given indices have in common.
+    pub fn shared_cells(&self, first: usize, second: usize) -> usize {
+        let (first, second) = (first.min(second), first.max(second));
+
+        self.overlaps
+            .iter()
+            .find(|overlap| overlap.first == first && overlap.second == second)
+            .map_or(0, |overlap| overlap.shared)
+    }
+
+    pub fn dominated_by(&self, group: usize) -> Option<usize> {
+        self.dominated_by.get(group).copied().flatten()
+    }
+
+    // The indices of all groups which are dominated by another one.
+    pub fn redundant_groups(&self) -> impl Iterator<Item = usize> + '_ {
+        self.dominated_by
+            .iter()
+            .enumerate()
+            .filter(|(_, dominated_by)| dominated_by.is_some())
+            .map(|(group, _)| group)
+    }
+
+    // Whether adding a group with the given cells, such as a new cage, would
+    // demand distinctness anywhere it is not demanded already.
+    pub fn restricts_further(&self, cells: &[(usize, usize)]) -> bool {
+        cells.len() > 1
+            && !self
+                .groups
+                .iter()
+                .any(|group| cells.iter().all(|cell| group.contains(cell)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn row(row: usize) -> Group {
+        (0..4).map(|column| (column, row)).collect()
+    }
+
+    #[test]
+    fn overlaps_and_dominance() {
+        let report = OverlapReport::new(vec![
+            row(0),
+            vec![(0, 0), (1, 0), (0, 1), (1, 1)],
+            vec![(1, 0), (0, 0)],
+            row(0),
+        ]);
+
+        assert_eq!(2, report.shared_cells(0, 1));
+        assert_eq!(2, report.shared_cells(1, 0));
+        assert_eq!(4, report.shared_cells(0, 3));
+        assert_eq!(6, report.overlaps().len());
+        assert_eq!(None, report.dominated_by(0));
+        assert_eq!(None, report.dominated_by(1));
+        assert_eq!(Some(0), report.dominated_by(2));
+        assert_eq!(Some(0), report.dominated_by(3));
+        assert_eq!(vec![2, 3], report.redundant_groups().collect::<Vec<_>>());
+
+        assert!(!report.restricts_further(&[(2, 0), (3, 0)]));
+        assert!(!report.restricts_further(&[(3, 3)]));
+        assert!(report.restricts_further(&[(2, 0), (2, 1)]));
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(report, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::error::{SudokuError, SudokuResult};
+use crate::{index, SudokuGrid};
+
+// The output of a digit classifier for every cell of a scanned puzzle. Each
+// cell holds `size + 1` probabilities: index 0 for an empty cell and index
+// `n` for the number `n`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProbabilityMatrix {
+    block_width: usize,
+    block_height: usize,
+    cells: Vec<Vec<f64>>,
+}
+
+impl ProbabilityMatrix {
+    // Takes the probabilities row by row. Fails with InvalidDimensions if
+    // the shape does not match the block dimensions and InvalidNumber if a
+    // probability lies outside [0, 1].
+    pub fn from_rows(
+        block_width: usize,
+        block_height: usize,
+        rows: Vec<Vec<Vec<f64>>>,
+    ) -> SudokuResult<ProbabilityMatrix> {
+        let size = block_width * block_height;
+
+        if size == 0
+            || rows.len() != size
+            || rows
+                .iter()
+                .any(|row| row.len() != size || row.iter().any(|cell| cell.len() != size + 1))
+        {
+            return Err(SudokuError::InvalidDimensions);
+        }
+
+        let cells: Vec<Vec<f64>> = rows.into_iter().flatten().collect();
+
+        if cells.iter().flatten().any(|p| !(0.0..=1.0).contains(p)) {
+            return Err(SudokuError::InvalidNumber);
+        }
+
+        Ok(ProbabilityMatrix {
+            block_width,
+            block_height,
+            cells,
+        })
+    }
+
+    pub fn block_width(&self) -> usize {
+        self.block_width
+    }
+
+    pub fn block_height(&self) -> usize {
+        self.block_height
+    }
+
+    pub fn size(&self) -> usize {
+        self.block_width * self.block_height
+    }
+
+    pub fn probabilities(&self, column: usize, row: usize) -> SudokuResult<&[f64]> {
+        let index = index(column, row, self.size())?;
+        Ok(&self.cells[index])
+    }
+}
+
+// A cell whose most likely reading fell below the confidence threshold. The
+// readings are sorted by decreasing probability, `None` standing for empty.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AmbiguousCell {
+    column: usize,
+    row: usize,
+    readings: Vec<(Option<usize>, f64)>,
+}
+
+impl AmbiguousCell {
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn readings(&self) -> &[(Option<usize>, f64)] {
+        &self.readings
+    }
+}
+
+// A recognized grid. Ambiguous cells are left empty in the grid, so a caller
+// can ask the user to confirm them. The confidence is the lowest probability
+// among the readings which were accepted.
+#[derive(Clone, Debug)]
+pub struct Recognition {
+    grid: SudokuGrid,
+    ambiguous: Vec<AmbiguousCell>,
+    confidence: f64,
+}
+
+impl Recognition {
+    pub fn grid(&self) -> &SudokuGrid {
+        &self.grid
+    }
+
+    pub fn ambiguous(&self) -> &[AmbiguousCell] {
+        &self.ambiguous
+    }
+
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    pub fn is_certain(&self) -> bool {
+        self.ambiguous.is_empty()
+    }
+}
+
+// Turns some representation of a scanned puzzle into a grid. Implementations
+// backed by actual image recognition can produce a ProbabilityMatrix and
+// delegate to ThresholdRecognizer.
+pub trait GridRecognizer {
+    type Input: ?Sized;
+
+    fn recognize(&self, input: &Self::Input) -> SudokuResult<Recognition>;
+}
+
+// Accepts the most likely reading of every cell if its probability reaches
+// the threshold and reports the cell as ambiguous otherwise.
+#[derive(Clone, Copy, Debug)]
+pub struct ThresholdRecognizer {
+    threshold: f64,
+}
+
+impl ThresholdRecognizer {
+    pub fn new(threshold: f64) -> SudokuResult<ThresholdRecognizer> {
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(SudokuError::InvalidNumber);
+        }
+
+        Ok(ThresholdRecognizer { threshold })
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+}
+
+impl GridRecognizer for ThresholdRecognizer {
+    type Input = ProbabilityMatrix;
+
+    fn recognize(&self, matrix: &ProbabilityMatrix) -> SudokuResult<Recognition> {
+        let size = matrix.size();
+        let mut grid = SudokuGrid::new(matrix.block_width, matrix.block_height)?;
+        let mut ambiguous = Vec::new();
+        let mut confidence = 1.0f64;
+
+        for (index, probabilities) in matrix.cells.iter().enumerate() {
+            let (column, row) = (index % size, index / size);
+            let mut readings: Vec<(Option<usize>, f64)> = probabilities
+                .iter()
+                .enumerate()
+                .filter(|&(_, &p)| p > 0.0)
+                .map(|(n, &p)| (Some(n).filter(|&n| n > 0), p))
+                .collect();
+            readings.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+            match readings.first() {
+                Some(&(number, p)) if p >= self.threshold => {
+                    confidence = confidence.min(p);
+
+                    if let Some(number) = number {
+                        grid.set_cell(column, row, number)?;
+                    }
+                }
+                _ => ambiguous.push(AmbiguousCell {
+                    column,
+                    row,
+                    readings,
+                }),
+            }
+        }
+
+        Ok(Recognition {
+            grid,
+            ambiguous,
+            confidence,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn certain(cell: usize) -> Vec<f64> {
+        let mut probabilities = vec![0.0; 5];
+        probabilities[cell] = 1.0;
+        probabilities
+    }
+
+    fn matrix(first: Vec<f64>) -> ProbabilityMatrix {
+        let mut rows = vec![vec![certain(0); 4]; 4];
+        rows[0][0] = first;
+        rows[3][3] = certain(4);
+        ProbabilityMatrix::from_rows(2, 2, rows).unwrap()
+    }
+
+    #[test]
+    fn confident_readings_are_accepted() {
+        let recognizer = ThresholdRecognizer::new(0.8).unwrap();
+        let recognition = recognizer
+            .recognize(&matrix(vec![0.05, 0.9, 0.05, 0.0, 0.0]))
+            .unwrap();
+
+        assert!(recognition.is_certain());
+        assert_eq!(0.9, recognition.confidence());
+        assert_eq!(
+            SudokuGrid::parse("2x2;1,,,,,,,,,,,,,,,4").unwrap(),
+            *recognition.grid()
+        );
+    }
+
+    #[test]
+    fn uncertain_readings_are_reported() {
+        let recognizer = ThresholdRecognizer::new(0.8).unwrap();
+        let recognition = recognizer
+            .recognize(&matrix(vec![0.1, 0.5, 0.0, 0.0, 0.4]))
+            .unwrap();
+        let cell = &recognition.ambiguous()[0];
+
+        assert_eq!(1, recognition.ambiguous().len());
+        assert_eq!((0, 0), (cell.column(), cell.row()));
+        assert_eq!(
+            &[(Some(1), 0.5), (Some(4), 0.4), (None, 0.1)],
+            cell.readings()
+        );
+        assert_eq!(None, recognition.grid().get_cell(0, 0).unwrap());
+    }
+
+    #[test]
+    fn malformed_matrices_are_rejected() {
+        assert_eq!(
+            Err(SudokuError::InvalidDimensions),
+            ProbabilityMatrix::from_rows(2, 2, vec![vec![certain(0); 4]; 3])
+        );
+        assert_eq!(
+            Err(SudokuError::InvalidNumber),
+            ProbabilityMatrix::from_rows(2, 2, vec![vec![vec![1.5; 5]; 4]; 4])
+        );
+        assert!(ThresholdRecognizer::new(0.0).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use crate::error::{SudokuError, SudokuResult};
+use crate::grader::Difficulty;
+use crate::render::write_grid;
+use crate::SudokuGrid;
+
+use std::fmt::Write;
+
+// A puzzle to place on a page, with the information shown in its caption and
+// an optional solution for the solutions section.
+#[derive(Clone, Debug)]
+pub struct LayoutEntry {
+    id: String,
+    puzzle: SudokuGrid,
+    solution: Option<SudokuGrid>,
+    difficulty: Option<Difficulty>,
+}
+
+impl LayoutEntry {
+    pub fn new(id: impl Into<String>, puzzle: SudokuGrid) -> LayoutEntry {
+        LayoutEntry {
+            id: id.into(),
+            puzzle,
+            solution: None,
+            difficulty: None,
+        }
+    }
+
+    pub fn with_solution(mut self, solution: SudokuGrid) -> LayoutEntry {
+        self.solution = Some(solution);
+        self
+    }
+
+    pub fn with_difficulty(mut self, difficulty: Difficulty) -> LayoutEntry {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn puzzle(&self) -> &SudokuGrid {
+        &self.puzzle
+    }
+
+    pub fn solution(&self) -> Option<&SudokuGrid> {
+        self.solution.as_ref()
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.difficulty
+    }
+
+    fn caption(&self) -> String {
+        match self.difficulty {
+            Some(difficulty) => format!("{} ({:?})", escape(&self.id), difficulty),
+            None => escape(&self.id),
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Arranges puzzles in a grid of slots on fixed-size pages, by default A4 in
+// points. Every grid is scaled to fit its slot, leaving room for a caption
+// below it. Solutions, if enabled, follow the puzzles on pages of their own.
+#[derive(Clone, Debug)]
+pub struct PageLayout {
+    columns: usize,
+    rows: usize,
+    page_width: f64,
+    page_height: f64,
+    margin: f64,
+    caption_height: f64,
+    solutions: bool,
+}
+
+impl PageLayout {
+    pub fn new(columns: usize, rows: usize) -> SudokuResult<PageLayout> {
+        if columns == 0 || rows == 0 {
+            return Err(SudokuError::InvalidDimensions);
+        }
+
+        Ok(PageLayout {
+            columns,
+            rows,
+            page_width: 595.0,
+            page_height: 842.0,
+            margin: 36.0,
+            caption_height: 18.0,
+            solutions: false,
+        })
+    }
+
+    pub fn with_page_size(mut self, width: f64, height: f64) -> PageLayout {
+        self.page_width = width;
+        self.page_height = height;
+        self
+    }
+
+    pub fn with_margin(mut self, margin: f64) -> PageLayout {
+        self.margin = margin;
+        self
+    }
+
+    pub fn with_solutions(mut self, solutions: bool) -> PageLayout {
+        self.solutions = solutions;
+        self
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn per_page(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    fn page(&self, items: &[(String, &SudokuGrid)]) -> String {
+        let slot_width = (self.page_width - 2.0 * self.margin) / self.columns as f64;
+        let slot_height = (self.page_height - 2.0 * self.margin) / self.rows as f64;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\">\n",
+            self.page_width, self.page_height
+        );
+
+        for (i, (caption, grid)) in items.iter().enumerate() {
+            let slot_x = self.margin + slot_width * (i % self.columns) as f64;
+            let slot_y = self.margin + slot_height * (i / self.columns) as f64;
+
+            // The grid is centered horizontally, with some padding around it
+            // and the caption below.
+            let extent = (slot_width * 0.9).min(slot_height * 0.9 - self.caption_height);
+            let x = slot_x + (slot_width - extent) / 2.0;
+            let y = slot_y + slot_height * 0.05;
+            write_grid(&mut svg, grid, x, y, extent / grid.size() as f64);
+            writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\">{}</text>",
+                slot_x + slot_width / 2.0,
+                y + extent + self.caption_height,
+                self.caption_height * 0.7,
+                caption
+            )
+            .unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    // Composes the entries into pages and returns one SVG document per page.
+    pub fn compose(&self, entries: &[LayoutEntry]) -> Vec<String> {
+        let puzzles: Vec<(String, &SudokuGrid)> = entries
+            .iter()
+            .map(|entry| (entry.caption(), &entry.puzzle))
+            .collect();
+        let mut pages: Vec<String> = puzzles
+            .chunks(self.per_page())
+            .map(|items| self.page(items))
+            .collect();
+
+        if self.solutions {
+            let solutions: Vec<(String, &SudokuGrid)> = entries
+                .iter()
+                .filter_map(|entry| {
+                    let solution = entry.solution.as_ref()?;
+                    Some((format!("Solution {}", escape(&entry.id)), solution))
+                })
+                .collect();
+            pages.extend(
+                solutions
+                    .chunks(self.per_page())
+                    .map(|items| self.page(items)),
+            );
+        }
+
+        pages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn entries(count: usize) -> Vec<LayoutEntry> {
+        let puzzle = SudokuGrid::parse("2x2;1,,,,,,,,,,,,,,,").unwrap();
+        let solution = SudokuGrid::parse("2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,1").unwrap();
+
+        (0..count)
+            .map(|i| {
+                LayoutEntry::new(format!("#{}", i), puzzle.clone())
+                    .with_solution(solution.clone())
+                    .with_difficulty(Difficulty::Easy)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn puzzles_are_split_into_pages() {
+        let layout = PageLayout::new(2, 2).unwrap();
+        let pages = layout.compose(&entries(5));
+
+        assert_eq!(2, pages.len());
+        assert!(pages[0].contains(">#3 (Easy)</text>"));
+        assert!(!pages[0].contains(">#4"));
+        assert!(pages[1].contains(">#4 (Easy)</text>"));
+        assert!(PageLayout::new(0, 2).is_err());
+    }
+
+    #[test]
+    fn solutions_follow_the_puzzles() {
+        let layout = PageLayout::new(3, 1).unwrap().with_solutions(true);
+        let mut entries = entries(4);
+        entries.push(LayoutEntry::new("a<b", entries[0].puzzle().clone()));
+        let pages = layout.compose(&entries);
+
+        assert_eq!(4, pages.len());
+        assert!(pages[1].contains(">a&lt;b</text>"));
+        assert!(pages[2].contains(">Solution #0</text>"));
+        assert!(pages[3].contains(">Solution #3</text>"));
+        assert!(!pages[3].contains("a&lt;b"));
+    }
+}
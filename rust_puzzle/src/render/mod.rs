@@ -0,0 +1,156 @@
+pub mod layout;
+
+pub use layout::{LayoutEntry, PageLayout};
+
+use crate::solver::CandidateHeatmap;
+use crate::SudokuGrid;
+
+use std::fmt::Write;
+
+// Writes the grid as SVG elements with its top left corner at (x, y), thin
+// lines between cells and thick lines between blocks.
+pub(crate) fn write_grid(svg: &mut String, grid: &SudokuGrid, x: f64, y: f64, cell_size: f64) {
+    let size = grid.size();
+    let extent = cell_size * size as f64;
+
+    for i in 0..=size {
+        let offset = cell_size * i as f64;
+        let vertical_width = if i % grid.block_width() == 0 { 3 } else { 1 };
+        let horizontal_width = if i % grid.block_height() == 0 { 3 } else { 1 };
+        writeln!(
+            svg,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"{}\"/>",
+            x + offset,
+            y,
+            x + offset,
+            y + extent,
+            vertical_width
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"{}\"/>",
+            x,
+            y + offset,
+            x + extent,
+            y + offset,
+            horizontal_width
+        )
+        .unwrap();
+    }
+
+    for row in 0..size {
+        for column in 0..size {
+            if let Some(number) = grid.get_cell(column, row).unwrap() {
+                writeln!(
+                    svg,
+                    "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" \
+                     dominant-baseline=\"central\">{}</text>",
+                    x + cell_size * (column as f64 + 0.5),
+                    y + cell_size * (row as f64 + 0.5),
+                    cell_size * 0.6,
+                    number
+                )
+                .unwrap();
+            }
+        }
+    }
+}
+
+// Shades every cell with more than one candidate, from transparent for two
+// candidates to fully opaque for as many candidates as digits.
+pub(crate) fn write_heatmap(
+    svg: &mut String,
+    heatmap: &CandidateHeatmap,
+    x: f64,
+    y: f64,
+    cell_size: f64,
+) {
+    let size = heatmap.counts().size();
+    let scale = size.saturating_sub(1).max(1) as f64;
+
+    for row in 0..size {
+        for column in 0..size {
+            let count = heatmap.count(column, row).unwrap();
+
+            if count > 1 {
+                writeln!(
+                    svg,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"red\" \
+                     fill-opacity=\"{}\"/>",
+                    x + cell_size * column as f64,
+                    y + cell_size * row as f64,
+                    cell_size,
+                    cell_size,
+                    (count - 1) as f64 / scale
+                )
+                .unwrap();
+            }
+        }
+    }
+}
+
+fn svg_header(grid: &SudokuGrid, cell_size: f64) -> String {
+    let extent = cell_size * grid.size() as f64 + 4.0;
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" \
+         viewBox=\"0 0 {0} {0}\">\n",
+        extent
+    )
+}
+
+// Renders the grid as a standalone SVG document.
+pub fn to_svg(grid: &SudokuGrid, cell_size: f64) -> String {
+    let mut svg = svg_header(grid, cell_size);
+    write_grid(&mut svg, grid, 2.0, 2.0, cell_size);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Renders the grid like to_svg, with the heatmap drawn beneath it. The
+// heatmap must have the same size as the grid.
+pub fn to_svg_with_heatmap(
+    grid: &SudokuGrid,
+    heatmap: &CandidateHeatmap,
+    cell_size: f64,
+) -> String {
+    let mut svg = svg_header(grid, cell_size);
+    write_heatmap(&mut svg, heatmap, 2.0, 2.0, cell_size);
+    write_grid(&mut svg, grid, 2.0, 2.0, cell_size);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+    use crate::solver::PropagatingSolver;
+    use crate::Sudoku;
+
+    #[test]
+    fn svg_contains_lines_and_numbers() {
+        let grid = SudokuGrid::parse("2x2;1,,,,,,,,,,,,,,,4").unwrap();
+        let svg = to_svg(&grid, 10.0);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(10, svg.matches("<line").count());
+        assert_eq!(2, svg.matches("<text").count());
+        assert!(svg.contains(">4</text>"));
+    }
+
+    #[test]
+    fn heatmap_shades_open_cells() {
+        let sudoku = Sudoku::parse("2x2;1,2,,,,,,,,,,,,,,", DefaultConstraint).unwrap();
+        let heatmap = PropagatingSolver::new().candidate_heatmap(&sudoku).unwrap();
+        let svg = to_svg_with_heatmap(sudoku.grid(), &heatmap, 10.0);
+
+        assert_eq!(14, svg.matches("<rect").count());
+        assert!(svg.find("<rect").unwrap() < svg.find("<line").unwrap());
+        assert_eq!(2, svg.matches("<text").count());
+        assert!(svg.contains("fill-opacity=\"1\""));
+    }
+}
@@ -0,0 +1,392 @@
+use crate::constraint::Constraint;
+use crate::error::{ReplayError, ReplayResult, SudokuError};
+use crate::session::GameSession;
+use crate::{Sudoku, SudokuGrid};
+
+use serde::{Deserialize, Serialize};
+
+use sha2::{Digest, Sha256};
+
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum ReplayAction {
+    Enter {
+        column: usize,
+        row: usize,
+        number: usize,
+    },
+    Erase {
+        column: usize,
+        row: usize,
+    },
+    Hint,
+    Undo,
+}
+
+// An action of the player and the time since the start of the game at which
+// it happened.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ReplayEvent {
+    at: Duration,
+    action: ReplayAction,
+}
+
+impl ReplayEvent {
+    pub fn at(&self) -> Duration {
+        self.at
+    }
+
+    pub fn action(&self) -> ReplayAction {
+        self.action
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReplaySummary {
+    solve_time: Option<Duration>,
+    mistakes: usize,
+    hints: usize,
+    undos: usize,
+}
+
+impl ReplaySummary {
+    // The time of the event which completed the puzzle, if any did.
+    pub fn solve_time(&self) -> Option<Duration> {
+        self.solve_time
+    }
+
+    pub fn mistakes(&self) -> usize {
+        self.mistakes
+    }
+
+    pub fn hints(&self) -> usize {
+        self.hints
+    }
+
+    pub fn undos(&self) -> usize {
+        self.undos
+    }
+}
+
+// A recording of a game as the initial puzzle and the timestamped actions of
+// the player, which `verify` plays back on a GameSession. Every recorded
+// event extends a SHA-256 hash chain over the puzzle and all previous
+// events. The chain is an integrity check only: it catches corrupted or
+// carelessly edited replays, but anyone can recompute it. To detect
+// deliberate edits, `sign` the replay with a secret key and check it with
+// `verify_signed`.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Replay<C: Constraint + Clone> {
+    sudoku: Sudoku<C>,
+    solution: SudokuGrid,
+    events: Vec<ReplayEvent>,
+    digest: [u8; 32],
+}
+
+fn hash_grid(hasher: &mut Sha256, grid: &SudokuGrid) {
+    hasher.update((grid.block_width() as u64).to_le_bytes());
+    hasher.update((grid.block_height() as u64).to_le_bytes());
+
+    for cell in grid.cells() {
+        hasher.update((cell.unwrap_or(0) as u64).to_le_bytes());
+    }
+}
+
+fn initial_digest(sudoku_grid: &SudokuGrid, solution: &SudokuGrid) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hash_grid(&mut hasher, sudoku_grid);
+    hash_grid(&mut hasher, solution);
+    hasher.finalize().into()
+}
+
+fn chain(digest: &[u8; 32], event: &ReplayEvent) -> [u8; 32] {
+    let fields = match event.action {
+        ReplayAction::Enter {
+            column,
+            row,
+            number,
+        } => [0, column, row, number],
+        ReplayAction::Erase { column, row } => [1, column, row, 0],
+        ReplayAction::Hint => [2, 0, 0, 0],
+        ReplayAction::Undo => [3, 0, 0, 0],
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(digest);
+    hasher.update(event.at.as_nanos().to_le_bytes());
+
+    for field in fields {
+        hasher.update((field as u64).to_le_bytes());
+    }
+
+    hasher.finalize().into()
+}
+
+// HMAC-SHA256 as in RFC 2104.
+fn hmac(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0; BLOCK_SIZE];
+
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let mut inner = Sha256::new();
+    inner.update(pad(0x36));
+    inner.update(message);
+    let mut outer = Sha256::new();
+    outer.update(pad(0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+impl<C: Constraint + Clone> Replay<C> {
+    pub fn new(sudoku: Sudoku<C>, solution: SudokuGrid) -> ReplayResult<Replay<C>> {
+        let valid = sudoku
+            .is_valid_solution(&solution)
+            .map_err(ReplayError::InvalidPuzzle)?;
+
+        if !valid {
+            return Err(ReplayError::InvalidPuzzle(SudokuError::InvalidSolution));
+        }
+
+        Ok(Replay {
+            digest: initial_digest(sudoku.grid(), &solution),
+            sudoku,
+            solution,
+            events: Vec::new(),
+        })
+    }
+
+    pub fn sudoku(&self) -> &Sudoku<C> {
+        &self.sudoku
+    }
+
+    pub fn solution(&self) -> &SudokuGrid {
+        &self.solution
+    }
+
+    pub fn events(&self) -> &[ReplayEvent] {
+        &self.events
+    }
+
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+
+    // Appends an action without checking it, which is left to `verify`.
+    pub fn record(&mut self, at: Duration, action: ReplayAction) {
+        let event = ReplayEvent { at, action };
+        self.digest = chain(&self.digest, &event);
+        self.events.push(event);
+    }
+
+    // The HMAC-SHA256 of the digest under the given key. Since the digest
+    // covers the puzzle and all events, only holders of the key can sign a
+    // modified replay.
+    pub fn sign(&self, key: &[u8]) -> [u8; 32] {
+        hmac(key, &self.digest)
+    }
+
+    // Like `verify`, but also fails with Tampered unless the signature was
+    // made by `sign` with the same key. The comparison takes the same time
+    // wherever the signatures differ.
+    pub fn verify_signed(&self, key: &[u8], signature: &[u8; 32]) -> ReplayResult<ReplaySummary> {
+        let difference = self
+            .sign(key)
+            .iter()
+            .zip(signature)
+            .fold(0, |difference, (a, b)| difference | (a ^ b));
+
+        if difference != 0 {
+            return Err(ReplayError::Tampered);
+        }
+
+        self.verify()
+    }
+
+    // Checks the digest and plays the events back in order. Fails if an
+    // event happened before its predecessor, is rejected by the session or
+    // does nothing, such as a hint for a complete puzzle.
+    pub fn verify(&self) -> ReplayResult<ReplaySummary> {
+        let digest = self.events.iter().fold(
+            initial_digest(self.sudoku.grid(), &self.solution),
+            |d, e| chain(&d, e),
+        );
+
+        if digest != self.digest {
+            return Err(ReplayError::Tampered);
+        }
+
+        let mut session = GameSession::new(self.sudoku.clone(), self.solution.clone())
+            .map_err(ReplayError::InvalidPuzzle)?;
+        let mut solve_time = None;
+        let mut undos = 0;
+        let mut last = Duration::ZERO;
+
+        for (index, event) in self.events.iter().enumerate() {
+            if event.at < last {
+                return Err(ReplayError::OutOfOrder { event: index });
+            }
+
+            last = event.at;
+            let invalid = |error| ReplayError::InvalidMove {
+                event: index,
+                error,
+            };
+
+            match event.action {
+                ReplayAction::Enter {
+                    column,
+                    row,
+                    number,
+                } => {
+                    session.enter(column, row, number).map_err(invalid)?;
+                }
+                ReplayAction::Erase { column, row } => {
+                    session.erase(column, row).map_err(invalid)?;
+                }
+                ReplayAction::Hint => {
+                    session
+                        .hint()
+                        .ok_or(ReplayError::NoEffect { event: index })?;
+                }
+                ReplayAction::Undo => {
+                    session
+                        .undo()
+                        .ok_or(ReplayError::NoEffect { event: index })?;
+                    undos += 1;
+                }
+            }
+
+            if solve_time.is_none() && session.is_complete() {
+                solve_time = Some(event.at);
+            }
+        }
+
+        Ok(ReplaySummary {
+            solve_time,
+            mistakes: session.mistakes(),
+            hints: session.hints(),
+            undos,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+
+    fn replay() -> Replay<DefaultConstraint> {
+        let sudoku = Sudoku::parse("2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,,", DefaultConstraint).unwrap();
+        let solution = SudokuGrid::parse("2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,1").unwrap();
+        Replay::new(sudoku, solution).unwrap()
+    }
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn verifies_a_recorded_game() {
+        let mut replay = replay();
+        replay.record(
+            seconds(3),
+            ReplayAction::Enter {
+                column: 2,
+                row: 3,
+                number: 1,
+            },
+        );
+        replay.record(seconds(4), ReplayAction::Undo);
+        replay.record(seconds(9), ReplayAction::Hint);
+        replay.record(seconds(12), ReplayAction::Hint);
+        let summary = replay.verify().unwrap();
+
+        assert_eq!(Some(seconds(12)), summary.solve_time());
+        assert_eq!(1, summary.mistakes());
+        assert_eq!(2, summary.hints());
+        assert_eq!(1, summary.undos());
+
+        let json = serde_json::to_string(&replay).unwrap();
+        let restored: Replay<DefaultConstraint> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(Ok(summary), restored.verify());
+    }
+
+    #[test]
+    fn detects_tampering() {
+        let mut replay = replay();
+        replay.record(seconds(5), ReplayAction::Hint);
+        replay.events[0].at = seconds(1);
+
+        assert_eq!(Err(ReplayError::Tampered), replay.verify());
+    }
+
+    #[test]
+    fn hmac_matches_reference() {
+        // Test case 2 of RFC 4231.
+        let mac = hmac(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = mac.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        assert_eq!(
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            hex
+        );
+    }
+
+    #[test]
+    fn signatures_detect_recomputed_chains() {
+        let mut replay = replay();
+        replay.record(seconds(5), ReplayAction::Hint);
+        let signature = replay.sign(b"secret");
+
+        assert!(replay.verify_signed(b"secret", &signature).is_ok());
+        assert_eq!(
+            Err(ReplayError::Tampered),
+            replay.verify_signed(b"guess", &signature)
+        );
+
+        // An editor without the key can recompute the chain, but not the
+        // signature.
+        let mut forged = self::replay();
+        forged.record(seconds(1), ReplayAction::Hint);
+
+        assert!(forged.verify().is_ok());
+        assert_eq!(
+            Err(ReplayError::Tampered),
+            forged.verify_signed(b"secret", &signature)
+        );
+    }
+
+    #[test]
+    fn rejects_impossible_histories() {
+        let mut replay = replay();
+        replay.record(seconds(5), ReplayAction::Hint);
+        replay.record(seconds(4), ReplayAction::Hint);
+
+        assert_eq!(Err(ReplayError::OutOfOrder { event: 1 }), replay.verify());
+
+        let mut replay = self::replay();
+        replay.record(seconds(1), ReplayAction::Erase { column: 0, row: 0 });
+
+        assert_eq!(
+            Err(ReplayError::InvalidMove {
+                event: 0,
+                error: SudokuError::GivenCell
+            }),
+            replay.verify()
+        );
+
+        let mut replay = self::replay();
+        replay.record(seconds(1), ReplayAction::Undo);
+
+        assert_eq!(Err(ReplayError::NoEffect { event: 0 }), replay.verify());
+    }
+}
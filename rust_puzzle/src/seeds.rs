@@ -0,0 +1,191 @@
+use crate::constraint::Constraint;
+use crate::error::{SudokuError, SudokuResult};
+use crate::solver::BacktrackingSolver;
+use crate::sudoku_generator::{Generator, Reducer};
+use crate::Sudoku;
+
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use serde::{Deserialize, Serialize};
+
+use std::ops::Range;
+
+// A half-open range of seeds, which can be split into disjoint parts to hand
+// out to independent workers.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub struct SeedRange {
+    start: u64,
+    end: u64,
+}
+
+impl SeedRange {
+    pub fn new(start: u64, end: u64) -> SudokuResult<SeedRange> {
+        if start > end {
+            return Err(SudokuError::InvalidNumber);
+        }
+
+        Ok(SeedRange { start, end })
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn contains(&self, seed: u64) -> bool {
+        self.start <= seed && seed < self.end
+    }
+
+    pub fn seeds(&self) -> Range<u64> {
+        self.start..self.end
+    }
+
+    // Splits the range into `parts` consecutive, disjoint ranges which
+    // together cover it and whose lengths differ by at most one. Parts are
+    // empty if there are fewer seeds than parts.
+    pub fn split(&self, parts: usize) -> Vec<SeedRange> {
+        if parts == 0 {
+            return Vec::new();
+        }
+
+        let parts = parts as u64;
+        let (base, extra) = (self.len() / parts, self.len() % parts);
+        let mut start = self.start;
+
+        (0..parts)
+            .map(|part| {
+                let end = start + base + u64::from(part < extra);
+                let range = SeedRange { start, end };
+                start = end;
+                range
+            })
+            .collect()
+    }
+}
+
+// Generates and reduces the puzzle identified by `seed` and `index`, using
+// only those two numbers as its source of randomness. Every combination uses
+// its own ChaCha stream, so workers given disjoint seeds or indices produce
+// the same puzzles without coordinating. Generation and the default reduction
+// only draw integers from those streams, so the puzzles are also the same on
+// any machine.
+pub fn generate_nth<C>(
+    seed: u64,
+    index: u64,
+    block_width: usize,
+    block_height: usize,
+    constraint: C,
+) -> SudokuResult<Sudoku<C>>
+where
+    C: Constraint + Clone + 'static,
+{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(index);
+    let reducer_seed = rng.gen();
+    let mut sudoku = Generator::new(rng).generate(block_width, block_height, constraint)?;
+    Reducer::new(BacktrackingSolver, ChaCha8Rng::seed_from_u64(reducer_seed)).reduce(&mut sudoku);
+    Ok(sudoku)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+    use crate::SudokuGrid;
+
+    use std::collections::HashSet;
+
+    // The grid with its digits renamed in order of first appearance, which
+    // is the same for puzzles that only differ by relabeling.
+    fn canonical(grid: &SudokuGrid) -> Vec<usize> {
+        let mut labels = Vec::new();
+
+        grid.cells()
+            .iter()
+            .map(|cell| match cell {
+                Some(number) => match labels.iter().position(|label| label == number) {
+                    Some(position) => position + 1,
+                    None => {
+                        labels.push(*number);
+                        labels.len()
+                    }
+                },
+                None => 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn split_covers_the_range() {
+        let range = SeedRange::new(10, 20).unwrap();
+        let parts = range.split(3);
+
+        assert_eq!(
+            vec![(10, 14), (14, 17), (17, 20)],
+            parts
+                .iter()
+                .map(|part| (part.start(), part.end()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(5, range.split(5).iter().filter(|p| p.len() == 2).count());
+        assert_eq!(2, range.split(12).iter().filter(|p| p.is_empty()).count());
+        assert!(range.split(0).is_empty());
+        assert!(SeedRange::new(20, 10).is_err());
+
+        let full = SeedRange::new(0, u64::MAX).unwrap();
+        assert_eq!(u64::MAX, full.split(7).last().unwrap().end());
+    }
+
+    #[test]
+    fn distributed_generation_is_deterministic_and_disjoint() {
+        let workers = SeedRange::new(0, 12).unwrap().split(3);
+        let mut puzzles = HashSet::new();
+
+        for worker in workers {
+            for seed in worker.seeds() {
+                for index in 0..2 {
+                    let sudoku = generate_nth(seed, index, 3, 2, DefaultConstraint).unwrap();
+                    let again = generate_nth(seed, index, 3, 2, DefaultConstraint).unwrap();
+
+                    assert_eq!(sudoku.grid(), again.grid());
+                    assert!(puzzles.insert(canonical(sudoku.grid())));
+                }
+            }
+        }
+
+        assert_eq!(24, puzzles.len());
+    }
+
+    #[test]
+    fn nth_puzzle_matches_reference() {
+        // Pinned output, which must not depend on the machine.
+        let sudoku = generate_nth(42, 1, 3, 2, DefaultConstraint).unwrap();
+        let expected = SudokuGrid::parse(
+            "3x2;\
+             ,1, , ,4,6,\
+            6, , ,2, , ,\
+            4, , , , , ,\
+             , ,2,1, , ,\
+             , ,6,5, ,2,\
+             , , , , , ",
+        )
+        .unwrap();
+
+        assert_eq!(&expected, sudoku.grid());
+    }
+}
@@ -0,0 +1,506 @@
+use crate::constraint::{Constraint, Group};
+use crate::error::{SudokuError, SudokuResult};
+use crate::events::{self, CompletionEvent};
+use crate::{Sudoku, SudokuGrid};
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use std::mem;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum MoveKind {
+    Entry,
+    Erasure,
+    Hint,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Move {
+    column: usize,
+    row: usize,
+    previous: Option<usize>,
+    number: Option<usize>,
+    kind: MoveKind,
+}
+
+impl Move {
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn previous(&self) -> Option<usize> {
+        self.previous
+    }
+
+    pub fn number(&self) -> Option<usize> {
+        self.number
+    }
+
+    pub fn kind(&self) -> MoveKind {
+        self.kind
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub enum HintDetail {
+    // Fills in the cell.
+    #[default]
+    Cell,
+    // Only points out a group of the constraint which contains the cell.
+    Group,
+}
+
+// How generous `request_hint` is. The default gives any number of hints
+// which fill in the cell.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct HintPolicy {
+    limit: Option<usize>,
+    detail: HintDetail,
+}
+
+impl HintPolicy {
+    pub fn new(limit: Option<usize>, detail: HintDetail) -> HintPolicy {
+        HintPolicy { limit, detail }
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn detail(&self) -> HintDetail {
+        self.detail
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Hint {
+    Cell(usize, usize),
+    Group(Group),
+}
+
+// The bookkeeping of a player working on a sudoku: elapsed time, move
+// history, hints, mistakes and completion. A mistake is any entry which
+// differs from the solution. Serializing a running session stores the time
+// elapsed so far, a deserialized session is paused until it is resumed.
+#[derive(Clone, Deserialize)]
+pub struct GameSession<C: Constraint + Clone> {
+    sudoku: Sudoku<C>,
+    givens: SudokuGrid,
+    solution: SudokuGrid,
+    history: Vec<Move>,
+    hints: usize,
+    mistakes: usize,
+    #[serde(default)]
+    hint_policy: HintPolicy,
+    elapsed: Duration,
+    #[serde(skip)]
+    running_since: Option<Instant>,
+    #[serde(skip)]
+    events: Vec<CompletionEvent>,
+}
+
+// The serialized form of a session, which has the same fields as the
+// session except that the timer is stopped at the time of serializing.
+#[derive(Serialize)]
+struct GameSessionRef<'a, C: Constraint + Clone> {
+    sudoku: &'a Sudoku<C>,
+    givens: &'a SudokuGrid,
+    solution: &'a SudokuGrid,
+    history: &'a [Move],
+    hints: usize,
+    mistakes: usize,
+    hint_policy: HintPolicy,
+    elapsed: Duration,
+}
+
+impl<C: Constraint + Clone + Serialize> Serialize for GameSession<C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameSessionRef {
+            sudoku: &self.sudoku,
+            givens: &self.givens,
+            solution: &self.solution,
+            history: &self.history,
+            hints: self.hints,
+            mistakes: self.mistakes,
+            hint_policy: self.hint_policy,
+            elapsed: self.elapsed(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<C: Constraint + Clone> GameSession<C> {
+    // Starts a running session for the given puzzle. The filled cells of the
+    // puzzle are treated as givens, which cannot be changed.
+    pub fn new(sudoku: Sudoku<C>, solution: SudokuGrid) -> SudokuResult<GameSession<C>> {
+        if !sudoku.is_valid_solution(&solution)? {
+            return Err(SudokuError::InvalidSolution);
+        }
+
+        Ok(GameSession {
+            givens: sudoku.grid().clone(),
+            sudoku,
+            solution,
+            history: Vec::new(),
+            hints: 0,
+            mistakes: 0,
+            hint_policy: HintPolicy::default(),
+            elapsed: Duration::ZERO,
+            running_since: Some(Instant::now()),
+            events: Vec::new(),
+        })
+    }
+
+    pub fn sudoku(&self) -> &Sudoku<C> {
+        &self.sudoku
+    }
+
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    pub fn hints(&self) -> usize {
+        self.hints
+    }
+
+    pub fn mistakes(&self) -> usize {
+        self.mistakes
+    }
+
+    pub fn hint_policy(&self) -> HintPolicy {
+        self.hint_policy
+    }
+
+    pub fn set_hint_policy(&mut self, hint_policy: HintPolicy) {
+        self.hint_policy = hint_policy;
+    }
+
+    pub fn is_given(&self, column: usize, row: usize) -> SudokuResult<bool> {
+        Ok(self.givens.get_cell(column, row)?.is_some())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.sudoku.grid().cells() == self.solution.cells()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.running_since.is_none()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        match self.running_since {
+            Some(since) => self.elapsed + since.elapsed(),
+            None => self.elapsed,
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.elapsed += since.elapsed();
+        }
+    }
+
+    // Continues the timer, unless the puzzle is already complete.
+    pub fn resume(&mut self) {
+        if self.running_since.is_none() && !self.is_complete() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    fn apply(
+        &mut self,
+        column: usize,
+        row: usize,
+        number: Option<usize>,
+        kind: MoveKind,
+    ) -> SudokuResult<()> {
+        if self.is_given(column, row)? {
+            return Err(SudokuError::GivenCell);
+        }
+
+        let previous = self.sudoku.grid().get_cell(column, row)?;
+
+        match number {
+            Some(number) => self.sudoku.grid_mut().set_cell(column, row, number)?,
+            None => self.sudoku.grid_mut().clear_cell(column, row)?,
+        }
+
+        self.events.extend(events::completion_events(
+            self.sudoku.grid(),
+            column,
+            row,
+            previous,
+        ));
+        self.history.push(Move {
+            column,
+            row,
+            previous,
+            number,
+            kind,
+        });
+
+        if self.is_complete() {
+            self.pause();
+        }
+
+        Ok(())
+    }
+
+    // Enters a number and returns whether it matches the solution. Wrong
+    // entries are counted as mistakes.
+    pub fn enter(&mut self, column: usize, row: usize, number: usize) -> SudokuResult<bool> {
+        self.apply(column, row, Some(number), MoveKind::Entry)?;
+        let correct = self.solution.get_cell(column, row)? == Some(number);
+
+        if !correct {
+            self.mistakes += 1;
+        }
+
+        Ok(correct)
+    }
+
+    pub fn erase(&mut self, column: usize, row: usize) -> SudokuResult<()> {
+        self.apply(column, row, None, MoveKind::Erasure)
+    }
+
+    fn next_hint_cell(&self) -> Option<(usize, usize)> {
+        let size = self.solution.size();
+        let index = (0..size * size)
+            .find(|&i| self.sudoku.grid().cells()[i] != self.solution.cells()[i])?;
+        Some((index % size, index / size))
+    }
+
+    // Fills the first empty or wrong cell in reading order with its solution
+    // and returns its position, or `None` if the puzzle is complete. Unlike
+    // `request_hint`, this ignores the hint policy.
+    pub fn hint(&mut self) -> Option<(usize, usize)> {
+        let (column, row) = self.next_hint_cell()?;
+        let number = self.solution.get_cell(column, row).unwrap();
+
+        self.apply(column, row, number, MoveKind::Hint).unwrap();
+        self.hints += 1;
+        Some((column, row))
+    }
+
+    // Gives a hint for the cell `hint` would fill, as detailed as the hint
+    // policy allows. Returns `None` if the puzzle is complete or the policy's
+    // limit is used up. Hints which only point out a group count towards the
+    // limit, but do not change the grid.
+    pub fn request_hint(&mut self) -> Option<Hint> {
+        if self
+            .hint_policy
+            .limit
+            .is_some_and(|limit| self.hints >= limit)
+        {
+            return None;
+        }
+
+        match self.hint_policy.detail {
+            HintDetail::Cell => self.hint().map(|(column, row)| Hint::Cell(column, row)),
+            HintDetail::Group => {
+                let cell = self.next_hint_cell()?;
+                let group = self
+                    .sudoku
+                    .constraint()
+                    .get_groups(self.sudoku.grid())
+                    .into_iter()
+                    .find(|group| group.contains(&cell))
+                    .unwrap_or_else(|| vec![cell]);
+                self.hints += 1;
+                Some(Hint::Group(group))
+            }
+        }
+    }
+
+    // Returns the completion events caused by entries and hints since the
+    // last call, in the order they happened. Undoing a move does not revoke
+    // its events.
+    pub fn drain_events(&mut self) -> Vec<CompletionEvent> {
+        mem::take(&mut self.events)
+    }
+
+    // Restarts the game: the grid goes back to the givens, and the history,
+    // hint and mistake counts and the timer start over. The hint policy is
+    // kept.
+    pub fn reset_to_givens(&mut self) {
+        self.sudoku.grid_mut().assign(&self.givens).unwrap();
+        self.history.clear();
+        self.hints = 0;
+        self.mistakes = 0;
+        self.elapsed = Duration::ZERO;
+        self.running_since = Some(Instant::now());
+        self.events.clear();
+    }
+
+    // Reverts the last move and returns it. Mistakes and hints stay counted.
+    pub fn undo(&mut self) -> Option<Move> {
+        let last = self.history.pop()?;
+        let grid = self.sudoku.grid_mut();
+
+        match last.previous {
+            Some(number) => grid.set_cell(last.column, last.row, number).unwrap(),
+            None => grid.clear_cell(last.column, last.row).unwrap(),
+        }
+
+        Some(last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+
+    fn session() -> GameSession<DefaultConstraint> {
+        let sudoku = Sudoku::parse("2x2;1,,,,,,1,,,1,,,,,,1", DefaultConstraint).unwrap();
+        let solution = SudokuGrid::parse("2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,1").unwrap();
+        GameSession::new(sudoku, solution).unwrap()
+    }
+
+    #[test]
+    fn counts_mistakes_and_protects_givens() {
+        let mut session = session();
+
+        assert!(session.enter(1, 0, 2).unwrap());
+        assert!(!session.enter(2, 0, 4).unwrap());
+        assert_eq!(1, session.mistakes());
+        assert_eq!(Err(SudokuError::GivenCell), session.enter(0, 0, 2));
+        assert_eq!(2, session.history().len());
+    }
+
+    #[test]
+    fn reset_keeps_only_the_givens() {
+        let mut session = session();
+        session.enter(1, 0, 4).unwrap();
+        session.hint();
+        session.pause();
+        session.reset_to_givens();
+
+        assert_eq!(4, session.sudoku().grid().count_clues());
+        assert!(session.history().is_empty());
+        assert_eq!((0, 0), (session.hints(), session.mistakes()));
+        assert!(!session.is_paused());
+        assert_eq!(Ok(true), session.is_given(0, 0));
+    }
+
+    #[test]
+    fn undo_restores_previous_content() {
+        let mut session = session();
+        session.enter(1, 0, 3).unwrap();
+        session.enter(1, 0, 2).unwrap();
+
+        assert_eq!(Some(3), session.undo().unwrap().previous());
+        assert_eq!(Some(3), session.sudoku().grid().get_cell(1, 0).unwrap());
+        session.undo();
+        assert_eq!(None, session.sudoku().grid().get_cell(1, 0).unwrap());
+        assert!(session.undo().is_none());
+    }
+
+    #[test]
+    fn hints_complete_the_puzzle() {
+        let mut session = session();
+        session.enter(1, 0, 3).unwrap();
+
+        assert_eq!(Some((1, 0)), session.hint());
+
+        while session.hint().is_some() {}
+
+        assert!(session.is_complete());
+        assert!(session.is_paused());
+        assert_eq!(12, session.hints());
+
+        session.resume();
+        assert!(session.is_paused());
+    }
+
+    #[test]
+    fn hint_policy_limits_and_degrades_hints() {
+        let mut session = session();
+        session.set_hint_policy(HintPolicy::new(Some(2), HintDetail::Group));
+
+        assert_eq!(
+            Some(Hint::Group(vec![(0, 0), (1, 0), (2, 0), (3, 0)])),
+            session.request_hint()
+        );
+        assert_eq!(None, session.sudoku().grid().get_cell(1, 0).unwrap());
+
+        session.set_hint_policy(HintPolicy::new(Some(2), HintDetail::Cell));
+
+        assert_eq!(Some(Hint::Cell(1, 0)), session.request_hint());
+        assert_eq!(None, session.request_hint());
+        assert_eq!(2, session.hints());
+        assert_eq!(Some((2, 0)), session.hint());
+    }
+
+    #[test]
+    fn completion_events_are_queued() {
+        let mut session = session();
+        session.enter(1, 0, 2).unwrap();
+        session.enter(2, 0, 3).unwrap();
+
+        assert!(session.drain_events().is_empty());
+
+        session.enter(3, 0, 4).unwrap();
+
+        assert_eq!(
+            vec![CompletionEvent::RowCompleted(0)],
+            session.drain_events()
+        );
+        assert!(session.drain_events().is_empty());
+
+        while session.hint().is_some() {}
+
+        assert_eq!(
+            Some(&CompletionEvent::GridCompleted),
+            session.drain_events().last()
+        );
+    }
+
+    #[test]
+    fn paused_timer_stands_still() {
+        let mut session = session();
+        session.pause();
+        let elapsed = session.elapsed();
+
+        assert!(session.is_paused());
+        assert_eq!(elapsed, session.elapsed());
+
+        session.resume();
+        assert!(!session.is_paused());
+        assert!(session.elapsed() >= elapsed);
+    }
+
+    #[test]
+    fn restored_sessions_are_paused() {
+        let mut session = session();
+        session.enter(1, 0, 4).unwrap();
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: GameSession<DefaultConstraint> = serde_json::from_str(&json).unwrap();
+
+        assert!(restored.is_paused());
+        assert_eq!(1, restored.mistakes());
+        assert_eq!(session.history(), restored.history());
+        assert!(restored.is_given(0, 0).unwrap());
+    }
+
+    #[test]
+    fn serializing_a_running_session_keeps_its_time() {
+        let mut session = session();
+        session.elapsed = Duration::from_secs(30);
+        session.running_since = Instant::now().checked_sub(Duration::from_secs(60));
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: GameSession<DefaultConstraint> = serde_json::from_str(&json).unwrap();
+
+        assert!(!session.is_paused());
+        assert!(restored.is_paused());
+        assert!(restored.elapsed() >= Duration::from_secs(90));
+        assert!(restored.elapsed() <= session.elapsed());
+    }
+}
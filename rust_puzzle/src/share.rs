@@ -0,0 +1,137 @@
+use crate::constraint::Constraint;
+use crate::error::{SudokuError, SudokuResult};
+use crate::{Sudoku, SudokuGrid};
+
+use rand::Rng;
+
+use serde::{Deserialize, Serialize};
+
+use sha2::{Digest, Sha256};
+
+// A puzzle that can be shared without its solution. It carries a salted
+// SHA-256 commitment of the solution, so a claimed solution can be checked
+// against it without distributing the solution itself. Note that anyone with
+// a solver can still solve the puzzle, the commitment only keeps the answer
+// out of the shared data.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SharedPuzzle<C: Constraint + Clone> {
+    sudoku: Sudoku<C>,
+    salt: [u8; 16],
+    commitment: [u8; 32],
+}
+
+fn commit(salt: &[u8; 16], grid: &SudokuGrid) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update((grid.block_width() as u64).to_le_bytes());
+    hasher.update((grid.block_height() as u64).to_le_bytes());
+
+    for cell in grid.cells() {
+        hasher.update((cell.unwrap_or(0) as u64).to_le_bytes());
+    }
+
+    hasher.finalize().into()
+}
+
+impl<C: Constraint + Clone> SharedPuzzle<C> {
+    // Redacts the given solution of the sudoku with a random salt. Fails with
+    // `SudokuError::InvalidSolution` if the solution does not solve the sudoku.
+    pub fn new(sudoku: Sudoku<C>, solution: &SudokuGrid) -> SudokuResult<SharedPuzzle<C>> {
+        SharedPuzzle::with_salt(sudoku, solution, rand::thread_rng().gen())
+    }
+
+    pub fn with_salt(
+        sudoku: Sudoku<C>,
+        solution: &SudokuGrid,
+        salt: [u8; 16],
+    ) -> SudokuResult<SharedPuzzle<C>> {
+        if solution.block_width() != sudoku.grid().block_width()
+            || solution.block_height() != sudoku.grid().block_height()
+        {
+            return Err(SudokuError::InvalidDimensions);
+        }
+
+        if !sudoku.is_valid_solution(solution)? {
+            return Err(SudokuError::InvalidSolution);
+        }
+
+        Ok(SharedPuzzle {
+            commitment: commit(&salt, solution),
+            sudoku,
+            salt,
+        })
+    }
+
+    pub fn sudoku(&self) -> &Sudoku<C> {
+        &self.sudoku
+    }
+
+    pub fn salt(&self) -> &[u8; 16] {
+        &self.salt
+    }
+
+    pub fn commitment(&self) -> &[u8; 32] {
+        &self.commitment
+    }
+
+    pub fn commitment_hex(&self) -> String {
+        self.commitment
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    // Checks whether the claimed grid is the committed solution.
+    pub fn verify(&self, claimed: &SudokuGrid) -> bool {
+        claimed.block_width() == self.sudoku.grid().block_width()
+            && claimed.block_height() == self.sudoku.grid().block_height()
+            && commit(&self.salt, claimed) == self.commitment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+
+    fn puzzle() -> Sudoku<DefaultConstraint> {
+        Sudoku::parse("2x2;1,,,,,,1,,,1,,,,,,1", DefaultConstraint).unwrap()
+    }
+
+    fn solution() -> SudokuGrid {
+        SudokuGrid::parse("2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,1").unwrap()
+    }
+
+    #[test]
+    fn verifies_only_the_committed_solution() {
+        let shared = SharedPuzzle::new(puzzle(), &solution()).unwrap();
+        let mut other = solution();
+        other.cells_mut().swap(1, 2).unwrap();
+
+        assert!(shared.verify(&solution()));
+        assert!(!shared.verify(&other));
+        assert!(!shared.verify(shared.sudoku().grid()));
+    }
+
+    #[test]
+    fn commitment_depends_on_salt() {
+        let a = SharedPuzzle::with_salt(puzzle(), &solution(), [0; 16]).unwrap();
+        let b = SharedPuzzle::with_salt(puzzle(), &solution(), [1; 16]).unwrap();
+
+        assert_ne!(a.commitment(), b.commitment());
+        assert_eq!(64, a.commitment_hex().len());
+    }
+
+    #[test]
+    fn rejects_wrong_solutions() {
+        let mut wrong = solution();
+        wrong.clear_cell(3, 3).unwrap();
+
+        assert_eq!(
+            Err(SudokuError::InvalidSolution),
+            SharedPuzzle::new(puzzle(), &wrong).map(|_| ())
+        );
+    }
+}
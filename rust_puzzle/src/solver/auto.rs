@@ -0,0 +1,74 @@
+use crate::constraint::Constraint;
+use crate::solver::{ParallelBacktrackingSolver, SmallGridSolver, Solution, Solver};
+use crate::Sudoku;
+
+// Grids at least this large are split across threads when enough of their
+// cells are empty. Below it, spawning threads costs more than it saves.
+const PARALLEL_MIN_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backend {
+    Backtracking,
+    ParallelBacktracking,
+}
+
+// Picks the backend `solve_auto` uses for the given sudoku.
+pub fn select_backend<C>(sudoku: &Sudoku<C>) -> Backend
+where
+    C: Constraint + Clone,
+{
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let empty = size * size - grid.count_clues();
+
+    if size >= PARALLEL_MIN_SIZE && empty * 2 > size * size {
+        Backend::ParallelBacktracking
+    } else {
+        Backend::Backtracking
+    }
+}
+
+// Solves the sudoku with the backend chosen by `select_backend`, so callers
+// need not pick a solver themselves. Backtracking uses the size-specialized
+// search of SmallGridSolver where it applies.
+pub fn solve_auto<C>(sudoku: &Sudoku<C>) -> Solution
+where
+    C: Constraint + Clone + Send + Sync + 'static,
+{
+    match select_backend(sudoku) {
+        Backend::Backtracking => SmallGridSolver.solve(sudoku),
+        Backend::ParallelBacktracking => ParallelBacktrackingSolver::default().solve(sudoku),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+    use crate::solver::BacktrackingSolver;
+
+    #[test]
+    fn selects_parallel_backend_for_large_open_grids() {
+        let small = Sudoku::new_empty(3, 3, DefaultConstraint).unwrap();
+        let large = Sudoku::new_empty(4, 4, DefaultConstraint).unwrap();
+
+        assert_eq!(Backend::Backtracking, select_backend(&small));
+        assert_eq!(Backend::ParallelBacktracking, select_backend(&large));
+    }
+
+    #[test]
+    fn solves_like_backtracking() {
+        let puzzles = [
+            "2x2;1, , , , ,3, , , , ,4, , , , ,2",
+            "2x2;1,1, , , , , , , , , , , , , , ",
+            "3x2; , ,5, , , ,1, , , ,5, , , , , , , , ,4, ,2,6,3,6, , ,1,4, ,4, , , , , ",
+        ];
+
+        for puzzle in puzzles.iter() {
+            let sudoku = Sudoku::parse(puzzle, DefaultConstraint).unwrap();
+            assert_eq!(BacktrackingSolver.solve(&sudoku), solve_auto(&sudoku));
+        }
+    }
+}
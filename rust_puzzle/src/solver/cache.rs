@@ -0,0 +1,454 @@
+use crate::constraint::{ConsecutivePairsConstraint, Constraint, GivenCandidatesConstraint};
+use crate::solver::memory::{MemoryBudget, SolveStats};
+use crate::solver::{Solution, Solver};
+use crate::{Sudoku, SudokuGrid};
+
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+pub const DEFAULT_CACHE_CAPACITY: usize = 1 << 16;
+
+// Minimum number of nodes below a grid for its result to be cached.
+const MIN_CACHED_SUBTREE: usize = 32;
+
+const FILTER_WORDS: usize = 1 << 12;
+
+// The Zobrist hashes are already uniformly distributed, so the table uses
+// them as they are instead of hashing them again.
+#[derive(Default)]
+struct IdentityHasher(u64);
+
+impl Hasher for IdentityHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 << 8) | byte as u64;
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value;
+    }
+}
+
+// Entries keep the cells of their grid, so a hash collision between two
+// different grids is detected instead of returning the wrong result.
+struct Entry {
+    cells: Box<[Option<usize>]>,
+    solution: Solution,
+}
+
+type Entries = HashMap<u64, Entry, BuildHasherDefault<IdentityHasher>>;
+
+// A part of a constraint, as far as the table can tell parts apart. Parts
+// without data are identified by their type, the reducible built-in
+// constraints by a copy of their clues.
+#[derive(PartialEq)]
+enum KeyPart {
+    Stateless(TypeId),
+    Candidates(GivenCandidatesConstraint),
+    ConsecutivePairs(ConsecutivePairsConstraint),
+}
+
+// Identifies the state of a constraint, or returns None if it has parts with
+// data the table cannot compare, such as custom rules.
+fn constraint_key<C>(constraint: &C) -> Option<(TypeId, Vec<KeyPart>)>
+where
+    C: Constraint + 'static,
+{
+    let parts = constraint
+        .to_objects()
+        .into_iter()
+        .map(|object| {
+            if size_of_val(object) == 0 {
+                Some(KeyPart::Stateless((*object).type_id()))
+            } else if let Some(c) = object.downcast_ref::<GivenCandidatesConstraint>() {
+                Some(KeyPart::Candidates(c.clone()))
+            } else {
+                object
+                    .downcast_ref::<ConsecutivePairsConstraint>()
+                    .map(|c| KeyPart::ConsecutivePairs(c.clone()))
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((TypeId::of::<C>(), parts))
+}
+
+struct Table {
+    size: usize,
+    constraint_key: Option<(TypeId, Vec<KeyPart>)>,
+    entries: Entries,
+    filter: Vec<u64>,
+    hits: usize,
+    misses: usize,
+    stats: SolveStats,
+}
+
+// Estimated heap size of a table entry, including the cells of its grid and
+// of a solution.
+fn entry_bytes(cells: &[Option<usize>], solution: &Solution) -> usize {
+    let solution_cells = match solution {
+        Solution::Unique(grid) => grid.cells().len(),
+        _ => 0,
+    };
+
+    size_of::<(u64, Entry)>() + (cells.len() + solution_cells) * size_of::<Option<usize>>()
+}
+
+impl Table {
+    fn new() -> Table {
+        Table {
+            size: 0,
+            constraint_key: None,
+            entries: Entries::default(),
+            filter: vec![0; FILTER_WORDS],
+            hits: 0,
+            misses: 0,
+            stats: SolveStats::default(),
+        }
+    }
+
+    // Results are only kept for the same size and constraint. A constraint
+    // without a key may have changed in any way, so it always empties the
+    // table.
+    fn prepare(&mut self, size: usize, constraint_key: Option<(TypeId, Vec<KeyPart>)>) {
+        if self.size != size {
+            self.size = size;
+            self.clear();
+        }
+
+        if constraint_key.is_none() || self.constraint_key != constraint_key {
+            self.constraint_key = constraint_key;
+            self.clear();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.stats.release_all();
+
+        for word in self.filter.iter_mut() {
+            *word = 0;
+        }
+    }
+
+    // The filter is a single-hash Bloom filter over the stored hashes. It is
+    // small enough to stay in the CPU cache, which makes the common case of
+    // a grid that is not in the table much cheaper than a map lookup.
+    fn filter_bit(hash: u64) -> (usize, u64) {
+        let bit = (hash >> 40) as usize % (FILTER_WORDS * 64);
+        (bit / 64, 1 << (bit % 64))
+    }
+
+    fn get(&self, hash: u64, cells: &[Option<usize>]) -> Option<&Solution> {
+        let (word, mask) = Table::filter_bit(hash);
+
+        if self.filter[word] & mask == 0 {
+            return None;
+        }
+
+        self.entries
+            .get(&hash)
+            .filter(|entry| *entry.cells == *cells)
+            .map(|entry| &entry.solution)
+    }
+
+    fn insert(&mut self, hash: u64, cells: &[Option<usize>], solution: Solution, limits: Limits) {
+        let bytes = entry_bytes(cells, &solution);
+        let budget = limits.budget.map_or(usize::MAX, |budget| budget.bytes());
+
+        if self.entries.len() >= limits.capacity || self.stats.used() + bytes > budget {
+            self.clear();
+            self.stats.degrade();
+        }
+
+        if bytes > budget {
+            return;
+        }
+
+        let (word, mask) = Table::filter_bit(hash);
+        self.filter[word] |= mask;
+        self.entries.insert(
+            hash,
+            Entry {
+                cells: cells.into(),
+                solution,
+            },
+        );
+        self.stats.allocate(bytes);
+    }
+}
+
+// Backtracking solver with a transposition table: the results of larger
+// explored subtrees are stored under the Zobrist hash of their grid, so later
+// solves reaching the same partial grid (as happens when a Reducer checks
+// puzzles which differ by a single clue) can reuse them.
+//
+// Results are only reused for the same constraint. Built-in constraints are
+// compared by their parts and clues, constraints with custom rules or other
+// unknown state empty the table on every solve.
+//
+// With a memory budget, the table is also emptied whenever storing another
+// result would exceed it. Only the entries count towards the budget, the
+// filter in front of them has a fixed size of 32 KiB.
+pub struct CachingSolver {
+    capacity: usize,
+    memory_budget: Option<MemoryBudget>,
+    table: RefCell<Table>,
+}
+
+#[derive(Clone, Copy)]
+struct Limits {
+    capacity: usize,
+    budget: Option<MemoryBudget>,
+}
+
+fn solve_rec<C>(
+    constraint: &C,
+    grid: &mut SudokuGrid,
+    start: usize,
+    limits: Limits,
+    table: &mut Table,
+) -> Solution
+where
+    C: Constraint,
+{
+    let size = grid.size();
+    let index = match (start..size * size).find(|&i| grid.cells()[i].is_none()) {
+        Some(index) => index,
+        None => return Solution::Unique(grid.clone()),
+    };
+
+    let hash = grid.zobrist_hash();
+
+    if let Some(solution) = table.get(hash, grid.cells()) {
+        let solution = solution.clone();
+        table.hits += 1;
+        return solution;
+    }
+
+    table.misses += 1;
+    let misses_before = table.misses;
+    let (column, row) = (index % size, index / size);
+    let mut solution = Solution::Impossible;
+
+    for number in 1..=size {
+        if constraint.check_number(grid, column, row, number) {
+            grid.set_cell(column, row, number).unwrap();
+            let child = solve_rec(constraint, grid, index + 1, limits, table);
+            grid.clear_cell(column, row).unwrap();
+            solution = solution.union(child);
+
+            if solution == Solution::Ambiguous {
+                break;
+            }
+        }
+    }
+
+    // Small subtrees are cheaper to search again than to store.
+    if table.misses - misses_before >= MIN_CACHED_SUBTREE {
+        table.insert(hash, grid.cells(), solution.clone(), limits);
+    }
+
+    solution
+}
+
+impl CachingSolver {
+    pub fn new(capacity: usize) -> CachingSolver {
+        CachingSolver {
+            capacity,
+            memory_budget: None,
+            table: RefCell::new(Table::new()),
+        }
+    }
+
+    pub fn with_memory_budget(mut self, memory_budget: MemoryBudget) -> CachingSolver {
+        self.memory_budget = Some(memory_budget);
+        self
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn memory_budget(&self) -> Option<MemoryBudget> {
+        self.memory_budget
+    }
+
+    // The memory used by the table now and at most so far, and how often it
+    // had to be emptied because it was full.
+    pub fn stats(&self) -> SolveStats {
+        self.table.borrow().stats
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.borrow().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn hits(&self) -> usize {
+        self.table.borrow().hits
+    }
+
+    pub fn misses(&self) -> usize {
+        self.table.borrow().misses
+    }
+
+    pub fn clear(&self) {
+        self.table.borrow_mut().clear();
+    }
+}
+
+impl Default for CachingSolver {
+    fn default() -> CachingSolver {
+        CachingSolver::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl Solver for CachingSolver {
+    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let mut table = self.table.borrow_mut();
+        let mut grid = sudoku.grid().clone();
+        table.prepare(grid.size(), constraint_key(sudoku.constraint()));
+        grid.refresh_zobrist();
+
+        if self.capacity == 0 {
+            table.clear();
+        }
+
+        let limits = Limits {
+            capacity: self.capacity.max(1),
+            budget: self.memory_budget,
+        };
+
+        solve_rec(sudoku.constraint(), &mut grid, 0, limits, &mut table)
+    }
+
+    fn constraint_changed(&self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::constraint::{DefaultConstraint, Rule, RuleSet};
+    use crate::solver::BacktrackingSolver;
+    use crate::utilities::USizeSet;
+
+    #[test]
+    fn agrees_with_backtracking_solver() {
+        let puzzles = [
+            "2x2;,,,,,,,,,,,,,,,",
+            "2x2;1,1,,,,,,,,,,,,,,",
+            "2x2;1,,,,,3,,,,,4,,,,,2",
+            "2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,",
+            "3x2; , ,5, , , ,1, , , ,5, , , , , , , , ,4, ,2,6,3,6, , ,1,4, ,4, , , , , ",
+        ];
+        let solver = CachingSolver::new(64);
+
+        for _ in 0..2 {
+            for puzzle in puzzles.iter() {
+                let sudoku = Sudoku::parse(puzzle, DefaultConstraint).unwrap();
+                assert_eq!(BacktrackingSolver.solve(&sudoku), solver.solve(&sudoku));
+            }
+        }
+
+        assert!(solver.len() <= 64);
+    }
+
+    const PUZZLE: &str =
+        "3x2; , ,5, , , ,1, , , ,5, , , , , , , , ,4, ,2,6,3,6, , ,1,4, ,4, , , , , ";
+
+    #[test]
+    fn similar_puzzles_hit_cache() {
+        let solver = CachingSolver::default();
+        let sudoku = Sudoku::parse(PUZZLE, DefaultConstraint).unwrap();
+        let mut reduced = sudoku.clone();
+        // Once the search has put the 5 back, it is in a known state.
+        reduced.grid_mut().clear_cell(2, 0).unwrap();
+        solver.solve(&sudoku);
+        let misses = solver.misses();
+
+        assert_eq!(0, solver.hits());
+        assert_eq!(BacktrackingSolver.solve(&reduced), solver.solve(&reduced));
+        assert!(solver.hits() > 0);
+        assert!(solver.misses() > misses);
+    }
+
+    #[test]
+    fn memory_budget_bounds_the_table() {
+        let budget = MemoryBudget::new(4 * size_of::<(u64, Entry)>());
+        let solver = CachingSolver::default().with_memory_budget(budget);
+        let sudoku = Sudoku::new_empty(2, 3, DefaultConstraint).unwrap();
+        let unlimited = CachingSolver::default();
+
+        assert_eq!(unlimited.solve(&sudoku), solver.solve(&sudoku));
+        assert!(solver.stats().peak() <= budget.bytes());
+        assert!(solver.stats().is_degraded());
+        assert!(unlimited.stats().peak() > budget.bytes());
+        assert!(!unlimited.stats().is_degraded());
+
+        solver.clear();
+        assert_eq!(0, solver.stats().used());
+    }
+
+    #[test]
+    fn colliding_hashes_do_not_match() {
+        let mut table = Table::new();
+        let limits = Limits {
+            capacity: 4,
+            budget: None,
+        };
+        let grid = SudokuGrid::parse("2x2;1,,,,,,,,,,,,,,,").unwrap();
+        let other = SudokuGrid::parse("2x2;2,,,,,,,,,,,,,,,").unwrap();
+        table.insert(7, grid.cells(), Solution::Ambiguous, limits);
+
+        assert_eq!(Some(&Solution::Ambiguous), table.get(7, grid.cells()));
+        assert_eq!(None, table.get(7, other.cells()));
+    }
+
+    #[test]
+    fn different_rule_sets_do_not_share_results() {
+        let sudoku = Sudoku::parse(PUZZLE, DefaultConstraint).unwrap();
+        let number = match BacktrackingSolver.solve(&sudoku) {
+            Solution::Unique(solution) => solution.get_cell(0, 0).unwrap().unwrap(),
+            _ => panic!("puzzle is not unique"),
+        };
+        let rules = |number| {
+            let mut candidates = GivenCandidatesConstraint::new();
+            candidates.restrict(0, 0, USizeSet::singleton(1, 6, number).unwrap());
+            RuleSet::new(vec![Rule::Default, Rule::Candidates(candidates)])
+        };
+        let solver = CachingSolver::default();
+
+        for number in [number, number % 6 + 1] {
+            let sudoku = Sudoku::new_with_grid(sudoku.grid().clone(), rules(number)).unwrap();
+            assert_eq!(BacktrackingSolver.solve(&sudoku), solver.solve(&sudoku));
+        }
+    }
+
+    #[test]
+    fn clear_empties_table() {
+        let solver = CachingSolver::default();
+        let sudoku = Sudoku::parse(PUZZLE, DefaultConstraint).unwrap();
+        solver.solve(&sudoku);
+
+        assert!(!solver.is_empty());
+        solver.constraint_changed();
+        assert!(solver.is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::constraint::Constraint;
+use crate::solver::search::{self, SearchDecision, SearchVisitor};
+use crate::solver::{Propagation, Solution, Solver};
+use crate::{Sudoku, SudokuGrid};
+
+use std::cell::RefCell;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PruningDecision {
+    Continue,
+    Backtrack,
+}
+
+// Extension point for experimenting with search heuristics. The hook is
+// called at every node of the search with the partial grid, the empty cell
+// that is about to be filled and the numbers the constraint allows there.
+// Removing numbers from the candidates vetoes them, returning Backtrack
+// abandons the node entirely. Numbers the hook adds are ignored, since the
+// constraint does not allow them.
+pub trait PruningHook {
+    fn prune(
+        &mut self,
+        grid: &SudokuGrid,
+        column: usize,
+        row: usize,
+        candidates: &mut Vec<usize>,
+    ) -> PruningDecision;
+}
+
+impl<F> PruningHook for F
+where
+    F: FnMut(&SudokuGrid, usize, usize, &mut Vec<usize>) -> PruningDecision,
+{
+    fn prune(
+        &mut self,
+        grid: &SudokuGrid,
+        column: usize,
+        row: usize,
+        candidates: &mut Vec<usize>,
+    ) -> PruningDecision {
+        self(grid, column, row, candidates)
+    }
+}
+
+struct Pruning<'a, H> {
+    hook: &'a mut H,
+    allowed: Vec<usize>,
+}
+
+impl<H: PruningHook> SearchVisitor for Pruning<'_, H> {
+    fn branch(
+        &mut self,
+        grid: &SudokuGrid,
+        column: usize,
+        row: usize,
+        _depth: usize,
+        candidates: &mut Vec<usize>,
+    ) -> SearchDecision {
+        self.allowed.clone_from(candidates);
+        let decision = self.hook.prune(grid, column, row, candidates);
+        let allowed = &self.allowed;
+        candidates.retain(|number| allowed.contains(number));
+
+        match decision {
+            PruningDecision::Continue => SearchDecision::Continue,
+            PruningDecision::Backtrack => SearchDecision::Backtrack,
+        }
+    }
+}
+
+// Runs the search of BacktrackingSolver, but lets a PruningHook restrict it.
+pub struct PruningSolver<H: PruningHook> {
+    hook: RefCell<H>,
+}
+
+impl<H: PruningHook> PruningSolver<H> {
+    pub fn new(hook: H) -> PruningSolver<H> {
+        PruningSolver {
+            hook: RefCell::new(hook),
+        }
+    }
+
+    pub fn into_hook(self) -> H {
+        self.hook.into_inner()
+    }
+}
+
+impl<H: PruningHook> Solver for PruningSolver<H> {
+    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let mut hook = self.hook.borrow_mut();
+        let mut pruning = Pruning {
+            hook: &mut *hook,
+            allowed: Vec::new(),
+        };
+
+        search::backtrack(sudoku, Propagation::Off, &mut pruning).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+    use crate::solver::BacktrackingSolver;
+
+    fn puzzle() -> Sudoku<DefaultConstraint> {
+        Sudoku::parse("2x2;1,,,,,,2,,,3,,,,,,4", DefaultConstraint).unwrap()
+    }
+
+    fn keep_all(_: &SudokuGrid, _: usize, _: usize, _: &mut Vec<usize>) -> PruningDecision {
+        PruningDecision::Continue
+    }
+
+    #[test]
+    fn hook_without_pruning_matches_backtracking() {
+        let solver = PruningSolver::new(keep_all);
+
+        assert_eq!(BacktrackingSolver.solve(&puzzle()), solver.solve(&puzzle()));
+        assert_eq!(
+            Solution::Ambiguous,
+            solver.solve(&Sudoku::new_empty(2, 2, DefaultConstraint).unwrap())
+        );
+    }
+
+    #[test]
+    fn hook_sees_every_node() {
+        let mut nodes = 0;
+        PruningSolver::new(|_: &SudokuGrid, _, _, _: &mut Vec<usize>| {
+            nodes += 1;
+            PruningDecision::Continue
+        })
+        .solve(&puzzle());
+
+        // One node per empty cell on the path to the unique solution, plus
+        // the dead ends.
+        assert!(nodes >= 12);
+    }
+
+    #[test]
+    fn vetoed_candidates_are_skipped() {
+        let Solution::Unique(solution) = BacktrackingSolver.solve(&puzzle()) else {
+            panic!("puzzle should be unique");
+        };
+        let expected = solution.get_cell(1, 0).unwrap().unwrap();
+        let solver = PruningSolver::new(move |_: &SudokuGrid, column, row, c: &mut Vec<usize>| {
+            if (column, row) == (1, 0) {
+                c.retain(|&n| n != expected);
+            }
+
+            PruningDecision::Continue
+        });
+
+        assert_eq!(Solution::Impossible, solver.solve(&puzzle()));
+    }
+
+    #[test]
+    fn added_candidates_are_ignored() {
+        let solver = PruningSolver::new(|grid: &SudokuGrid, _, _, c: &mut Vec<usize>| {
+            c.extend([0, 1, grid.size() + 1, usize::MAX]);
+            PruningDecision::Continue
+        });
+
+        assert_eq!(BacktrackingSolver.solve(&puzzle()), solver.solve(&puzzle()));
+    }
+
+    #[test]
+    fn backtrack_abandons_node() {
+        let solver = PruningSolver::new(|grid: &SudokuGrid, _, _, _: &mut Vec<usize>| {
+            if grid.count_clues() > 6 {
+                PruningDecision::Backtrack
+            } else {
+                PruningDecision::Continue
+            }
+        });
+
+        assert_eq!(Solution::Impossible, solver.solve(&puzzle()));
+    }
+}
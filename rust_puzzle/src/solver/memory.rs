@@ -0,0 +1,65 @@
+// A limit on the memory a solver may spend on data it keeps besides the
+// search itself, such as a transposition table or a recorded search tree.
+// Solvers measure that data by estimates of its heap size. Once the budget
+// is exhausted they evict or stop recording data instead of growing, so
+// their results stay correct but may take longer or be less detailed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MemoryBudget {
+    bytes: usize,
+}
+
+impl MemoryBudget {
+    pub fn new(bytes: usize) -> MemoryBudget {
+        MemoryBudget { bytes }
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    // How many items of the given size fit into the budget.
+    pub(crate) fn items(&self, item_bytes: usize) -> usize {
+        self.bytes / item_bytes.max(1)
+    }
+}
+
+// Memory used by the auxiliary data of a solver, in bytes, and how often it
+// had to give up data because of its budget or capacity.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SolveStats {
+    used: usize,
+    peak: usize,
+    degradations: usize,
+}
+
+impl SolveStats {
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn peak(&self) -> usize {
+        self.peak
+    }
+
+    // The number of times data was evicted or not recorded.
+    pub fn degradations(&self) -> usize {
+        self.degradations
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degradations > 0
+    }
+
+    pub(crate) fn allocate(&mut self, bytes: usize) {
+        self.used += bytes;
+        self.peak = self.peak.max(self.used);
+    }
+
+    pub(crate) fn release_all(&mut self) {
+        self.used = 0;
+    }
+
+    pub(crate) fn degrade(&mut self) {
+        self.degradations += 1;
+    }
+}
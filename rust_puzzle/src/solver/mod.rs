@@ -3,7 +3,37 @@ use std::backtrace;
 use crate::constraint::Constraint;
 use crate::{Sudoku, SudokuGrid};
 
+use rand::Rng;
+
+pub mod auto;
+pub mod cache;
+pub mod hook;
+pub mod memory;
+pub mod parallel;
+pub mod progress;
+pub mod propagation;
+#[cfg(feature = "sat")]
+pub mod sat;
+mod search;
+pub mod session;
+pub mod small;
 pub mod strategy;
+pub mod tree;
+pub mod unsat;
+
+pub use auto::{select_backend, solve_auto, Backend};
+pub use cache::CachingSolver;
+pub use hook::{PruningDecision, PruningHook, PruningSolver};
+pub use memory::{MemoryBudget, SolveStats};
+pub use parallel::ParallelBacktrackingSolver;
+pub use progress::{ObservedSolver, ObserverDecision, SolveObserver, SolveProgress};
+pub use propagation::{CandidateHeatmap, PropagatingSolver, Propagation};
+#[cfg(feature = "sat")]
+pub use sat::SatSolver;
+pub use session::SolverSession;
+pub use small::SmallGridSolver;
+pub use tree::{NodeOutcome, SearchNode, SearchTree, SearchTreeRecorder};
+pub use unsat::{explain_unsat, explain_unsat_rules, CellOrRuleRef};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Solution {
@@ -36,66 +66,138 @@ pub trait Solver {
     fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
     where
         C: Constraint + Clone + 'static;
+
+    // Called by the Reducer before and after it modifies the constraint of
+    // the sudoku it is reducing, for solvers which cache results.
+    fn constraint_changed(&self) {}
 }
 
 #[derive(Clone)]
 pub struct BacktrackingSolver;
 
-impl BacktrackingSolver {
-    fn solve_rec<C>(sudoku: &mut Sudoku<C>, column: usize, row: usize) -> Solution
+impl Solver for BacktrackingSolver {
+    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
     where
         C: Constraint + Clone + 'static,
     {
-        let size = sudoku.grid().size();
-        let last_cell = row == size;
+        search::backtrack(sudoku, Propagation::Off, &mut ()).unwrap()
+    }
+}
 
-        if last_cell {
-            return Solution::Unique(sudoku.grid().clone());
-        }
+impl BacktrackingSolver {
+    // The same search, with the given propagation between branch decisions.
+    pub fn with_propagation(self, propagation: Propagation) -> PropagatingSolver {
+        PropagatingSolver::new().with_propagation(propagation)
+    }
+}
 
-        let next_column = (column + 1) % size;
-        let next_row = if next_column == 0 { row + 1 } else { row };
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolutionCountEstimate {
+    mean: f64,
+    standard_error: f64,
+    samples: usize,
+}
 
-        if sudoku.grid().get_cell(column, row).unwrap().is_some() {
-            BacktrackingSolver::solve_rec(sudoku, next_column, next_row)
-        } else {
-            let mut solution = Solution::Impossible;
-            for number in 1..=size {
-                if sudoku.is_valid_number(column, row, number).unwrap() {
-                    sudoku.grid_mut().set_cell(column, row, number).unwrap();
-                    let next_solution =
-                        BacktrackingSolver::solve_rec(sudoku, next_column, next_row);
-                    sudoku.grid_mut().clear_cell(column, row).unwrap();
-                    solution = solution.union(next_solution);
-
-                    if solution == Solution::Ambiguous {
-                        break;
-                    }
-                }
+impl SolutionCountEstimate {
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn standard_error(&self) -> f64 {
+        self.standard_error
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+}
+
+// Knuth's random probing: follows one random path through the search tree
+// and returns the product of the branching factors along it if the path
+// ends in a solution, or zero otherwise. The expected value of a probe is
+// the exact number of solutions.
+fn probe<C, R>(sudoku: &mut Sudoku<C>, rng: &mut R) -> f64
+where
+    C: Constraint + Clone + 'static,
+    R: Rng,
+{
+    let size = sudoku.grid().size();
+    let mut weight = 1.0;
+    let mut candidates = Vec::with_capacity(size);
+
+    for row in 0..size {
+        for column in 0..size {
+            if sudoku.grid().get_cell(column, row).unwrap().is_some() {
+                continue;
             }
 
-            solution
+            candidates.clear();
+            candidates.extend(
+                (1..=size).filter(|&number| sudoku.is_valid_number(column, row, number).unwrap()),
+            );
+
+            if candidates.is_empty() {
+                return 0.0;
+            }
+
+            let number = candidates[rng.gen_range(0..candidates.len())];
+            sudoku.grid_mut().set_cell(column, row, number).unwrap();
+            weight *= candidates.len() as f64;
         }
     }
 
-    fn solve<C>(sudoku: &mut Sudoku<C>) -> Solution
-    where
-        C: Constraint + Clone + 'static,
-    {
-        BacktrackingSolver::solve_rec(sudoku, 0, 0)
-    }
+    weight
 }
 
-impl Solver for BacktrackingSolver {
-    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
-    where
-        C: Constraint + Clone + 'static,
-    {
+pub fn estimate_solution_count_with_rng<C, R>(
+    sudoku: &Sudoku<C>,
+    samples: usize,
+    rng: &mut R,
+) -> SolutionCountEstimate
+where
+    C: Constraint + Clone + 'static,
+    R: Rng,
+{
+    if samples == 0 || !sudoku.is_valid() {
+        return SolutionCountEstimate {
+            mean: 0.0,
+            standard_error: 0.0,
+            samples,
+        };
+    }
+
+    let mut sum = 0.0;
+    let mut sum_of_squares = 0.0;
+
+    for _ in 0..samples {
         let mut clone = sudoku.clone();
-        BacktrackingSolver::solve(&mut clone)
+        let value = probe(&mut clone, rng);
+        sum += value;
+        sum_of_squares += value * value;
+    }
+
+    let n = samples as f64;
+    let mean = sum / n;
+    let variance = if samples > 1 {
+        ((sum_of_squares - n * mean * mean) / (n - 1.0)).max(0.0)
+    } else {
+        0.0
+    };
+
+    SolutionCountEstimate {
+        mean,
+        standard_error: (variance / n).sqrt(),
+        samples,
     }
 }
 
+pub fn estimate_solution_count<C>(sudoku: &Sudoku<C>, samples: usize) -> SolutionCountEstimate
+where
+    C: Constraint + Clone + 'static,
+{
+    estimate_solution_count_with_rng(sudoku, samples, &mut rand::thread_rng())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,6 +207,9 @@ mod tests {
         KingsMoveConstraint, KnightsMoveConstraint,
     };
 
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
     fn test_solves_correctly<C>(puzzle: &str, solution: &str, constraint: C)
     where
         C: Constraint + Clone + 'static,
@@ -319,4 +424,41 @@ mod tests {
             CompositeConstraint::new(DefaultConstraint, constraint),
         );
     }
+
+    #[test]
+    fn estimate_solution_count_of_empty_4x4() {
+        // An empty 4x4 sudoku has exactly 288 solutions.
+        let sudoku = Sudoku::new_empty(2, 2, DefaultConstraint).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(42);
+        let estimate = estimate_solution_count_with_rng(&sudoku, 2000, &mut rng);
+
+        assert_eq!(2000, estimate.samples());
+        assert!((estimate.mean() - 288.0).abs() < 288.0 * 0.1);
+        assert!(estimate.standard_error() > 0.0);
+    }
+
+    #[test]
+    fn estimate_solution_count_of_unique_sudoku() {
+        let sudoku = Sudoku::parse(
+            "2x2;\
+            1,2,3,4,\
+            3,4,1,2,\
+            2,1,4,3,\
+            4,3,2,",
+            DefaultConstraint,
+        )
+        .unwrap();
+        let estimate = estimate_solution_count(&sudoku, 10);
+
+        assert_eq!(1.0, estimate.mean());
+        assert_eq!(0.0, estimate.standard_error());
+    }
+
+    #[test]
+    fn estimate_solution_count_of_invalid_sudoku() {
+        let sudoku = Sudoku::parse("2x2;1,1,,,,,,,,,,,,,,", DefaultConstraint).unwrap();
+        let estimate = estimate_solution_count(&sudoku, 10);
+
+        assert_eq!(0.0, estimate.mean());
+    }
 }
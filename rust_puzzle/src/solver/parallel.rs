@@ -0,0 +1,122 @@
+use crate::constraint::Constraint;
+use crate::solver::{BacktrackingSolver, Solution, Solver};
+use crate::Sudoku;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// Backtracking solver which splits the options of the first empty cell
+// across several threads. Only worthwhile for large grids, where a single
+// solve takes long enough to outweigh spawning the threads.
+#[derive(Clone, Copy, Debug)]
+pub struct ParallelBacktrackingSolver {
+    threads: usize,
+}
+
+impl Default for ParallelBacktrackingSolver {
+    fn default() -> ParallelBacktrackingSolver {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        ParallelBacktrackingSolver::new(threads)
+    }
+}
+
+impl ParallelBacktrackingSolver {
+    pub fn new(threads: usize) -> ParallelBacktrackingSolver {
+        ParallelBacktrackingSolver {
+            threads: threads.max(1),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    // Not an implementation of Solver, since the constraint has to be shared
+    // between threads.
+    pub fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + Send + Sync + 'static,
+    {
+        let size = sudoku.grid().size();
+        let (column, row) = match sudoku.grid().empty_cells().next() {
+            Some(cell) if self.threads > 1 => cell,
+            _ => return BacktrackingSolver.solve(sudoku),
+        };
+
+        let options = (1..=size)
+            .filter(|&number| sudoku.is_valid_number(column, row, number).unwrap())
+            .collect::<Vec<_>>();
+        let ambiguous = AtomicBool::new(false);
+        let solution = Mutex::new(Solution::Impossible);
+
+        thread::scope(|scope| {
+            for offset in 0..self.threads.min(options.len()) {
+                let (options, ambiguous, solution) = (&options, &ambiguous, &solution);
+
+                scope.spawn(move || {
+                    for &number in options.iter().skip(offset).step_by(self.threads) {
+                        if ambiguous.load(Ordering::Relaxed) {
+                            return;
+                        }
+
+                        let mut branch = sudoku.clone();
+                        branch.grid_mut().set_cell(column, row, number).unwrap();
+                        let branch_solution = BacktrackingSolver.solve(&branch);
+                        let mut solution = solution.lock().unwrap();
+                        let union = solution.clone().union(branch_solution);
+
+                        if union == Solution::Ambiguous {
+                            ambiguous.store(true, Ordering::Relaxed);
+                        }
+
+                        *solution = union;
+                    }
+                });
+            }
+        });
+
+        solution.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+
+    #[test]
+    fn agrees_with_backtracking_solver() {
+        let puzzles = [
+            "2x2;1, , , , ,3, , , , ,4, , , , ,2",
+            "2x2;1,2, , ,3, , , , , , , , , , , ",
+            "2x2;1,1, , , , , , , , , , , , , , ",
+            "3x2; , ,5, , , ,1, , , ,5, , , , , , , , ,4, ,2,6,3,6, , ,1,4, ,4, , , , , ",
+        ];
+
+        for puzzle in puzzles.iter() {
+            let sudoku = Sudoku::parse(puzzle, DefaultConstraint).unwrap();
+            let expected = BacktrackingSolver.solve(&sudoku);
+
+            for threads in 1..=4 {
+                let solver = ParallelBacktrackingSolver::new(threads);
+                assert_eq!(expected, solver.solve(&sudoku), "{} threads", threads);
+            }
+        }
+    }
+
+    #[test]
+    fn full_grid() {
+        let sudoku =
+            Sudoku::parse("2x2;1,2,3,4,3,4,1,2,2,3,4,1,4,1,2,3", DefaultConstraint).unwrap();
+        let solver = ParallelBacktrackingSolver::new(4);
+
+        assert_eq!(
+            Solution::Unique(sudoku.grid().clone()),
+            solver.solve(&sudoku)
+        );
+        assert_eq!(1, ParallelBacktrackingSolver::new(0).threads());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::constraint::Constraint;
+use crate::solver::search::{self, SearchDecision, SearchVisitor};
+use crate::solver::{Propagation, Solution};
+use crate::{Sudoku, SudokuGrid};
+
+use std::cell::RefCell;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ObserverDecision {
+    Continue,
+    Abort,
+}
+
+// A snapshot of a running search. The depth counts the cells filled by the
+// search so far, the best grid is the deepest partial assignment reached.
+#[derive(Clone, Copy, Debug)]
+pub struct SolveProgress<'a> {
+    nodes: u64,
+    depth: usize,
+    max_depth: usize,
+    best: &'a SudokuGrid,
+}
+
+impl<'a> SolveProgress<'a> {
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn best(&self) -> &'a SudokuGrid {
+        self.best
+    }
+}
+
+// Called periodically during a solve, e.g. to update a progress display or
+// to abort a search which takes too long.
+pub trait SolveObserver {
+    fn observe(&mut self, progress: &SolveProgress<'_>) -> ObserverDecision;
+}
+
+impl<F> SolveObserver for F
+where
+    F: FnMut(&SolveProgress<'_>) -> ObserverDecision,
+{
+    fn observe(&mut self, progress: &SolveProgress<'_>) -> ObserverDecision {
+        self(progress)
+    }
+}
+
+struct Observation<'a, O> {
+    observer: &'a mut O,
+    interval: u64,
+    nodes: u64,
+    max_depth: usize,
+    best: SudokuGrid,
+}
+
+impl<O: SolveObserver> SearchVisitor for Observation<'_, O> {
+    fn branch(
+        &mut self,
+        grid: &SudokuGrid,
+        _column: usize,
+        _row: usize,
+        depth: usize,
+        _candidates: &mut Vec<usize>,
+    ) -> SearchDecision {
+        self.nodes += 1;
+
+        if depth > self.max_depth {
+            self.max_depth = depth;
+            self.best.assign(grid).unwrap();
+        }
+
+        if self.nodes.is_multiple_of(self.interval) {
+            let progress = SolveProgress {
+                nodes: self.nodes,
+                depth,
+                max_depth: self.max_depth,
+                best: &self.best,
+            };
+
+            if self.observer.observe(&progress) == ObserverDecision::Abort {
+                return SearchDecision::Abort;
+            }
+        }
+
+        SearchDecision::Continue
+    }
+}
+
+// Runs the search of BacktrackingSolver and reports to an observer every
+// `interval` nodes. Not an implementation of Solver, since an aborted search
+// has no solution.
+pub struct ObservedSolver<O: SolveObserver> {
+    observer: RefCell<O>,
+    interval: u64,
+}
+
+impl<O: SolveObserver> ObservedSolver<O> {
+    pub fn new(observer: O, interval: u64) -> ObservedSolver<O> {
+        ObservedSolver {
+            observer: RefCell::new(observer),
+            interval: interval.max(1),
+        }
+    }
+
+    pub fn into_observer(self) -> O {
+        self.observer.into_inner()
+    }
+
+    // Returns `None` if the observer aborted the search.
+    pub fn solve<C>(&self, sudoku: &Sudoku<C>) -> Option<Solution>
+    where
+        C: Constraint + Clone,
+    {
+        let mut observer = self.observer.borrow_mut();
+        let mut observation = Observation {
+            observer: &mut *observer,
+            interval: self.interval,
+            nodes: 0,
+            max_depth: 0,
+            best: sudoku.grid().clone(),
+        };
+
+        search::backtrack(sudoku, Propagation::Off, &mut observation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+    use crate::solver::{BacktrackingSolver, Solver};
+
+    fn puzzle() -> Sudoku<DefaultConstraint> {
+        Sudoku::parse("2x2;1,,,,,,2,,,3,,,,,,4", DefaultConstraint).unwrap()
+    }
+
+    #[test]
+    fn reports_every_interval() {
+        let mut reports = Vec::new();
+        let solution = ObservedSolver::new(
+            |progress: &SolveProgress<'_>| {
+                reports.push((progress.nodes(), progress.max_depth()));
+                ObserverDecision::Continue
+            },
+            2,
+        )
+        .solve(&puzzle());
+
+        assert_eq!(Some(BacktrackingSolver.solve(&puzzle())), solution);
+        assert!(!reports.is_empty());
+        assert!(reports.iter().all(|&(nodes, _)| nodes % 2 == 0));
+        assert!(reports.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+
+    #[test]
+    fn observer_can_abort() {
+        let solver = ObservedSolver::new(
+            |progress: &SolveProgress<'_>| {
+                if progress.nodes() >= 5 {
+                    ObserverDecision::Abort
+                } else {
+                    ObserverDecision::Continue
+                }
+            },
+            1,
+        );
+        let empty = Sudoku::new_empty(3, 3, DefaultConstraint).unwrap();
+
+        assert_eq!(None, solver.solve(&empty));
+    }
+
+    #[test]
+    fn best_grid_is_deepest_assignment() {
+        let solver = ObservedSolver::new(
+            |progress: &SolveProgress<'_>| {
+                let filled = progress.best().count_clues();
+                assert_eq!(puzzle().grid().count_clues() + progress.max_depth(), filled);
+                ObserverDecision::Continue
+            },
+            1,
+        );
+
+        assert!(solver.solve(&puzzle()).is_some());
+    }
+}
@@ -0,0 +1,451 @@
+use crate::constraint::{Constraint, Group};
+use crate::error::{SudokuError, SudokuResult};
+use crate::solver::search;
+use crate::solver::{Solution, Solver};
+use crate::utilities::Grid;
+use crate::Sudoku;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+
+// How much the backtracking search deduces between branch decisions.
+// Singles fills in naked and hidden singles, LockedCandidates also
+// eliminates locked candidates. Both are only derived from groups with as
+// many cells as the grid has numbers, which must contain every number
+// exactly once.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Propagation {
+    #[default]
+    Off,
+    Singles,
+    LockedCandidates,
+}
+
+// The state of a node after propagation. Branch holds the candidates of
+// every cell in row-major order, which are empty for filled cells.
+pub(crate) enum Propagated {
+    Contradiction,
+    Solved,
+    Branch(Vec<Vec<usize>>),
+}
+
+// The number of candidates left for every cell once propagation reaches a
+// fixed point, where filled cells count as one. Low counts show where a
+// puzzle is tight, high counts where it is loose.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct CandidateHeatmap {
+    counts: Grid<usize>,
+}
+
+impl CandidateHeatmap {
+    pub fn counts(&self) -> &Grid<usize> {
+        &self.counts
+    }
+
+    pub fn count(&self, column: usize, row: usize) -> SudokuResult<usize> {
+        self.counts.get(column, row).copied()
+    }
+
+    // The entropy in bits of a uniform choice among the cell's candidates.
+    pub fn entropy(&self, column: usize, row: usize) -> SudokuResult<f64> {
+        Ok((self.count(column, row)? as f64).log2())
+    }
+
+    pub fn total_entropy(&self) -> f64 {
+        self.counts
+            .cells()
+            .iter()
+            .map(|&count| (count as f64).log2())
+            .sum()
+    }
+}
+
+// BacktrackingSolver with propagation between branch decisions, see
+// Propagation. With propagation it branches on the cell with the fewest
+// candidates. Like BacktrackingSolver it works for any constraint, since
+// candidates only come from is_valid_number.
+#[derive(Clone, Copy, Debug)]
+pub struct PropagatingSolver {
+    propagation: Propagation,
+}
+
+impl Default for PropagatingSolver {
+    fn default() -> PropagatingSolver {
+        PropagatingSolver::new()
+    }
+}
+
+impl PropagatingSolver {
+    pub fn new() -> PropagatingSolver {
+        PropagatingSolver {
+            propagation: Propagation::LockedCandidates,
+        }
+    }
+
+    pub fn with_propagation(self, propagation: Propagation) -> PropagatingSolver {
+        PropagatingSolver { propagation }
+    }
+
+    pub fn with_locked_candidates(self, locked_candidates: bool) -> PropagatingSolver {
+        if locked_candidates {
+            self.with_propagation(Propagation::LockedCandidates)
+        } else {
+            self.with_propagation(Propagation::Singles)
+        }
+    }
+
+    pub fn propagation(&self) -> Propagation {
+        self.propagation
+    }
+
+    pub fn locked_candidates(&self) -> bool {
+        self.propagation == Propagation::LockedCandidates
+    }
+
+    // Propagates without branching and counts the candidates left. Fails
+    // with UnsatisfiableConstraint if propagation finds a contradiction.
+    pub fn candidate_heatmap<C>(&self, sudoku: &Sudoku<C>) -> SudokuResult<CandidateHeatmap>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        if !sudoku.is_valid() {
+            return Err(SudokuError::UnsatisfiableConstraint);
+        }
+
+        let mut clone = sudoku.clone();
+        let size = clone.grid().size();
+        let (houses, intersections) = houses(&clone, self.propagation);
+        let candidates = match propagate(&mut clone, &houses, &intersections, &mut Vec::new()) {
+            Propagated::Contradiction => return Err(SudokuError::UnsatisfiableConstraint),
+            Propagated::Solved => vec![Vec::new(); size * size],
+            Propagated::Branch(candidates) => candidates,
+        };
+        let counts = candidates
+            .iter()
+            .map(|cell_candidates| cell_candidates.len().max(1))
+            .collect();
+
+        Ok(CandidateHeatmap {
+            counts: Grid::from_cells(size, counts).unwrap(),
+        })
+    }
+}
+
+// The houses propagation derives singles from, and the intersections of
+// houses locked candidates are derived from. Both are empty without
+// propagation.
+pub(crate) fn houses<C>(
+    sudoku: &Sudoku<C>,
+    propagation: Propagation,
+) -> (Vec<Group>, Vec<(usize, usize)>)
+where
+    C: Constraint + Clone,
+{
+    if propagation == Propagation::Off {
+        return (Vec::new(), Vec::new());
+    }
+
+    let size = sudoku.grid().size();
+    let houses: Vec<Group> = sudoku
+        .constraint()
+        .get_groups(sudoku.grid())
+        .into_iter()
+        .filter(|group| group.len() == size)
+        .collect();
+    let intersections = if propagation == Propagation::LockedCandidates {
+        intersections(&houses)
+    } else {
+        Vec::new()
+    };
+
+    (houses, intersections)
+}
+
+// Fills in singles and eliminates locked candidates until neither finds
+// anything new. The filled cells are added to `placed`, so the caller can
+// clear them again.
+pub(crate) fn propagate<C>(
+    sudoku: &mut Sudoku<C>,
+    houses: &[Group],
+    intersections: &[(usize, usize)],
+    placed: &mut Vec<(usize, usize)>,
+) -> Propagated
+where
+    C: Constraint + Clone,
+{
+    let size = sudoku.grid().size();
+    let mut eliminated = HashSet::new();
+
+    loop {
+        let mut candidates = vec![Vec::new(); size * size];
+
+        if sudoku.grid().is_full() {
+            return Propagated::Solved;
+        }
+
+        for (column, row) in sudoku.grid().empty_cells() {
+            let cell_candidates: Vec<usize> = (1..=size)
+                .filter(|&number| !eliminated.contains(&(column, row, number)))
+                .filter(|&number| sudoku.is_valid_number(column, row, number).unwrap())
+                .collect();
+
+            if cell_candidates.is_empty() {
+                return Propagated::Contradiction;
+            }
+
+            candidates[row * size + column] = cell_candidates;
+        }
+
+        let mut singles = Vec::new();
+
+        for (index, cell_candidates) in candidates.iter().enumerate() {
+            if cell_candidates.len() == 1 {
+                singles.push((index % size, index / size, cell_candidates[0]));
+            }
+        }
+
+        if singles.is_empty() {
+            match hidden_singles(sudoku, houses, &candidates) {
+                Some(hidden) => singles = hidden,
+                None => return Propagated::Contradiction,
+            }
+        }
+
+        if !singles.is_empty() {
+            for (column, row, number) in singles {
+                if sudoku.grid().get_cell(column, row).unwrap() == Some(number) {
+                    continue;
+                }
+
+                // Two singles of the same round may contradict each other.
+                if sudoku.grid().get_cell(column, row).unwrap().is_some()
+                    || !sudoku.is_valid_number(column, row, number).unwrap()
+                {
+                    return Propagated::Contradiction;
+                }
+
+                sudoku.grid_mut().set_cell(column, row, number).unwrap();
+                placed.push((column, row));
+            }
+
+            continue;
+        }
+
+        let eliminations = locked_candidates(size, houses, intersections, &candidates);
+        let before = eliminated.len();
+        eliminated.extend(eliminations);
+
+        if eliminated.len() > before {
+            continue;
+        }
+
+        return Propagated::Branch(candidates);
+    }
+}
+
+// Numbers which can only go into one cell of a house. Returns None if a
+// number missing from a house has no cell left in it.
+fn hidden_singles<C>(
+    sudoku: &Sudoku<C>,
+    houses: &[Group],
+    candidates: &[Vec<usize>],
+) -> Option<Vec<(usize, usize, usize)>>
+where
+    C: Constraint + Clone,
+{
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let mut singles = Vec::new();
+
+    for house in houses {
+        for number in 1..=size {
+            if house
+                .iter()
+                .any(|&(column, row)| grid.get_cell(column, row).unwrap() == Some(number))
+            {
+                continue;
+            }
+
+            let mut cells = house
+                .iter()
+                .filter(|&&(column, row)| candidates[row * size + column].contains(&number));
+
+            match (cells.next(), cells.next()) {
+                (None, _) => return None,
+                (Some(&(column, row)), None) => singles.push((column, row, number)),
+                _ => {}
+            }
+        }
+    }
+
+    Some(singles)
+}
+
+// If all candidates for a number in one house lie in its intersection with
+// another house, the number cannot go into the rest of the other house.
+fn locked_candidates(
+    size: usize,
+    houses: &[Group],
+    intersections: &[(usize, usize)],
+    candidates: &[Vec<usize>],
+) -> Vec<(usize, usize, usize)> {
+    let mut eliminations = Vec::new();
+
+    for &(a, b) in intersections {
+        for number in 1..=size {
+            let has_candidate =
+                |&(column, row): &(usize, usize)| candidates[row * size + column].contains(&number);
+            let mut in_a = houses[a]
+                .iter()
+                .filter(|cell| has_candidate(cell))
+                .peekable();
+
+            if in_a.peek().is_none() || !in_a.all(|cell| houses[b].contains(cell)) {
+                continue;
+            }
+
+            eliminations.extend(
+                houses[b]
+                    .iter()
+                    .filter(|cell| !houses[a].contains(cell) && has_candidate(cell))
+                    .map(|&(column, row)| (column, row, number)),
+            );
+        }
+    }
+
+    eliminations
+}
+
+// Ordered pairs of distinct houses which share more than one cell.
+fn intersections(houses: &[Group]) -> Vec<(usize, usize)> {
+    let mut intersections = Vec::new();
+
+    for (a, house_a) in houses.iter().enumerate() {
+        for (b, house_b) in houses.iter().enumerate() {
+            let shared = house_a.iter().filter(|cell| house_b.contains(cell)).count();
+
+            if a != b && shared > 1 && shared < house_b.len() {
+                intersections.push((a, b));
+            }
+        }
+    }
+
+    intersections
+}
+
+impl Solver for PropagatingSolver {
+    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + 'static,
+    {
+        // Unlike plain backtracking, propagation never checks the clues.
+        if !sudoku.is_valid() {
+            return Solution::Impossible;
+        }
+
+        search::backtrack(sudoku, self.propagation, &mut ()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{CompositeConstraint, DefaultConstraint, KnightsMoveConstraint};
+    use crate::solver::BacktrackingSolver;
+
+    fn classic() -> Sudoku<DefaultConstraint> {
+        Sudoku::parse(
+            "3x3;\
+             , , , ,8,1, , , ,\
+             , ,2, , ,7,8, , ,\
+             ,5,3, , , ,1,7, ,\
+            3,7, , , , , , , ,\
+            6, , , , , , , ,3,\
+             , , , , , , ,2,4,\
+             ,6,9, , , ,2,3, ,\
+             , ,5,9, , ,4, , ,\
+             , , ,6,5, , , , ",
+            DefaultConstraint,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn propagation_matches_backtracking() {
+        let expected = BacktrackingSolver.solve(&classic());
+
+        assert!(matches!(expected, Solution::Unique(_)));
+        assert_eq!(expected, PropagatingSolver::new().solve(&classic()));
+        assert_eq!(
+            expected,
+            PropagatingSolver::new()
+                .with_locked_candidates(false)
+                .solve(&classic())
+        );
+
+        for propagation in [
+            Propagation::Off,
+            Propagation::Singles,
+            Propagation::LockedCandidates,
+        ] {
+            let solver = BacktrackingSolver.with_propagation(propagation);
+
+            assert_eq!(propagation, solver.propagation());
+            assert_eq!(expected, solver.solve(&classic()));
+        }
+    }
+
+    #[test]
+    fn propagation_detects_ambiguous_and_impossible_puzzles() {
+        let solver = PropagatingSolver::new();
+        let empty = Sudoku::new_empty(2, 2, DefaultConstraint).unwrap();
+        let impossible = Sudoku::parse("2x2;1,2,3,,,,,4,,,,,,,,", DefaultConstraint).unwrap();
+        let invalid = Sudoku::parse("2x2;1,1,,,,,,,,,,,,,,", DefaultConstraint).unwrap();
+
+        assert_eq!(Solution::Ambiguous, solver.solve(&empty));
+        assert_eq!(Solution::Impossible, solver.solve(&impossible));
+        assert_eq!(Solution::Impossible, solver.solve(&invalid));
+    }
+
+    #[test]
+    fn heatmap_counts_remaining_candidates() {
+        let solver = PropagatingSolver::new();
+        let solution = match solver.solve(&classic()) {
+            Solution::Unique(grid) => grid,
+            _ => panic!("classic sudoku should have a unique solution"),
+        };
+        let solved = classic().clone_with_grid(solution).unwrap();
+        let solved = solver.candidate_heatmap(&solved).unwrap();
+        let empty = Sudoku::new_empty(2, 2, DefaultConstraint).unwrap();
+        let open = solver.candidate_heatmap(&empty).unwrap();
+        let partial = Sudoku::parse("2x2;1,2,,,,,,,,,,,,,,", DefaultConstraint).unwrap();
+        let partial = solver.candidate_heatmap(&partial).unwrap();
+        let invalid = Sudoku::parse("2x2;1,1,,,,,,,,,,,,,,", DefaultConstraint).unwrap();
+
+        assert!(solved.counts().cells().iter().all(|&count| count == 1));
+        assert_eq!(0.0, solved.total_entropy());
+        assert!(open.counts().cells().iter().all(|&count| count == 4));
+        assert_eq!(32.0, open.total_entropy());
+        assert_eq!(1, partial.count(0, 0).unwrap());
+        assert_eq!(2, partial.count(2, 0).unwrap());
+        assert_eq!(Ok(1.0), partial.entropy(2, 0));
+        assert_eq!(
+            Err(SudokuError::UnsatisfiableConstraint),
+            solver.candidate_heatmap(&invalid)
+        );
+    }
+
+    #[test]
+    fn propagation_respects_constraints_without_groups() {
+        let constraint = CompositeConstraint::new(DefaultConstraint, KnightsMoveConstraint);
+        let sudoku = Sudoku::parse("2x2;1,,,,,,2,,,3,,,,,,4", constraint).unwrap();
+
+        assert_eq!(
+            BacktrackingSolver.solve(&sudoku),
+            PropagatingSolver::new().solve(&sudoku)
+        );
+    }
+}
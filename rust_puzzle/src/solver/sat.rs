@@ -0,0 +1,207 @@
+use crate::constraint::presets::{ArgyleConstraint, WindokuConstraint};
+use crate::constraint::{
+    AdjacentConsecutiveConstraint, BlockConstraint, ColumnConstraint, ConsecutivePairsConstraint,
+    Constraint, DefaultConstraint, DiagonallyAdjacentConstraint, DiagonalsConstraint,
+    GivenCandidatesConstraint, KingsMoveConstraint, KnightsMoveConstraint, RowConstraint,
+};
+use crate::export;
+use crate::solver::{BacktrackingSolver, Solution, Solver};
+use crate::{Sudoku, SudokuGrid};
+
+use varisat::{ExtendFormula, Lit, Var};
+
+use std::any::TypeId;
+
+// Whether the encoding captures all rules of the constraint, i.e. each of
+// its parts only restricts single cells or pairs of cells.
+fn is_encodable<C>(constraint: &C) -> bool
+where
+    C: Constraint + 'static,
+{
+    let encodable = [
+        TypeId::of::<DefaultConstraint>(),
+        TypeId::of::<RowConstraint>(),
+        TypeId::of::<ColumnConstraint>(),
+        TypeId::of::<BlockConstraint>(),
+        TypeId::of::<DiagonalsConstraint>(),
+        TypeId::of::<KnightsMoveConstraint>(),
+        TypeId::of::<KingsMoveConstraint>(),
+        TypeId::of::<DiagonallyAdjacentConstraint>(),
+        TypeId::of::<AdjacentConsecutiveConstraint>(),
+        TypeId::of::<WindokuConstraint>(),
+        TypeId::of::<ArgyleConstraint>(),
+        TypeId::of::<GivenCandidatesConstraint>(),
+        TypeId::of::<ConsecutivePairsConstraint>(),
+    ];
+
+    constraint
+        .to_objects()
+        .into_iter()
+        .all(|part| encodable.contains(&(*part).type_id()))
+}
+
+// Index of the variable stating that the cell with the given index holds
+// the given number.
+fn var(size: usize, index: usize, number: usize) -> Var {
+    Var::from_index(index * size + number - 1)
+}
+
+// Encodes the sudoku in conjunctive normal form. Every cell holds exactly
+// one number, the givens are unit clauses and the exclusions found by
+// probing the constraint are binary clauses. Rules over more than two cells,
+// e.g. cage sums, are not encoded.
+fn encode<C>(sudoku: &Sudoku<C>, formula: &mut impl ExtendFormula)
+where
+    C: Constraint + Clone,
+{
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let exclusions = export::exclusions(sudoku);
+
+    for index in 0..size * size {
+        let lits: Vec<Lit> = (1..=size)
+            .map(|number| var(size, index, number).positive())
+            .collect();
+        formula.add_clause(&lits);
+
+        for (i, &a) in lits.iter().enumerate() {
+            for &b in lits[i + 1..].iter() {
+                formula.add_clause(&[!a, !b]);
+            }
+        }
+
+        if let Some(number) = grid.cells()[index] {
+            formula.add_clause(&[var(size, index, number).positive()]);
+        }
+    }
+
+    for (index, number) in exclusions.placements {
+        formula.add_clause(&[var(size, index, number).negative()]);
+    }
+
+    for ((a, n), (b, m)) in exclusions.pairs {
+        formula.add_clause(&[var(size, a, n).negative(), var(size, b, m).negative()]);
+    }
+}
+
+fn decode(model: &[Lit], template: &SudokuGrid) -> SudokuGrid {
+    let size = template.size();
+    let mut grid = template.clone();
+
+    let placements: Vec<(usize, usize, usize)> = model
+        .iter()
+        .filter(|lit| lit.is_positive())
+        .map(|lit| lit.index())
+        .map(|index| (index / size % size, index / size / size, index % size + 1))
+        .collect();
+    grid.apply_bulk(&placements).unwrap();
+    grid
+}
+
+// An experimental backend which encodes the puzzle as a SAT problem and
+// solves it with the CDCL solver varisat. Clause learning copes well with
+// puzzles whose rules interact in ways that make plain backtracking explore
+// huge trees. Uniqueness is checked by blocking the first solution and
+// solving again.
+//
+// Only rules over single cells or pairs of cells are encoded. Constraints
+// with other rules, such as cage sums or custom rules, would have to be
+// checked by blocking one invalid model after another, of which there can be
+// a huge number, so they are solved by backtracking instead.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SatSolver;
+
+impl Solver for SatSolver {
+    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + 'static,
+    {
+        if !is_encodable(sudoku.constraint()) {
+            return BacktrackingSolver.solve(sudoku);
+        }
+
+        let mut solver = varisat::Solver::new();
+        encode(sudoku, &mut solver);
+        let mut solution = Solution::Impossible;
+
+        while solver.solve().unwrap() {
+            let model = solver.model().unwrap();
+            let grid = decode(&model, sudoku.grid());
+            let blocking: Vec<Lit> = model
+                .iter()
+                .filter(|lit| lit.is_positive())
+                .map(|&lit| !lit)
+                .collect();
+            solver.add_clause(&blocking);
+            solution = solution.union(Solution::Unique(grid));
+
+            if solution == Solution::Ambiguous {
+                break;
+            }
+        }
+
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{CompositeConstraint, ConstraintRegistry, Rule, RuleSet};
+
+    #[test]
+    fn sat_solver_agrees_with_backtracking() {
+        let puzzles = [
+            "2x2;1,,,,,,1,,,1,,,,,,1",
+            "2x2;1,,,,,,,,,,,,,,,",
+            "2x2;1,1,,,,,,,,,,,,,,",
+            "3x3;\
+             , , , ,8,1, , , ,\
+             , ,2, , ,7,8, , ,\
+             ,5,3, , , ,1,7, ,\
+            3,7, , , , , , , ,\
+            6, , , , , , , ,3,\
+             , , , , , , ,2,4,\
+             ,6,9, , , ,2,3, ,\
+             , ,5,9, , ,4, , ,\
+             , , ,6,5, , , , ",
+        ];
+
+        for puzzle in puzzles {
+            let sudoku = Sudoku::parse(puzzle, DefaultConstraint).unwrap();
+            assert_eq!(BacktrackingSolver.solve(&sudoku), SatSolver.solve(&sudoku));
+        }
+    }
+
+    #[test]
+    fn sat_solver_handles_variants() {
+        let constraint = CompositeConstraint::new(DefaultConstraint, KnightsMoveConstraint);
+        let sudoku =
+            Sudoku::parse("3x2;1,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,6", constraint).unwrap();
+
+        assert_eq!(BacktrackingSolver.solve(&sudoku), SatSolver.solve(&sudoku));
+    }
+
+    #[test]
+    fn unencodable_constraints_are_backtracked() {
+        let mut registry = ConstraintRegistry::new();
+        registry.register_fn("odd corner", |grid, column, row, number| {
+            let last = grid.size() - 1;
+            (column, row) != (last, last) || number % 2 == 1
+        });
+        let rules = RuleSet::new(vec![
+            Rule::Default,
+            Rule::custom(&registry, "odd corner").unwrap(),
+        ]);
+        let sudoku = Sudoku::parse("2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,", rules).unwrap();
+
+        assert!(!is_encodable(sudoku.constraint()));
+        assert_eq!(BacktrackingSolver.solve(&sudoku), SatSolver.solve(&sudoku));
+        assert!(is_encodable(&CompositeConstraint::new(
+            DefaultConstraint,
+            KnightsMoveConstraint
+        )));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::constraint::{Constraint, Group};
+use crate::solver::propagation::{self, Propagated, Propagation};
+use crate::solver::Solution;
+use crate::{Sudoku, SudokuGrid};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum SearchDecision {
+    Continue,
+    Backtrack,
+    Abort,
+}
+
+// Receives the events of a backtracking search, so that observers, pruning
+// hooks and tree recorders all run on the same search. Every event does
+// nothing by default.
+pub(crate) trait SearchVisitor {
+    // A node was reached by the given (column, row, number) assignment, or is
+    // the root if it is None. Every enter is matched by a leave.
+    fn enter(&mut self, _assignment: Option<(usize, usize, usize)>, _depth: usize) {}
+
+    fn leave(&mut self) {}
+
+    // The grid of the current node is full.
+    fn solved(&mut self, _grid: &SudokuGrid) {}
+
+    // The search is about to try the candidates of the given empty cell,
+    // which may be none. The visitor may remove candidates, abandon the node
+    // or abort the whole search.
+    fn branch(
+        &mut self,
+        _grid: &SudokuGrid,
+        _column: usize,
+        _row: usize,
+        _depth: usize,
+        _candidates: &mut Vec<usize>,
+    ) -> SearchDecision {
+        SearchDecision::Continue
+    }
+}
+
+impl SearchVisitor for () {}
+
+enum Next {
+    Contradiction,
+    Solved,
+    Branch(usize, usize),
+}
+
+// The candidate lists are reused between nodes, since allocating one per
+// node is a noticeable part of the search.
+struct Search<'a, V> {
+    propagation: Propagation,
+    houses: Vec<Group>,
+    intersections: Vec<(usize, usize)>,
+    visitor: &'a mut V,
+    aborted: bool,
+    buffers: Vec<Vec<usize>>,
+}
+
+impl<V: SearchVisitor> Search<'_, V> {
+    fn solve_rec<C>(
+        &mut self,
+        sudoku: &mut Sudoku<C>,
+        index: usize,
+        assignment: Option<(usize, usize, usize)>,
+        depth: usize,
+    ) -> Solution
+    where
+        C: Constraint + Clone,
+    {
+        self.visitor.enter(assignment, depth);
+        let solution = self.node(sudoku, index, depth);
+        self.visitor.leave();
+        solution
+    }
+
+    fn node<C>(&mut self, sudoku: &mut Sudoku<C>, index: usize, depth: usize) -> Solution
+    where
+        C: Constraint + Clone,
+    {
+        let mut placed = Vec::new();
+        let mut candidates = self.buffers.pop().unwrap_or_default();
+        candidates.clear();
+        let solution = match self.next(sudoku, index, &mut placed, &mut candidates) {
+            Next::Contradiction => Solution::Impossible,
+            Next::Solved => {
+                self.visitor.solved(sudoku.grid());
+                Solution::Unique(sudoku.grid().clone())
+            }
+            Next::Branch(column, row) => {
+                match self
+                    .visitor
+                    .branch(sudoku.grid(), column, row, depth, &mut candidates)
+                {
+                    SearchDecision::Continue => {
+                        self.try_candidates(sudoku, column, row, depth, &candidates)
+                    }
+                    SearchDecision::Backtrack => Solution::Impossible,
+                    SearchDecision::Abort => {
+                        self.aborted = true;
+                        Solution::Impossible
+                    }
+                }
+            }
+        };
+
+        for (column, row) in placed {
+            sudoku.grid_mut().clear_cell(column, row).unwrap();
+        }
+
+        self.buffers.push(candidates);
+        solution
+    }
+
+    // Finds the cell to branch on and its candidates. Without propagation
+    // this is the next empty cell from `index` on, with propagation the cell
+    // with the fewest candidates once the propagated cells are placed.
+    fn next<C>(
+        &self,
+        sudoku: &mut Sudoku<C>,
+        index: usize,
+        placed: &mut Vec<(usize, usize)>,
+        candidates: &mut Vec<usize>,
+    ) -> Next
+    where
+        C: Constraint + Clone,
+    {
+        let size = sudoku.grid().size();
+
+        if self.propagation == Propagation::Off {
+            let (column, row) = match next_empty_cell(sudoku.grid(), index) {
+                Some(cell) => cell,
+                None => return Next::Solved,
+            };
+            candidates.extend(
+                (1..=size).filter(|&number| sudoku.is_valid_number(column, row, number).unwrap()),
+            );
+            return Next::Branch(column, row);
+        }
+
+        match propagation::propagate(sudoku, &self.houses, &self.intersections, placed) {
+            Propagated::Contradiction => Next::Contradiction,
+            Propagated::Solved => Next::Solved,
+            Propagated::Branch(cells) => {
+                let (index, cell_candidates) = cells
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, cell_candidates)| !cell_candidates.is_empty())
+                    .min_by_key(|(_, cell_candidates)| cell_candidates.len())
+                    .unwrap();
+                candidates.extend(cell_candidates);
+                Next::Branch(index % size, index / size)
+            }
+        }
+    }
+
+    fn try_candidates<C>(
+        &mut self,
+        sudoku: &mut Sudoku<C>,
+        column: usize,
+        row: usize,
+        depth: usize,
+        candidates: &[usize],
+    ) -> Solution
+    where
+        C: Constraint + Clone,
+    {
+        let next_index = row * sudoku.grid().size() + column + 1;
+        let mut solution = Solution::Impossible;
+
+        for &number in candidates {
+            sudoku.grid_mut().set_cell(column, row, number).unwrap();
+            let next_solution =
+                self.solve_rec(sudoku, next_index, Some((column, row, number)), depth + 1);
+            sudoku.grid_mut().clear_cell(column, row).unwrap();
+            solution = solution.union(next_solution);
+
+            if solution == Solution::Ambiguous || self.aborted {
+                break;
+            }
+        }
+
+        solution
+    }
+}
+
+fn next_empty_cell(grid: &SudokuGrid, mut index: usize) -> Option<(usize, usize)> {
+    let size = grid.size();
+
+    while index < size * size {
+        let (column, row) = (index % size, index / size);
+
+        if grid.get_cell(column, row).unwrap().is_none() {
+            return Some((column, row));
+        }
+
+        index += 1;
+    }
+
+    None
+}
+
+// The backtracking search behind BacktrackingSolver and the solvers built
+// on it. Without propagation, empty cells are filled in row-major order.
+// Numbers are tried in increasing order and the search stops at the second
+// solution. Returns None if the visitor aborted the search.
+pub(crate) fn backtrack<C, V>(
+    sudoku: &Sudoku<C>,
+    propagation: Propagation,
+    visitor: &mut V,
+) -> Option<Solution>
+where
+    C: Constraint + Clone,
+    V: SearchVisitor,
+{
+    let mut clone = sudoku.clone();
+    let (houses, intersections) = propagation::houses(sudoku, propagation);
+    let mut search = Search {
+        propagation,
+        houses,
+        intersections,
+        visitor,
+        aborted: false,
+        buffers: Vec::new(),
+    };
+    let solution = search.solve_rec(&mut clone, 0, None, 0);
+
+    if search.aborted {
+        None
+    } else {
+        Some(solution)
+    }
+}
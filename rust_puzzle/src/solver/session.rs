@@ -0,0 +1,152 @@
+use crate::constraint::Constraint;
+use crate::solver::{Solution, Solver};
+use crate::{Sudoku, SudokuGrid};
+
+use std::cell::RefCell;
+use std::mem;
+
+struct Buffers {
+    grid: Option<SudokuGrid>,
+    empty_cells: Vec<(usize, usize)>,
+}
+
+impl Buffers {
+    fn load(&mut self, source: &SudokuGrid) {
+        match &mut self.grid {
+            Some(grid)
+                if grid.block_width() == source.block_width()
+                    && grid.block_height() == source.block_height() =>
+            {
+                grid.assign(source).unwrap()
+            }
+            grid => *grid = Some(source.clone()),
+        }
+
+        self.empty_cells.clear();
+        self.empty_cells.extend(source.empty_cells());
+    }
+}
+
+// Backtracking solver that keeps its working grid and cell list between
+// solves, so solving many sudokus of the same dimensions does not allocate
+// anything except for the returned solution.
+pub struct SolverSession {
+    buffers: RefCell<Buffers>,
+}
+
+fn solve_rec<C>(
+    constraint: &C,
+    grid: &mut SudokuGrid,
+    empty_cells: &[(usize, usize)],
+    solution: &mut Solution,
+) where
+    C: Constraint,
+{
+    let (column, row) = match empty_cells.first() {
+        Some(&cell) => cell,
+        None => {
+            let found = mem::replace(solution, Solution::Impossible);
+            *solution = found.union(Solution::Unique(grid.clone()));
+            return;
+        }
+    };
+
+    for number in 1..=grid.size() {
+        if constraint.check_number(grid, column, row, number) {
+            grid.set_cell(column, row, number).unwrap();
+            solve_rec(constraint, grid, &empty_cells[1..], solution);
+            grid.clear_cell(column, row).unwrap();
+
+            if *solution == Solution::Ambiguous {
+                return;
+            }
+        }
+    }
+}
+
+impl SolverSession {
+    pub fn new() -> SolverSession {
+        SolverSession {
+            buffers: RefCell::new(Buffers {
+                grid: None,
+                empty_cells: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn solve_into<C>(&mut self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + 'static,
+    {
+        Solver::solve(self, sudoku)
+    }
+}
+
+impl Default for SolverSession {
+    fn default() -> SolverSession {
+        SolverSession::new()
+    }
+}
+
+impl Solver for SolverSession {
+    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let mut buffers = self.buffers.borrow_mut();
+        buffers.load(sudoku.grid());
+        let Buffers { grid, empty_cells } = &mut *buffers;
+        let grid = grid.as_mut().unwrap();
+        let mut solution = Solution::Impossible;
+        solve_rec(sudoku.constraint(), grid, empty_cells, &mut solution);
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+    use crate::solver::BacktrackingSolver;
+
+    const CLASSIC_PUZZLE: &str = "3x3;\
+         , , , ,8,1, , , ,\
+         , ,2, , ,7,8, , ,\
+         ,5,3, , , ,1,7, ,\
+        3,7, , , , , , , ,\
+        6, , , , , , , ,3,\
+         , , , , , , ,2,4,\
+         ,6,9, , , ,2,3, ,\
+         , ,5,9, , ,4, , ,\
+         , , ,6,5, , , , ";
+
+    #[test]
+    fn session_agrees_with_backtracking_solver() {
+        let puzzles = [
+            CLASSIC_PUZZLE,
+            "2x2;,,,,,,,,,,,,,,,",
+            "2x2;1,1,,,,,,,,,,,,,,",
+            "2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,",
+        ];
+        let mut session = SolverSession::new();
+
+        for puzzle in puzzles.iter() {
+            let sudoku = Sudoku::parse(puzzle, DefaultConstraint).unwrap();
+            let expected = BacktrackingSolver.solve(&sudoku);
+
+            assert_eq!(expected, session.solve_into(&sudoku));
+        }
+    }
+
+    #[test]
+    fn session_can_be_reused_across_dimensions() {
+        let mut session = SolverSession::new();
+        let small = Sudoku::parse("2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,", DefaultConstraint).unwrap();
+        let large = Sudoku::parse(CLASSIC_PUZZLE, DefaultConstraint).unwrap();
+
+        assert!(matches!(session.solve_into(&small), Solution::Unique(_)));
+        assert!(matches!(session.solve_into(&large), Solution::Unique(_)));
+        assert!(matches!(session.solve_into(&small), Solution::Unique(_)));
+    }
+}
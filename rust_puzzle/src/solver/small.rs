@@ -0,0 +1,190 @@
+use crate::constraint::{Constraint, DefaultConstraint};
+use crate::solver::{BacktrackingSolver, Solution, Solver};
+use crate::{Sudoku, SudokuGrid};
+
+// The search state for a grid with N numbers, kept in fixed-size arrays on
+// the stack. For every row, column and block, bit n of its mask is set if n
+// occurs in it.
+struct SmallGrid<const N: usize> {
+    cells: [[u8; N]; N],
+    rows: [u16; N],
+    columns: [u16; N],
+    blocks: [u16; N],
+    block_width: usize,
+    block_height: usize,
+}
+
+impl<const N: usize> SmallGrid<N> {
+    fn new(grid: &SudokuGrid) -> SmallGrid<N> {
+        let mut small = SmallGrid {
+            cells: [[0; N]; N],
+            rows: [0; N],
+            columns: [0; N],
+            blocks: [0; N],
+            block_width: grid.block_width(),
+            block_height: grid.block_height(),
+        };
+
+        for (column, row, number) in grid.filled_cells() {
+            small.set(column, row, number as u8);
+        }
+
+        small
+    }
+
+    fn block(&self, column: usize, row: usize) -> usize {
+        row / self.block_height * (N / self.block_width) + column / self.block_width
+    }
+
+    fn set(&mut self, column: usize, row: usize, number: u8) {
+        let bit = 1 << number;
+        let block = self.block(column, row);
+        self.cells[row][column] = number;
+        self.rows[row] |= bit;
+        self.columns[column] |= bit;
+        self.blocks[block] |= bit;
+    }
+
+    fn clear(&mut self, column: usize, row: usize) {
+        let bit = !(1 << self.cells[row][column]);
+        let block = self.block(column, row);
+        self.cells[row][column] = 0;
+        self.rows[row] &= bit;
+        self.columns[column] &= bit;
+        self.blocks[block] &= bit;
+    }
+
+    fn to_grid(&self) -> SudokuGrid {
+        let mut grid = SudokuGrid::new(self.block_width, self.block_height).unwrap();
+        let placements: Vec<(usize, usize, usize)> = self
+            .cells
+            .iter()
+            .enumerate()
+            .flat_map(|(row, numbers)| {
+                numbers
+                    .iter()
+                    .enumerate()
+                    .map(move |(column, &number)| (column, row, number as usize))
+            })
+            .collect();
+        grid.apply_bulk(&placements).unwrap();
+        grid
+    }
+
+    // Visits the cells and numbers in the same order as BacktrackingSolver,
+    // so both find the same solutions.
+    fn solve_rec(&mut self, index: usize) -> Solution {
+        if index == N * N {
+            return Solution::Unique(self.to_grid());
+        }
+
+        let (column, row) = (index % N, index / N);
+
+        if self.cells[row][column] != 0 {
+            return self.solve_rec(index + 1);
+        }
+
+        let used = self.rows[row] | self.columns[column] | self.blocks[self.block(column, row)];
+        let mut solution = Solution::Impossible;
+
+        for number in 1..=N as u8 {
+            if used & (1 << number) != 0 {
+                continue;
+            }
+
+            self.set(column, row, number);
+            let next_solution = self.solve_rec(index + 1);
+            self.clear(column, row);
+            solution = solution.union(next_solution);
+
+            if solution == Solution::Ambiguous {
+                break;
+            }
+        }
+
+        solution
+    }
+}
+
+// Solver which handles classic sudokus with 4, 6 or 9 numbers by a bitmask
+// search specialized for their size, and everything else by
+// BacktrackingSolver. Its results are the same as those of
+// BacktrackingSolver.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SmallGridSolver;
+
+fn is_classic<C>(sudoku: &Sudoku<C>) -> bool
+where
+    C: Constraint + Clone + 'static,
+{
+    let objects = sudoku.constraint().to_objects();
+    objects.len() == 1 && objects[0].is::<DefaultConstraint>()
+}
+
+impl Solver for SmallGridSolver {
+    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let grid = sudoku.grid();
+
+        if !is_classic(sudoku) {
+            return BacktrackingSolver.solve(sudoku);
+        }
+
+        match grid.size() {
+            4 => SmallGrid::<4>::new(grid).solve_rec(0),
+            6 => SmallGrid::<6>::new(grid).solve_rec(0),
+            9 => SmallGrid::<9>::new(grid).solve_rec(0),
+            _ => BacktrackingSolver.solve(sudoku),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{CompositeConstraint, DiagonalsConstraint};
+
+    #[test]
+    fn small_grids_solve_like_backtracking() {
+        let puzzles = [
+            "2x2;1, , , , ,3, , , , ,4, , , , ,2",
+            "2x2;1,1, , , , , , , , , , , , , , ",
+            "2x2; , , , , , , , , , , , , , , , ",
+            "3x2; , ,5, , , ,1, , , ,5, , , , , , , , ,4, ,2,6,3,6, , ,1,4, ,4, , , , , ",
+            "2x3; , ,5, , , ,1, , , ,5, , , , , , , , ,4, ,2,6,3,6, , ,1,4, ,4, , , , , ",
+            "3x3;\
+             , , , ,8,1, , , ,\
+             , ,2, , ,7,8, , ,\
+             ,5,3, , , ,1,7, ,\
+            3,7, , , , , , , ,\
+            6, , , , , , , ,3,\
+             , , , , , , ,2,4,\
+             ,6,9, , , ,2,3, ,\
+             , ,5,9, , ,4, , ,\
+             , , ,6,5, , , , ",
+        ];
+
+        for puzzle in puzzles.iter() {
+            let sudoku = Sudoku::parse(puzzle, DefaultConstraint).unwrap();
+            assert_eq!(
+                BacktrackingSolver.solve(&sudoku),
+                SmallGridSolver.solve(&sudoku)
+            );
+        }
+    }
+
+    #[test]
+    fn other_constraints_fall_back() {
+        let constraint = CompositeConstraint::new(DefaultConstraint, DiagonalsConstraint);
+        let sudoku = Sudoku::parse("2x2;1, , , , , , , , , , , , , , , ", constraint).unwrap();
+
+        assert_eq!(
+            BacktrackingSolver.solve(&sudoku),
+            SmallGridSolver.solve(&sudoku)
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use crate::constraint::Constraint;
+use crate::solver::memory::{MemoryBudget, SolveStats};
+use crate::solver::search::{self, SearchDecision, SearchVisitor};
+use crate::solver::{Propagation, Solution};
+use crate::{Sudoku, SudokuGrid};
+
+use serde::{Deserialize, Serialize};
+
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum NodeOutcome {
+    // The grid was full, i.e. this node is a solution.
+    Solved,
+
+    // The next empty cell had no valid number left.
+    DeadEnd {
+        column: usize,
+        row: usize,
+    },
+
+    // The search branched on the next empty cell. Children may be missing
+    // if the search stopped early after finding a second solution or the
+    // tree reached its capacity.
+    Branched {
+        column: usize,
+        row: usize,
+        candidates: usize,
+    },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct SearchNode {
+    parent: Option<usize>,
+    assignment: Option<(usize, usize, usize)>,
+    depth: usize,
+    outcome: NodeOutcome,
+}
+
+impl SearchNode {
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    // The (column, row, number) chosen to get from the parent to this node,
+    // or None for the root.
+    pub fn assignment(&self) -> Option<(usize, usize, usize)> {
+        self.assignment
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn outcome(&self) -> NodeOutcome {
+        self.outcome
+    }
+}
+
+// The tree explored by a backtracking search, in the order nodes were
+// visited. Serialize it (e.g. to JSON) or export it with to_dot.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct SearchTree {
+    nodes: Vec<SearchNode>,
+    truncated: bool,
+}
+
+impl SearchTree {
+    pub fn nodes(&self) -> &[SearchNode] {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // Whether the search visited more nodes than the recorder's capacity.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    // The memory used by the recorded nodes. Truncation counts as a
+    // degradation.
+    pub fn stats(&self) -> SolveStats {
+        let mut stats = SolveStats::default();
+        stats.allocate(self.nodes.len() * size_of::<SearchNode>());
+
+        if self.truncated {
+            stats.degrade();
+        }
+
+        stats
+    }
+
+    pub fn children(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| node.parent == Some(index))
+            .map(|(child, _)| child)
+    }
+
+    // Renders the tree in the Graphviz DOT language, with solutions in green
+    // and dead ends in red.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let label = match node.assignment {
+                Some((column, row, number)) => format!("({}, {}) = {}", column, row, number),
+                None => String::from("root"),
+            };
+            let (label, color) = match node.outcome {
+                NodeOutcome::Solved => (format!("{}\\nsolved", label), "green"),
+                NodeOutcome::DeadEnd { column, row } => (
+                    format!("{}\\nno candidates for ({}, {})", label, column, row),
+                    "red",
+                ),
+                NodeOutcome::Branched { .. } => (label, "black"),
+            };
+            writeln!(
+                dot,
+                "    n{} [label=\"{}\", color={}];",
+                index, label, color
+            )
+            .unwrap();
+
+            if let Some(parent) = node.parent {
+                writeln!(dot, "    n{} -> n{};", parent, index).unwrap();
+            }
+        }
+
+        if self.truncated {
+            dot.push_str("    truncated [label=\"...\", shape=plaintext];\n");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Runs the same search as BacktrackingSolver while recording the visited
+// nodes, up to the given capacity and memory budget. The search itself
+// always runs to completion, so the solution is correct even if the tree is
+// truncated.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchTreeRecorder {
+    capacity: usize,
+    memory_budget: Option<MemoryBudget>,
+}
+
+// Records the nodes entered by the search. The stack holds the recorded
+// index of every node on the current path, or None for unrecorded nodes.
+struct Recording {
+    tree: SearchTree,
+    capacity: usize,
+    stack: Vec<Option<usize>>,
+}
+
+impl Recording {
+    fn push(
+        &mut self,
+        parent: Option<usize>,
+        assignment: Option<(usize, usize, usize)>,
+        depth: usize,
+    ) -> Option<usize> {
+        if self.tree.nodes.len() >= self.capacity {
+            self.tree.truncated = true;
+            return None;
+        }
+
+        self.tree.nodes.push(SearchNode {
+            parent,
+            assignment,
+            depth,
+            outcome: NodeOutcome::Solved,
+        });
+        Some(self.tree.nodes.len() - 1)
+    }
+
+    fn set_outcome(&mut self, outcome: NodeOutcome) {
+        if let Some(&Some(node)) = self.stack.last() {
+            self.tree.nodes[node].outcome = outcome;
+        }
+    }
+}
+
+impl SearchVisitor for Recording {
+    fn enter(&mut self, assignment: Option<(usize, usize, usize)>, depth: usize) {
+        // Children of unrecorded nodes are not recorded either.
+        let node = match self.stack.last() {
+            None => self.push(None, assignment, depth),
+            Some(&Some(parent)) => self.push(Some(parent), assignment, depth),
+            Some(&None) => None,
+        };
+        self.stack.push(node);
+    }
+
+    fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    fn solved(&mut self, _grid: &SudokuGrid) {
+        self.set_outcome(NodeOutcome::Solved);
+    }
+
+    fn branch(
+        &mut self,
+        _grid: &SudokuGrid,
+        column: usize,
+        row: usize,
+        _depth: usize,
+        candidates: &mut Vec<usize>,
+    ) -> SearchDecision {
+        if candidates.is_empty() {
+            self.set_outcome(NodeOutcome::DeadEnd { column, row });
+        } else {
+            self.set_outcome(NodeOutcome::Branched {
+                column,
+                row,
+                candidates: candidates.len(),
+            });
+        }
+
+        SearchDecision::Continue
+    }
+}
+
+impl SearchTreeRecorder {
+    pub fn new(capacity: usize) -> SearchTreeRecorder {
+        SearchTreeRecorder {
+            capacity,
+            memory_budget: None,
+        }
+    }
+
+    pub fn with_memory_budget(self, memory_budget: MemoryBudget) -> SearchTreeRecorder {
+        SearchTreeRecorder {
+            memory_budget: Some(memory_budget),
+            ..self
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn memory_budget(&self) -> Option<MemoryBudget> {
+        self.memory_budget
+    }
+
+    pub fn solve<C>(&self, sudoku: &Sudoku<C>) -> (Solution, SearchTree)
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let budget_nodes = self
+            .memory_budget
+            .map_or(usize::MAX, |budget| budget.items(size_of::<SearchNode>()));
+        let mut recording = Recording {
+            tree: SearchTree::default(),
+            capacity: self.capacity.min(budget_nodes),
+            stack: Vec::new(),
+        };
+        let solution = search::backtrack(sudoku, Propagation::Off, &mut recording).unwrap();
+        (solution, recording.tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+    use crate::solver::{BacktrackingSolver, Solver};
+
+    fn puzzle() -> Sudoku<DefaultConstraint> {
+        Sudoku::parse("2x2;1,,,,,,2,,,3,,,,,,4", DefaultConstraint).unwrap()
+    }
+
+    #[test]
+    fn records_search() {
+        let (solution, tree) = SearchTreeRecorder::new(1000).solve(&puzzle());
+        let solved = tree
+            .nodes()
+            .iter()
+            .filter(|node| node.outcome() == NodeOutcome::Solved)
+            .count();
+
+        assert_eq!(BacktrackingSolver.solve(&puzzle()), solution);
+        assert!(!tree.is_truncated());
+        assert_eq!(1, solved);
+        assert_eq!(None, tree.nodes()[0].parent());
+        assert!(tree.nodes()[1..].iter().all(|node| node.parent().is_some()));
+        assert_eq!(
+            tree.nodes()[0].depth() + 1,
+            tree.nodes()[tree.children(0).next().unwrap()].depth()
+        );
+    }
+
+    #[test]
+    fn dead_ends_name_the_cell() {
+        // (1, 0) can neither be 1 (row) nor 2 (column) nor 3 or 4 (row).
+        let sudoku = Sudoku::parse("2x2;1,,3,4,,2,,,,,,,,,,", DefaultConstraint).unwrap();
+        let (solution, tree) = SearchTreeRecorder::new(10).solve(&sudoku);
+
+        assert_eq!(Solution::Impossible, solution);
+        assert_eq!(1, tree.len());
+        assert_eq!(
+            NodeOutcome::DeadEnd { column: 1, row: 0 },
+            tree.nodes()[0].outcome()
+        );
+    }
+
+    #[test]
+    fn memory_budget_truncates_tree() {
+        let empty = Sudoku::new_empty(2, 2, DefaultConstraint).unwrap();
+        let budget = MemoryBudget::new(3 * size_of::<SearchNode>());
+        let (solution, tree) = SearchTreeRecorder::new(100)
+            .with_memory_budget(budget)
+            .solve(&empty);
+
+        assert_eq!(Solution::Ambiguous, solution);
+        assert_eq!(3, tree.len());
+        assert_eq!(budget.bytes(), tree.stats().peak());
+        assert!(tree.stats().is_degraded());
+    }
+
+    #[test]
+    fn capacity_truncates_tree() {
+        let empty = Sudoku::new_empty(2, 2, DefaultConstraint).unwrap();
+        let (solution, tree) = SearchTreeRecorder::new(5).solve(&empty);
+
+        assert_eq!(Solution::Ambiguous, solution);
+        assert_eq!(5, tree.len());
+        assert!(tree.is_truncated());
+        assert!(tree.to_dot().contains("truncated"));
+    }
+
+    #[test]
+    fn export() {
+        let (_, tree) = SearchTreeRecorder::new(1000).solve(&puzzle());
+        let dot = tree.to_dot();
+        let json = serde_json::to_string(&tree).unwrap();
+
+        assert!(dot.starts_with("digraph search {"));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains("solved"));
+        assert_eq!(tree, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::constraint::{Constraint, RuleSet};
+use crate::solver::{PropagatingSolver, Solution, Solver};
+use crate::Sudoku;
+
+// A part of a puzzle which contributes to it being impossible: a given cell
+// as (column, row), or a rule of a RuleSet by its index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CellOrRuleRef {
+    Cell(usize, usize),
+    Rule(usize),
+}
+
+fn is_impossible<C>(sudoku: &Sudoku<C>) -> bool
+where
+    C: Constraint + Clone + 'static,
+{
+    PropagatingSolver::new().solve(sudoku) == Solution::Impossible
+}
+
+// Delta debugging (ddmin): shrinks a failing set of elements to one where
+// removing any single element makes it pass, testing ever smaller subsets
+// and their complements. The order of the elements is kept.
+fn ddmin<T, F>(elements: Vec<T>, fails: F) -> Vec<T>
+where
+    T: Copy,
+    F: Fn(&[T]) -> bool,
+{
+    let mut current = elements;
+    let mut granularity = 2;
+
+    while current.len() >= 2 {
+        let chunk_size = current.len().div_ceil(granularity);
+        let chunks: Vec<Vec<T>> = current.chunks(chunk_size).map(<[T]>::to_vec).collect();
+        let mut reduced = None;
+
+        if let Some(chunk) = chunks.iter().find(|chunk| fails(chunk)) {
+            reduced = Some((chunk.clone(), 2));
+        } else if chunks.len() > 2 {
+            for index in 0..chunks.len() {
+                let complement: Vec<T> = chunks
+                    .iter()
+                    .enumerate()
+                    .filter(|&(other, _)| other != index)
+                    .flat_map(|(_, chunk)| chunk.iter().copied())
+                    .collect();
+
+                if fails(&complement) {
+                    reduced = Some((complement, (granularity - 1).max(2)));
+                    break;
+                }
+            }
+        }
+
+        match reduced {
+            Some((subset, next_granularity)) => {
+                current = subset;
+                granularity = next_granularity;
+            }
+            None if granularity >= current.len() => break,
+            None => granularity = (granularity * 2).min(current.len()),
+        }
+    }
+
+    current
+}
+
+fn keep_givens<C>(sudoku: &Sudoku<C>, givens: &[(usize, usize)]) -> Sudoku<C>
+where
+    C: Constraint + Clone + 'static,
+{
+    let mut subset = sudoku.clone();
+    let removed: Vec<(usize, usize)> = sudoku
+        .grid()
+        .filled_cells()
+        .map(|(column, row, _)| (column, row))
+        .filter(|cell| !givens.contains(cell))
+        .collect();
+
+    for (column, row) in removed {
+        subset.grid_mut().clear_cell(column, row).unwrap();
+    }
+
+    subset
+}
+
+// Finds a small set of givens which is impossible on its own, such that
+// removing any one of them makes that set solvable. Removing one of them
+// from the full puzzle does not necessarily fix it, since there may be more
+// than one conflict. Returns nothing if the puzzle is not impossible, or if
+// the constraint cannot be satisfied even without givens.
+pub fn explain_unsat<C>(sudoku: &Sudoku<C>) -> Vec<CellOrRuleRef>
+where
+    C: Constraint + Clone + 'static,
+{
+    if !is_impossible(sudoku) || is_impossible(&keep_givens(sudoku, &[])) {
+        return Vec::new();
+    }
+
+    let givens = sudoku
+        .grid()
+        .filled_cells()
+        .map(|(column, row, _)| (column, row))
+        .collect();
+
+    ddmin(givens, |subset| is_impossible(&keep_givens(sudoku, subset)))
+        .into_iter()
+        .map(|(column, row)| CellOrRuleRef::Cell(column, row))
+        .collect()
+}
+
+// Like explain_unsat, but also blames the rules of the rule set, so it finds
+// a core even if the rules contradict each other without any givens.
+pub fn explain_unsat_rules(sudoku: &Sudoku<RuleSet>) -> Vec<CellOrRuleRef> {
+    if !is_impossible(sudoku) {
+        return Vec::new();
+    }
+
+    let rules = sudoku.constraint().rules();
+    let elements = sudoku
+        .grid()
+        .filled_cells()
+        .map(|(column, row, _)| CellOrRuleRef::Cell(column, row))
+        .chain((0..rules.len()).map(CellOrRuleRef::Rule))
+        .collect();
+
+    ddmin(elements, |subset| {
+        let givens: Vec<(usize, usize)> = subset
+            .iter()
+            .filter_map(|element| match *element {
+                CellOrRuleRef::Cell(column, row) => Some((column, row)),
+                CellOrRuleRef::Rule(_) => None,
+            })
+            .collect();
+        let rule_set = RuleSet::new(
+            subset
+                .iter()
+                .filter_map(|element| match *element {
+                    CellOrRuleRef::Rule(index) => Some(rules[index].clone()),
+                    CellOrRuleRef::Cell(..) => None,
+                })
+                .collect(),
+        );
+        let grid = keep_givens(sudoku, &givens).grid().clone();
+
+        Sudoku::new_with_grid(grid, rule_set).is_ok_and(|subset| is_impossible(&subset))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{DefaultConstraint, Rule};
+
+    #[test]
+    fn finds_the_conflicting_givens() {
+        let sudoku = Sudoku::parse(
+            "2x2;\
+            1,2,3, ,\
+             , , ,4,\
+             , , , ,\
+            2, , , ",
+            DefaultConstraint,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                CellOrRuleRef::Cell(0, 0),
+                CellOrRuleRef::Cell(1, 0),
+                CellOrRuleRef::Cell(3, 1)
+            ],
+            explain_unsat(&sudoku)
+        );
+
+        let solvable = Sudoku::parse("2x2;1, , , , , , , , , , , , , , , ", DefaultConstraint);
+        assert!(explain_unsat(&solvable.unwrap()).is_empty());
+    }
+
+    #[test]
+    fn blames_rules() {
+        let rules = RuleSet::new(vec![Rule::Row, Rule::Column, Rule::Diagonals]);
+        let sudoku = Sudoku::parse("2x2;1, , , , ,1, , , , , , , , , , ", rules).unwrap();
+
+        assert_eq!(
+            vec![
+                CellOrRuleRef::Cell(0, 0),
+                CellOrRuleRef::Cell(1, 1),
+                CellOrRuleRef::Rule(2)
+            ],
+            explain_unsat_rules(&sudoku)
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use crate::symmetry::{self, SymmetryReport};
+use crate::SudokuGrid;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PuzzleStats {
+    size: usize,
+    clue_count: usize,
+    clues_per_row: Vec<usize>,
+    clues_per_column: Vec<usize>,
+    clues_per_block: Vec<usize>,
+    digit_frequency: Vec<usize>,
+    entropy: f64,
+    symmetry: SymmetryReport,
+}
+
+impl PuzzleStats {
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn clue_count(&self) -> usize {
+        self.clue_count
+    }
+
+    pub fn clues_per_row(&self) -> &[usize] {
+        &self.clues_per_row
+    }
+
+    pub fn clues_per_column(&self) -> &[usize] {
+        &self.clues_per_column
+    }
+
+    // Blocks are numbered left to right, top to bottom.
+    pub fn clues_per_block(&self) -> &[usize] {
+        &self.clues_per_block
+    }
+
+    // The number of clues for each digit, where index 0 belongs to digit 1.
+    pub fn digit_frequency(&self) -> &[usize] {
+        &self.digit_frequency
+    }
+
+    // Shannon entropy (in bits) of the digit distribution among the clues.
+    // It is log2(size) if all digits occur equally often and 0 if at most one
+    // digit is used.
+    pub fn entropy(&self) -> f64 {
+        self.entropy
+    }
+
+    pub fn symmetry(&self) -> &SymmetryReport {
+        &self.symmetry
+    }
+}
+
+fn entropy(frequencies: &[usize], total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    frequencies
+        .iter()
+        .filter(|&&f| f > 0)
+        .map(|&f| {
+            let p = f as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum::<f64>()
+        .max(0.0)
+}
+
+pub fn puzzle_stats(grid: &SudokuGrid) -> PuzzleStats {
+    let size = grid.size();
+    let block_width = grid.block_width();
+    let block_height = grid.block_height();
+    let mut clue_count = 0;
+    let mut clues_per_row = vec![0; size];
+    let mut clues_per_column = vec![0; size];
+    let mut clues_per_block = vec![0; size];
+    let mut digit_frequency = vec![0; size];
+
+    for (index, cell) in grid.cells().iter().enumerate() {
+        if let Some(number) = cell {
+            let column = index % size;
+            let row = index / size;
+            let block = (row / block_height) * block_height + column / block_width;
+            clue_count += 1;
+            clues_per_row[row] += 1;
+            clues_per_column[column] += 1;
+            clues_per_block[block] += 1;
+            digit_frequency[number - 1] += 1;
+        }
+    }
+
+    PuzzleStats {
+        size,
+        clue_count,
+        clues_per_row,
+        clues_per_column,
+        clues_per_block,
+        entropy: entropy(&digit_frequency, clue_count),
+        digit_frequency,
+        symmetry: symmetry::detect_symmetry(grid),
+    }
+}
+
+// Aggregate over many puzzles, e.g. a generated data set.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct StatsSummary {
+    puzzles: usize,
+    min_clues: usize,
+    max_clues: usize,
+    mean_clues: f64,
+    mean_entropy: f64,
+    symmetric_puzzles: usize,
+}
+
+impl StatsSummary {
+    pub fn puzzles(&self) -> usize {
+        self.puzzles
+    }
+
+    pub fn min_clues(&self) -> usize {
+        self.min_clues
+    }
+
+    pub fn max_clues(&self) -> usize {
+        self.max_clues
+    }
+
+    pub fn mean_clues(&self) -> f64 {
+        self.mean_clues
+    }
+
+    pub fn mean_entropy(&self) -> f64 {
+        self.mean_entropy
+    }
+
+    pub fn symmetric_puzzles(&self) -> usize {
+        self.symmetric_puzzles
+    }
+}
+
+pub fn summarize<'a, I>(stats: I) -> StatsSummary
+where
+    I: IntoIterator<Item = &'a PuzzleStats>,
+{
+    let mut summary = StatsSummary {
+        min_clues: usize::MAX,
+        ..StatsSummary::default()
+    };
+    let mut clue_sum = 0usize;
+    let mut entropy_sum = 0.0;
+
+    for s in stats {
+        summary.puzzles += 1;
+        summary.min_clues = summary.min_clues.min(s.clue_count);
+        summary.max_clues = summary.max_clues.max(s.clue_count);
+        clue_sum += s.clue_count;
+        entropy_sum += s.entropy;
+
+        if s.symmetry.is_symmetric() {
+            summary.symmetric_puzzles += 1;
+        }
+    }
+
+    if summary.puzzles == 0 {
+        return StatsSummary::default();
+    }
+
+    summary.mean_clues = clue_sum as f64 / summary.puzzles as f64;
+    summary.mean_entropy = entropy_sum / summary.puzzles as f64;
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::symmetry::Symmetry;
+
+    fn example() -> SudokuGrid {
+        SudokuGrid::parse(
+            "3x2;\
+            1,2, , , , ,\
+             , , ,4, , ,\
+             , , , , , ,\
+             , , , , , ,\
+             , , , ,5, ,\
+             , , , , ,1",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn distributions() {
+        let stats = puzzle_stats(&example());
+
+        assert_eq!(6, stats.size());
+        assert_eq!(5, stats.clue_count());
+        assert_eq!(&[2, 1, 0, 0, 1, 1], stats.clues_per_row());
+        assert_eq!(&[1, 1, 0, 1, 1, 1], stats.clues_per_column());
+        assert_eq!(&[2, 1, 0, 0, 0, 2], stats.clues_per_block());
+        assert_eq!(&[2, 1, 0, 1, 1, 0], stats.digit_frequency());
+        assert!(!stats.symmetry().is_symmetric());
+    }
+
+    #[test]
+    fn entropy_bounds() {
+        let empty = puzzle_stats(&SudokuGrid::new(2, 2).unwrap());
+        let full = puzzle_stats(&SudokuGrid::parse("2x2;1,2,3,4,3,4,1,2,2,3,4,1,4,1,2,3").unwrap());
+        let single_digit = puzzle_stats(&SudokuGrid::parse("2x2;1,,,,,,1,,,,,,,,,").unwrap());
+
+        assert_eq!(0.0, empty.entropy());
+        assert!((full.entropy() - 2.0).abs() < 1e-9);
+        assert_eq!(0.0, single_digit.entropy());
+        assert!(full.symmetry().has(Symmetry::Rotation90));
+    }
+
+    #[test]
+    fn stats_serde_round_trip() {
+        let stats =
+            puzzle_stats(&SudokuGrid::parse("2x2;1,2,3,4,3,4,1,2,2,3,4,1,4,1,2,3").unwrap());
+        let json = serde_json::to_string(&stats).unwrap();
+
+        assert_eq!(stats, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn summary() {
+        let stats = vec![
+            puzzle_stats(&example()),
+            puzzle_stats(&SudokuGrid::new(3, 2).unwrap()),
+        ];
+        let summary = summarize(&stats);
+
+        assert_eq!(2, summary.puzzles());
+        assert_eq!(0, summary.min_clues());
+        assert_eq!(5, summary.max_clues());
+        assert_eq!(2.5, summary.mean_clues());
+        assert_eq!(1, summary.symmetric_puzzles());
+        assert_eq!(StatsSummary::default(), summarize(&[]));
+    }
+}
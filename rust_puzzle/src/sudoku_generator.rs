@@ -1,9 +1,11 @@
 // generate the sudokus
 // aka main programme
 
-use crate::constraint::{reducible, Constraint};
+use crate::constraint::{reducible, ClueDeriver, CompositeConstraint, Constraint};
 use crate::error::{SudokuError, SudokuResult};
-use crate::solver::{BacktrackingSolver, Solution, Solver};
+use crate::grader::{Difficulty, Grader, SearchEstimate};
+use crate::solver::{BacktrackingSolver, ParallelBacktrackingSolver, Solution, Solver};
+use crate::utilities::{shuffle_iter, Grid};
 use crate::{Sudoku, SudokuGrid};
 
 use rand::rngs::ThreadRng;
@@ -11,10 +13,74 @@ use rand::Rng;
 
 use rand_distr::Normal;
 
-use std::f64::consts::{self, FRAC_1_SQRT_2};
+use std::f64::consts::FRAC_1_SQRT_2;
+
+// The order in which the generator fills the empty cells of a grid. Scan
+// order is cheapest per step, but on heavily constrained variants the other
+// orders need far fewer backtracks.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FillOrder {
+    #[default]
+    Scan,
+    Random,
+    // The empty cell with the fewest valid numbers is filled next.
+    MostConstrained,
+}
+
+// Limits for filling grids. An attempt which needs more than
+// `max_backtracks` backtracks is abandoned and the fill restarts from the
+// initial grid with fresh random choices, until `max_attempts` attempts have
+// failed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RestartPolicy {
+    max_backtracks: usize,
+    max_attempts: usize,
+}
+
+impl RestartPolicy {
+    pub fn new(max_backtracks: usize, max_attempts: usize) -> RestartPolicy {
+        RestartPolicy {
+            max_backtracks,
+            max_attempts,
+        }
+    }
+
+    pub fn max_backtracks(&self) -> usize {
+        self.max_backtracks
+    }
+
+    pub fn max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FillStatistics {
+    attempts: usize,
+    backtracks: usize,
+}
+
+impl FillStatistics {
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    // The total number of backtracks over all attempts.
+    pub fn backtracks(&self) -> usize {
+        self.backtracks
+    }
+}
+
+enum FillOutcome {
+    Filled,
+    Unsatisfiable,
+    GaveUp,
+}
 
 pub struct Generator<R: Rng> {
     rng: R,
+    fill_order: FillOrder,
+    restart_policy: Option<RestartPolicy>,
 }
 
 impl Generator<ThreadRng> {
@@ -23,65 +89,155 @@ impl Generator<ThreadRng> {
     }
 }
 
-pub(crate) fn shuffle<T>(rng: &mut impl Rng, values: impl Iterator<Item = T>) -> Vec<T> {
-    let mut vec: Vec<T> = values.collect();
-    let len = vec.len();
+// Returns the empty cell with the fewest valid numbers, the first one in scan
+// order on ties.
+fn most_constrained_cell<C>(sudoku: &Sudoku<C>) -> Option<(usize, usize)>
+where
+    C: Constraint + Clone,
+{
+    let size = sudoku.grid().size();
+    let mut best: Option<((usize, usize), usize)> = None;
 
-    for i in 0..(len - 1) {
-        let j = rng.gen_range(i..len);
-        vec.swap(i, j);
+    for (column, row) in sudoku.grid().empty_cells() {
+        let options = (1..=size)
+            .filter(|&number| sudoku.is_valid_number(column, row, number).unwrap())
+            .count();
+
+        if best.is_none_or(|(_, best_options)| options < best_options) {
+            best = Some(((column, row), options));
+
+            if options <= 1 {
+                return Some((column, row));
+            }
+        }
     }
 
-    vec
+    best.map(|(cell, _)| cell)
 }
 
 impl<R: Rng> Generator<R> {
     pub fn new(rng: R) -> Generator<R> {
-        Generator { rng }
+        Generator {
+            rng,
+            fill_order: FillOrder::default(),
+            restart_policy: None,
+        }
     }
 
+    pub fn with_fill_order(mut self, fill_order: FillOrder) -> Generator<R> {
+        self.fill_order = fill_order;
+        self
+    }
+
+    pub fn fill_order(&self) -> FillOrder {
+        self.fill_order
+    }
+
+    // Without a restart policy, filling searches exhaustively in a single
+    // attempt.
+    pub fn with_restart_policy(mut self, restart_policy: RestartPolicy) -> Generator<R> {
+        self.restart_policy = Some(restart_policy);
+        self
+    }
+
+    pub fn restart_policy(&self) -> Option<RestartPolicy> {
+        self.restart_policy
+    }
+
+    // Fills the remaining cells, where `order` lists the cells in the order
+    // in which they are filled and `start` is the index of the first one
+    // which may still be empty. `order` is unused when filling the most
+    // constrained cell first. Gives up once `backtracks` exceeds `limit`.
     fn fill_rec<C: Constraint + Clone>(
         &mut self,
         sudoku: &mut Sudoku<C>,
-        column: usize,
-        row: usize,
-    ) -> bool {
+        order: &[(usize, usize)],
+        start: usize,
+        backtracks: &mut usize,
+        limit: usize,
+    ) -> FillOutcome {
         let size = sudoku.grid().size();
+        let (index, (column, row)) = if self.fill_order == FillOrder::MostConstrained {
+            match most_constrained_cell(sudoku) {
+                Some(cell) => (start, cell),
+                None => return FillOutcome::Filled,
+            }
+        } else {
+            match order[start..]
+                .iter()
+                .position(|&(c, r)| sudoku.grid().get_cell(c, r).unwrap().is_none())
+            {
+                Some(offset) => (start + offset, order[start + offset]),
+                None => return FillOutcome::Filled,
+            }
+        };
 
-        if row == size {
-            return true;
-        }
-
-        let next_column = (column + 1) % size;
-        let next_row = if next_column == 0 { row + 1 } else { row };
-
-        if sudoku.grid().get_cell(column, row).unwrap().is_some() {
-            return self.fill_rec(sudoku, next_column, next_row);
-        }
-
-        for number in shuffle(&mut self.rng, 1..=size) {
+        for number in shuffle_iter(&mut self.rng, 1..=size) {
             if sudoku.is_valid_number(column, row, number).unwrap() {
                 sudoku.grid_mut().set_cell(column, row, number).unwrap();
-                if self.fill_rec(sudoku, next_column, next_row) {
-                    return true;
+
+                match self.fill_rec(sudoku, order, index + 1, backtracks, limit) {
+                    FillOutcome::Unsatisfiable => {}
+                    outcome => return outcome,
                 }
 
                 sudoku.grid_mut().clear_cell(column, row).unwrap();
+                *backtracks += 1;
+
+                if *backtracks > limit {
+                    return FillOutcome::GaveUp;
+                }
             }
         }
 
-        false
+        FillOutcome::Unsatisfiable
     }
 
     pub fn fill<C>(&mut self, sudoku: &mut Sudoku<C>) -> SudokuResult<()>
     where
         C: Constraint + Clone,
     {
-        if self.fill_rec(sudoku, 0, 0) {
-            Ok(())
-        } else {
-            Err(SudokuError::UnsatisfiableConstraint)
+        self.fill_with_statistics(sudoku).map(|_| ())
+    }
+
+    // Fills the grid, restarting according to the restart policy. Fails with
+    // `SudokuError::FillExhausted` if every attempt gave up, in which case
+    // the grid is left as it was.
+    pub fn fill_with_statistics<C>(
+        &mut self,
+        sudoku: &mut Sudoku<C>,
+    ) -> SudokuResult<FillStatistics>
+    where
+        C: Constraint + Clone,
+    {
+        let size = sudoku.grid().size();
+        let initial = sudoku.grid().clone();
+        let (limit, max_attempts) = match self.restart_policy {
+            Some(policy) => (policy.max_backtracks, policy.max_attempts),
+            None => (usize::MAX, 1),
+        };
+        let mut statistics = FillStatistics::default();
+
+        while statistics.attempts < max_attempts {
+            let cells = (0..size).flat_map(|row| (0..size).map(move |column| (column, row)));
+            let order: Vec<(usize, usize)> = match self.fill_order {
+                FillOrder::Scan => cells.collect(),
+                FillOrder::Random => shuffle_iter(&mut self.rng, cells),
+                FillOrder::MostConstrained => Vec::new(),
+            };
+            let mut backtracks = 0;
+            let outcome = self.fill_rec(sudoku, &order, 0, &mut backtracks, limit);
+            statistics.attempts += 1;
+            statistics.backtracks += backtracks;
+
+            match outcome {
+                FillOutcome::Filled => return Ok(statistics),
+                FillOutcome::Unsatisfiable => return Err(SudokuError::UnsatisfiableConstraint),
+                FillOutcome::GaveUp => sudoku.grid_mut().assign(&initial)?,
+            }
         }
+
+        Err(SudokuError::FillExhausted(statistics))
     }
 
     pub fn generate<C>(
@@ -97,17 +253,64 @@ impl<R: Rng> Generator<R> {
         self.fill(&mut sudoku)?;
         Ok(sudoku)
     }
+
+    // Fills a grid under the given constraint, then adds every clue the
+    // deriver computes from the solution.
+    pub fn generate_with_clues<C, D>(
+        &mut self,
+        block_width: usize,
+        block_height: usize,
+        constraint: C,
+        deriver: &D,
+    ) -> SudokuResult<Sudoku<CompositeConstraint<C, D::Constraint>>>
+    where
+        C: Constraint + Clone + 'static,
+        D: ClueDeriver,
+        D::Constraint: 'static,
+    {
+        let (solution, constraint) = self
+            .generate(block_width, block_height, constraint)?
+            .into_raw_parts();
+        let clues = deriver.derive_clues(&solution);
+        Sudoku::new_with_grid(solution, CompositeConstraint::new(constraint, clues))
+    }
 }
 
+// Reductions with lower priority values are applied first. Priorities
+// should be finite; NaN is treated as 0 (see SanitizingPrioritizer).
 pub trait ReductionPrioritizer<R> {
     fn rough_priority(&mut self, reduction: &R) -> f64;
 }
 
-struct EqualPrioritizer;
+// Like ReductionPrioritizer, but with exact integer priorities. Reductions
+// of equal priority are applied in random order, no jitter is added.
+pub trait IntegerReductionPrioritizer<R> {
+    fn priority(&mut self, reduction: &R) -> i64;
+}
+
+pub struct SanitizingPrioritizer<P> {
+    prioritizer: P,
+}
+
+impl<P> SanitizingPrioritizer<P> {
+    pub fn new(prioritizer: P) -> SanitizingPrioritizer<P> {
+        SanitizingPrioritizer { prioritizer }
+    }
+
+    pub fn into_inner(self) -> P {
+        self.prioritizer
+    }
+}
+
+impl<R, P: ReductionPrioritizer<R>> ReductionPrioritizer<R> for SanitizingPrioritizer<P> {
+    fn rough_priority(&mut self, reduction: &R) -> f64 {
+        let priority = self.prioritizer.rough_priority(reduction);
 
-impl<R> ReductionPrioritizer<R> for EqualPrioritizer {
-    fn rough_priority(&mut self, _: &R) -> f64 {
-        0.0
+        if priority.is_nan() {
+            0.0
+        } else {
+            priority
+        }
     }
 }
 
@@ -117,9 +320,53 @@ impl<R, F: Fn(&R) -> f64> ReductionPrioritizer<R> for F {
     }
 }
 
+impl<R, F: Fn(&R) -> i64> IntegerReductionPrioritizer<R> for F {
+    fn priority(&mut self, reduction: &R) -> i64 {
+        self(reduction)
+    }
+}
+
+// Number of reductions graded per step in Reducer::reduce_adaptive.
+const ADAPTIVE_CANDIDATES: usize = 4;
+
+// Random noise added to rough priorities, so reductions of similar priority
+// are tried in varying order. The scale is the standard deviation of the
+// normal distribution and the half width of the uniform one. Without jitter,
+// reductions are ordered by priority alone, with ties broken by the RNG.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Jitter {
+    None,
+    Normal(f64),
+    Uniform(f64),
+}
+
+impl Default for Jitter {
+    fn default() -> Jitter {
+        Jitter::Normal(FRAC_1_SQRT_2)
+    }
+}
+
+impl Jitter {
+    fn is_valid(&self) -> bool {
+        match *self {
+            Jitter::None => true,
+            Jitter::Normal(scale) | Jitter::Uniform(scale) => scale.is_finite() && scale >= 0.0,
+        }
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        match *self {
+            Jitter::None => 0.0,
+            Jitter::Normal(scale) => rng.sample(Normal::new(0.0, scale).unwrap()),
+            Jitter::Uniform(scale) => rng.gen_range(-scale..=scale),
+        }
+    }
+}
+
 pub struct Reducer<S: Solver, R: Rng> {
     solver: S,
     rng: R,
+    jitter: Jitter,
 }
 
 impl Reducer<BacktrackingSolver, ThreadRng> {
@@ -128,6 +375,7 @@ impl Reducer<BacktrackingSolver, ThreadRng> {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Reduction<R> {
     RemoveDigit { column: usize, row: usize },
 
@@ -135,19 +383,61 @@ pub enum Reduction<R> {
 }
 
 impl<R> Reduction<R> {
-    fn apply<S, C>(&self, sudoku: &mut Sudoku<C>, solution: &SudokuGrid, solver: &S)
+    fn apply<S, C>(&self, sudoku: &mut Sudoku<C>, solution: &SudokuGrid, solver: &S) -> bool
+    where
+        S: Solver,
+        C: Constraint<Reduction = R> + Clone + 'static,
+    {
+        self.apply_if(sudoku, solution, solver, |_| true)
+    }
+
+    // Like apply, but also reverts the reduction if the reduced sudoku is
+    // not accepted.
+    fn apply_if<S, C, F>(
+        &self,
+        sudoku: &mut Sudoku<C>,
+        solution: &SudokuGrid,
+        solver: &S,
+        accept: F,
+    ) -> bool
     where
         S: Solver,
         C: Constraint<Reduction = R> + Clone + 'static,
+        F: Fn(&Sudoku<C>) -> bool,
+    {
+        let solve = |sudoku: &Sudoku<C>| match solver.solve(sudoku) {
+            Solution::Unique(_) if !accept(sudoku) => Solution::Ambiguous,
+            solution => solution,
+        };
+
+        if let Reduction::RemoveDigit { .. } = self {
+            return self.apply_with(sudoku, solution, solve);
+        }
+
+        solver.constraint_changed();
+        let applied = self.apply_with(sudoku, solution, solve);
+        solver.constraint_changed();
+        applied
+    }
+
+    fn apply_with<C, F>(&self, sudoku: &mut Sudoku<C>, solution: &SudokuGrid, solve: F) -> bool
+    where
+        C: Constraint<Reduction = R> + Clone + 'static,
+        F: Fn(&Sudoku<C>) -> Solution,
     {
         match self {
             Reduction::RemoveDigit { column, row } => {
-                let number = sudoku.grid().get_cell(*column, *row).unwrap().unwrap();
+                let number = match sudoku.grid().get_cell(*column, *row).unwrap() {
+                    Some(number) => number,
+                    None => return false,
+                };
                 sudoku.grid_mut().clear_cell(*column, *row).unwrap();
 
-                if let Solution::Unique(_) = solver.solve(sudoku) {
+                if let Solution::Unique(_) = solve(sudoku) {
+                    true
                 } else {
                     sudoku.grid_mut().set_cell(*column, *row, number).unwrap();
+                    false
                 }
             }
             Reduction::ReduceConstraint { reduction: r } => {
@@ -155,18 +445,44 @@ impl<R> Reduction<R> {
                 let reduce_res = constraint.reduce(solution, r);
 
                 if let Ok(revert_info) = reduce_res {
-                    if let Solution::Unique(_) = solver.solve(sudoku) {
+                    if let Solution::Unique(_) = solve(sudoku) {
+                        true
                     } else {
                         let constraint = sudoku.constraint_mut();
                         constraint.revert(solution, r, revert_info);
+                        false
                     }
+                } else {
+                    false
                 }
             }
         }
     }
 }
 
-fn reduction<R, C>(sudoku: &Sudoku<C>) -> impl Iterator<Item = Reduction<R>>
+// An entry of a reduction plan: a reduction and the priority, including
+// jitter, which placed it there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlannedReduction<R> {
+    reduction: Reduction<R>,
+    priority: f64,
+}
+
+impl<R> PlannedReduction<R> {
+    pub fn reduction(&self) -> &Reduction<R> {
+        &self.reduction
+    }
+
+    pub fn priority(&self) -> f64 {
+        self.priority
+    }
+
+    pub fn into_reduction(self) -> Reduction<R> {
+        self.reduction
+    }
+}
+
+fn reductions<R, C>(sudoku: &Sudoku<C>) -> impl Iterator<Item = Reduction<R>>
 where
     C: Constraint<Reduction = R> + Clone,
 {
@@ -181,39 +497,323 @@ where
     digit_reductions.chain(constraint_reductions)
 }
 
-fn prioritize<RED, P, RNG>(reduction: &RED, prioritizer: &mut P, rng: &mut RNG) -> f64
+fn prioritize<RED, P, RNG>(
+    reduction: &RED,
+    prioritizer: &mut P,
+    rng: &mut RNG,
+    jitter: Jitter,
+) -> f64
 where
     P: ReductionPrioritizer<RED>,
     RNG: Rng,
 {
-    let distr = Normal::new(0.0, consts::FRAC_1_SQRT_2).unwrap();
-    prioritizer.rough_priority(reduction) + rng.sample(distr)
+    prioritizer.rough_priority(reduction) + jitter.sample(rng)
 }
 
 impl<S: Solver, R: Rng> Reducer<S, R> {
     pub fn new(solver: S, rng: R) -> Reducer<S, R> {
-        Reducer { solver, rng }
+        Reducer {
+            solver,
+            rng,
+            jitter: Jitter::default(),
+        }
+    }
+
+    // Sets the jitter added to rough priorities by reduce_with_priority and
+    // plan_with_priority. Fails with InvalidJitter if the scale is negative
+    // or not finite.
+    pub fn with_jitter(mut self, jitter: Jitter) -> SudokuResult<Reducer<S, R>> {
+        if !jitter.is_valid() {
+            return Err(SudokuError::InvalidJitter);
+        }
+
+        self.jitter = jitter;
+        Ok(self)
+    }
+
+    pub fn jitter(&self) -> Jitter {
+        self.jitter
     }
 
     pub fn reduce<C>(&mut self, sudoku: &mut Sudoku<C>)
     where
         C: Constraint + Clone + 'static,
     {
-        self.reduce_with_priority(sudoku, EqualPrioritizer)
+        let plan = self.shuffled(sudoku);
+        self.apply_plan(sudoku, plan);
+    }
+
+    fn apply_all<C>(&self, sudoku: &mut Sudoku<C>, reductions: Vec<Reduction<C::Reduction>>)
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let solution = sudoku.grid().clone();
+
+        for reduction in reductions {
+            reduction.apply(sudoku, &solution, &self.solver);
+        }
+    }
+
+    pub fn reduce_with_priority<C, P>(&mut self, sudoku: &mut Sudoku<C>, prioritizer: P)
+    where
+        C: Constraint + Clone + 'static,
+        P: ReductionPrioritizer<Reduction<C::Reduction>>,
+    {
+        let plan = self.prioritized(sudoku, prioritizer);
+        self.apply_plan(sudoku, plan);
+    }
+
+    // Like reduce, but checks uniqueness with the given parallel solver
+    // instead of the reducer's own one. Uses the same RNG stream as reduce,
+    // so both produce the same puzzle for the same seed.
+    pub fn reduce_parallel<C>(
+        &mut self,
+        sudoku: &mut Sudoku<C>,
+        solver: &ParallelBacktrackingSolver,
+    ) where
+        C: Constraint + Clone + Send + Sync + 'static,
+    {
+        let solution = sudoku.grid().clone();
+
+        for planned in self.shuffled(sudoku) {
+            planned
+                .reduction
+                .apply_with(sudoku, &solution, |sudoku| solver.solve(sudoku));
+        }
+    }
+
+    fn prioritized<C, P>(
+        &mut self,
+        sudoku: &Sudoku<C>,
+        prioritizer: P,
+    ) -> Vec<PlannedReduction<C::Reduction>>
+    where
+        C: Constraint + Clone + 'static,
+        P: ReductionPrioritizer<Reduction<C::Reduction>>,
+    {
+        let mut prioritizer = SanitizingPrioritizer::new(prioritizer);
+
+        // Reductions of equal priority are ordered by a number drawn from the
+        // RNG rather than by listing order, so the result depends only on the
+        // RNG stream.
+        let mut reductions = reductions(sudoku)
+            .map(|r| {
+                let priority = prioritize(&r, &mut prioritizer, &mut self.rng, self.jitter);
+                let tie_breaker = self.rng.next_u64();
+                (priority, tie_breaker, r)
+            })
+            .collect::<Vec<_>>();
+        reductions.sort_by(|(p1, t1, _), (p2, t2, _)| p1.total_cmp(p2).then(t1.cmp(t2)));
+        reductions
+            .into_iter()
+            .map(|(priority, _, reduction)| PlannedReduction {
+                reduction,
+                priority,
+            })
+            .collect()
+    }
+
+    // The order of reduce, which draws one integer per reduction from the RNG
+    // and sorts by it. Without floating point arithmetic, a seed gives the
+    // same order on every platform.
+    fn shuffled<C>(&mut self, sudoku: &Sudoku<C>) -> Vec<PlannedReduction<C::Reduction>>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let mut reductions = reductions(sudoku)
+            .map(|r| (self.rng.next_u64(), r))
+            .collect::<Vec<_>>();
+        reductions.sort_by_key(|&(tie_breaker, _)| tie_breaker);
+        reductions
+            .into_iter()
+            .map(|(_, reduction)| PlannedReduction {
+                reduction,
+                priority: 0.0,
+            })
+            .collect()
     }
 
-    pub fn reduce_with_priority<C, P>(&mut self, sudoku: &mut Sudoku<C>, mut prioritizer: P)
+    // Computes the order in which reduce would try the reductions of the
+    // full grid without applying any of them. Uses the same RNG stream as
+    // reduce, so for the same seed, applying the whole plan gives the same
+    // puzzle.
+    pub fn plan<C>(&mut self, sudoku: &Sudoku<C>) -> Vec<PlannedReduction<C::Reduction>>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        self.shuffled(sudoku)
+    }
+
+    pub fn plan_with_priority<C, P>(
+        &mut self,
+        sudoku: &Sudoku<C>,
+        prioritizer: P,
+    ) -> Vec<PlannedReduction<C::Reduction>>
     where
         C: Constraint + Clone + 'static,
         P: ReductionPrioritizer<Reduction<C::Reduction>>,
+    {
+        self.prioritized(sudoku, prioritizer)
+    }
+
+    // Tries the planned reductions in the given order on the full grid, e.g.
+    // a selection from a plan, and returns for each of them whether it was
+    // applied, i.e. whether the solution stayed unique.
+    pub fn apply_plan<C, I>(&self, sudoku: &mut Sudoku<C>, plan: I) -> Vec<bool>
+    where
+        C: Constraint + Clone + 'static,
+        I: IntoIterator<Item = PlannedReduction<C::Reduction>>,
+    {
+        let solution = sudoku.grid().clone();
+
+        plan.into_iter()
+            .map(|planned| planned.reduction.apply(sudoku, &solution, &self.solver))
+            .collect()
+    }
+
+    pub fn reduce_with_integer_priority<C, P>(&mut self, sudoku: &mut Sudoku<C>, mut prioritizer: P)
+    where
+        C: Constraint + Clone + 'static,
+        P: IntegerReductionPrioritizer<Reduction<C::Reduction>>,
     {
         let mut reductions = reductions(sudoku)
-            .map(|r| (prioritize(&r, &mut prioritizer, &mut self.rng), r))
+            .map(|r| (prioritizer.priority(&r), self.rng.next_u64(), r))
             .collect::<Vec<_>>();
-        reductions.sort_by(|(p1, _), (p2, _)| p1.partial_cmp(p2).unwrap());
+        reductions.sort_by_key(|&(priority, tie_breaker, _)| (priority, tie_breaker));
+        let reductions = reductions.into_iter().map(|(_, _, r)| r).collect();
+        self.apply_all(sudoku, reductions);
+    }
+
+    // Removes the clues in all cells marked in the pattern, in random order,
+    // as long as the solution stays unique. Returns the marked cells whose
+    // clues had to be kept.
+    pub fn reduce_with_pattern<C>(
+        &mut self,
+        sudoku: &mut Sudoku<C>,
+        pattern: &Grid<bool>,
+    ) -> SudokuResult<Vec<(usize, usize)>>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        if pattern.size() != sudoku.grid().size() {
+            return Err(SudokuError::InvalidDimensions);
+        }
+
+        let marked_cells = pattern
+            .iter()
+            .filter(|&(column, row, &marked)| {
+                marked && sudoku.grid().get_cell(column, row).unwrap().is_some()
+            })
+            .map(|(column, row, _)| (column, row));
         let solution = sudoku.grid().clone();
-        for (_, reduction) in reductions {
-            reduction.apply(sudoku, &solution, &self.solver);
+        let mut kept = Vec::new();
+
+        for (column, row) in shuffle_iter(&mut self.rng, marked_cells) {
+            let reduction: Reduction<C::Reduction> = Reduction::RemoveDigit { column, row };
+
+            if !reduction.apply(sudoku, &solution, &self.solver) {
+                kept.push((column, row));
+            }
+        }
+
+        kept.sort_by_key(|&(column, row)| (row, column));
+        Ok(kept)
+    }
+
+    // Like reduce, but also rejects every reduction after which the grader
+    // rates the puzzle harder than the ceiling, so the result is a puzzle
+    // which is minimal among those within the ceiling. Returns the estimate
+    // of the final puzzle.
+    pub fn reduce_with_ceiling<C>(
+        &mut self,
+        sudoku: &mut Sudoku<C>,
+        grader: &Grader,
+        ceiling: Difficulty,
+    ) -> SudokuResult<SearchEstimate>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let solution = sudoku.grid().clone();
+        let within_ceiling = |sudoku: &Sudoku<C>| {
+            grader
+                .estimate_by_search(sudoku)
+                .is_ok_and(|estimate| estimate.difficulty() <= ceiling)
+        };
+
+        for planned in self.shuffled(sudoku) {
+            planned
+                .reduction
+                .apply_if(sudoku, &solution, &self.solver, within_ceiling);
+        }
+
+        grader.estimate_by_search(sudoku)
+    }
+
+    // Greedy reduction steered by the grader: in each step, a few random
+    // reductions are tried and graded, and the one which moves the puzzle
+    // towards the target difficulty is kept. While the puzzle is easier than
+    // the target, the hardest candidate is chosen, once it has reached the
+    // target, the easiest one, so it does not overshoot. Reductions which
+    // would make the puzzle harder than the target are never applied.
+    // Returns the estimate of the final puzzle.
+    pub fn reduce_adaptive<C>(
+        &mut self,
+        sudoku: &mut Sudoku<C>,
+        grader: &Grader,
+        target: Difficulty,
+    ) -> SudokuResult<SearchEstimate>
+    where
+        C: Constraint + Clone + 'static,
+    {
+        let mut estimate = grader.estimate_by_search(sudoku)?;
+        let solution = sudoku.grid().clone();
+        let mut pending = shuffle_iter(&mut self.rng, reductions(sudoku));
+
+        loop {
+            let harder = estimate.difficulty() < target;
+            let mut best: Option<(usize, Sudoku<C>, SearchEstimate)> = None;
+            let mut tried = 0;
+            let mut i = 0;
+
+            while tried < ADAPTIVE_CANDIDATES && i < pending.len() {
+                let mut trial = sudoku.clone();
+
+                // Reductions only ever add solutions and mostly make the
+                // puzzle harder, so rejected ones need not be tried again.
+                if !pending[i].apply(&mut trial, &solution, &self.solver) {
+                    pending.remove(i);
+                    continue;
+                }
+
+                let trial_estimate = grader.estimate_by_search(&trial)?;
+
+                if trial_estimate.difficulty() > target {
+                    pending.remove(i);
+                    continue;
+                }
+
+                let better = match &best {
+                    None => true,
+                    Some((_, _, e)) if harder => trial_estimate.score() > e.score(),
+                    Some((_, _, e)) => trial_estimate.score() < e.score(),
+                };
+
+                if better {
+                    best = Some((i, trial, trial_estimate));
+                }
+
+                tried += 1;
+                i += 1;
+            }
+
+            match best {
+                Some((index, trial, trial_estimate)) => {
+                    pending.remove(index);
+                    *sudoku = trial;
+                    estimate = trial_estimate;
+                }
+                None => return Ok(estimate),
+            }
         }
     }
 }
@@ -221,11 +821,405 @@ impl<S: Solver, R: Rng> Reducer<S, R> {
 #[cfg(test)]
 mod tests {
     use super::*;
+
     use crate::constraint::{
-        CompositeConstraint, DefaultConstraint, Group, KillerConstraint, ReductionError,
+        CompositeConstraint, ConsecutivePairsDeriver, DefaultConstraint, Group, KillerConstraint,
+        KnightsMoveConstraint, ReductionError,
     };
     use crate::solver::strategy::solvers::StrategicBacktrackingSolver;
     use crate::solver::strategy::{CompositeStrategy, NakedSingleStrategy, OnlyCellStrategy};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
 
     //LINE 389
+
+    fn classic_solution() -> SudokuGrid {
+        SudokuGrid::parse(
+            "3x3;\
+            7,4,6,2,8,1,3,5,9,\
+            9,1,2,5,3,7,8,4,6,\
+            8,5,3,4,9,6,1,7,2,\
+            3,7,4,1,2,5,6,9,8,\
+            6,2,8,7,4,9,5,1,3,\
+            5,9,1,3,6,8,7,2,4,\
+            1,6,9,8,7,4,2,3,5,\
+            2,8,5,9,1,3,4,6,7,\
+            4,3,7,6,5,2,9,8,1",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn reduce_with_pattern_only_removes_marked_cells() {
+        let solution = classic_solution();
+        let mut sudoku = Sudoku::new_with_grid(solution.clone(), DefaultConstraint).unwrap();
+        let pattern = Grid::from_fn(9, |column, row| (column + row) % 3 != 0);
+        let mut reducer = Reducer::new_default();
+        let kept = reducer.reduce_with_pattern(&mut sudoku, &pattern).unwrap();
+        let grid = sudoku.grid();
+
+        for (column, row, &marked) in pattern.iter() {
+            let cell = grid.get_cell(column, row).unwrap();
+
+            if !marked {
+                assert_eq!(solution.get_cell(column, row).unwrap(), cell);
+            } else if kept.contains(&(column, row)) {
+                assert!(cell.is_some());
+            } else {
+                assert!(cell.is_none());
+            }
+        }
+
+        let solver = BacktrackingSolver;
+        assert_eq!(Solution::Unique(solution), solver.solve(&sudoku));
+    }
+
+    #[test]
+    fn reduce_with_full_pattern_reports_required_clues() {
+        let mut sudoku = Sudoku::new_with_grid(classic_solution(), DefaultConstraint).unwrap();
+        let pattern = Grid::new(9, true);
+        let mut reducer = Reducer::new_default();
+        let kept = reducer.reduce_with_pattern(&mut sudoku, &pattern).unwrap();
+
+        assert_eq!(kept.len(), sudoku.grid().count_clues());
+        assert!(kept.len() >= 17);
+    }
+
+    fn seeded_generator(seed: u64) -> Generator<ChaCha8Rng> {
+        Generator::new(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    fn seeded_reducer(seed: u64) -> Reducer<BacktrackingSolver, ChaCha8Rng> {
+        Reducer::new(BacktrackingSolver, ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    fn generate_and_reduce(seed: u64) -> (SudokuGrid, SudokuGrid) {
+        let mut generator = seeded_generator(seed);
+        let mut sudoku = generator.generate(3, 2, DefaultConstraint).unwrap();
+        let solution = sudoku.grid().clone();
+        seeded_reducer(seed).reduce(&mut sudoku);
+        (solution, sudoku.grid().clone())
+    }
+
+    #[test]
+    fn generate_with_derived_clues() {
+        let mut generator = seeded_generator(7);
+        let mut sudoku = generator
+            .generate_with_clues(3, 2, DefaultConstraint, &ConsecutivePairsDeriver)
+            .unwrap();
+        let solution = sudoku.grid().clone();
+
+        assert!(solution.is_full());
+        assert!(sudoku.is_valid());
+        assert!(sudoku.constraint().second().markers().count() > 0);
+
+        seeded_reducer(7).reduce(&mut sudoku);
+        let solver = BacktrackingSolver;
+        assert_eq!(Solution::Unique(solution), solver.solve(&sudoku));
+    }
+
+    #[test]
+    fn adaptive_reduction_respects_target() {
+        let grader = Grader::new();
+
+        for seed in 0..3 {
+            let mut sudoku = seeded_generator(seed)
+                .generate(3, 2, DefaultConstraint)
+                .unwrap();
+            let solution = sudoku.grid().clone();
+            let estimate = seeded_reducer(seed)
+                .reduce_adaptive(&mut sudoku, &grader, Difficulty::Easy)
+                .unwrap();
+
+            assert_eq!(Difficulty::Easy, estimate.difficulty());
+            assert_eq!(estimate, grader.estimate_by_search(&sudoku).unwrap());
+            let solver = BacktrackingSolver;
+            assert_eq!(Solution::Unique(solution), solver.solve(&sudoku));
+        }
+    }
+
+    #[test]
+    fn ceiling_reduction_stays_within_ceiling() {
+        let grader = Grader::new();
+        let mut sudoku = seeded_generator(5)
+            .generate(3, 2, DefaultConstraint)
+            .unwrap();
+        let solution = sudoku.grid().clone();
+        let estimate = seeded_reducer(5)
+            .reduce_with_ceiling(&mut sudoku, &grader, Difficulty::Easy)
+            .unwrap();
+
+        assert_eq!(Difficulty::Easy, estimate.difficulty());
+        assert!(sudoku.grid().count_clues() < 36);
+        let solver = BacktrackingSolver;
+        assert_eq!(Solution::Unique(solution), solver.solve(&sudoku));
+    }
+
+    #[test]
+    fn adaptive_reduction_towards_harder_target() {
+        let grader = Grader::new();
+        let mut easy = seeded_generator(3)
+            .generate(3, 2, DefaultConstraint)
+            .unwrap();
+        let mut hard = easy.clone();
+        let easy_estimate = seeded_reducer(3)
+            .reduce_adaptive(&mut easy, &grader, Difficulty::Easy)
+            .unwrap();
+        let hard_estimate = seeded_reducer(3)
+            .reduce_adaptive(&mut hard, &grader, Difficulty::Expert)
+            .unwrap();
+
+        assert!(hard_estimate.score() > easy_estimate.score());
+    }
+
+    #[test]
+    fn parallel_reduction_matches_sequential() {
+        let mut sequential = seeded_generator(11)
+            .generate(3, 2, DefaultConstraint)
+            .unwrap();
+        let mut parallel = sequential.clone();
+        seeded_reducer(11).reduce(&mut sequential);
+        seeded_reducer(11).reduce_parallel(&mut parallel, &ParallelBacktrackingSolver::new(3));
+
+        assert_eq!(sequential.grid(), parallel.grid());
+    }
+
+    #[test]
+    fn every_fill_order_fills_anti_knight_grids() {
+        let constraint = CompositeConstraint::new(DefaultConstraint, KnightsMoveConstraint);
+
+        for fill_order in [
+            FillOrder::Scan,
+            FillOrder::Random,
+            FillOrder::MostConstrained,
+        ] {
+            let mut sudoku = Sudoku::parse("2x2;1,,,,,,,,,,,,,,,3", constraint.clone()).unwrap();
+            seeded_generator(5)
+                .with_fill_order(fill_order)
+                .fill(&mut sudoku)
+                .unwrap();
+
+            assert!(sudoku.grid().is_full());
+            assert!(sudoku.is_valid());
+            assert_eq!(Some(1), sudoku.grid().get_cell(0, 0).unwrap());
+        }
+
+        let sudoku = seeded_generator(5)
+            .with_fill_order(FillOrder::MostConstrained)
+            .generate(3, 3, constraint)
+            .unwrap();
+        assert!(sudoku.grid().is_full());
+        assert!(sudoku.is_valid());
+    }
+
+    #[test]
+    fn restarts_give_up_with_statistics() {
+        let constraint = CompositeConstraint::new(DefaultConstraint, KnightsMoveConstraint);
+        let mut sudoku = Sudoku::new_empty(3, 3, constraint).unwrap();
+        let result = seeded_generator(5)
+            .with_restart_policy(RestartPolicy::new(10, 3))
+            .fill_with_statistics(&mut sudoku);
+
+        match result {
+            Err(SudokuError::FillExhausted(statistics)) => {
+                assert_eq!(3, statistics.attempts());
+                assert_eq!(33, statistics.backtracks());
+            }
+            _ => panic!("expected the fill to give up"),
+        }
+
+        assert!(sudoku.grid().is_empty());
+    }
+
+    #[test]
+    fn restarts_keep_unsatisfiable_grids_unsatisfiable() {
+        let mut sudoku = Sudoku::parse("2x2;1,2,,,,,3,,,,4,,,,,", DefaultConstraint).unwrap();
+        let result = seeded_generator(5)
+            .with_restart_policy(RestartPolicy::new(100, 3))
+            .fill(&mut sudoku);
+
+        assert_eq!(Err(SudokuError::UnsatisfiableConstraint), result);
+    }
+
+    #[test]
+    fn restarts_fill_solvable_grids() {
+        let mut sudoku = Sudoku::new_empty(3, 2, DefaultConstraint).unwrap();
+        let statistics = seeded_generator(5)
+            .with_restart_policy(RestartPolicy::new(1000, 3))
+            .fill_with_statistics(&mut sudoku)
+            .unwrap();
+
+        assert_eq!(1, statistics.attempts());
+        assert!(sudoku.grid().is_full());
+    }
+
+    #[test]
+    fn seeded_generation_is_reproducible() {
+        for seed in 0..5 {
+            assert_eq!(generate_and_reduce(seed), generate_and_reduce(seed));
+        }
+    }
+
+    #[test]
+    fn seeded_generation_matches_reference() {
+        // Pinned output for a fixed seed, guarding against any change in
+        // how random numbers are consumed. Neither generation nor the
+        // default reduction uses floating point numbers, so the output is the
+        // same on every platform.
+        let (solution, puzzle) = generate_and_reduce(1234);
+
+        let expected_solution = SudokuGrid::parse(
+            "3x2;\
+            3,6,5,4,2,1,\
+            1,2,4,3,5,6,\
+            2,3,6,5,1,4,\
+            5,4,1,2,6,3,\
+            6,5,3,1,4,2,\
+            4,1,2,6,3,5",
+        )
+        .unwrap();
+        let expected_puzzle = SudokuGrid::parse(
+            "3x2;\
+             , , ,4,2, ,\
+            1, , , , , ,\
+            2,3, , , , ,\
+             , , , ,6, ,\
+             ,5, ,1, , ,\
+             , ,2, , ,5",
+        )
+        .unwrap();
+
+        assert_eq!(expected_solution, solution);
+        assert_eq!(expected_puzzle, puzzle);
+    }
+
+    #[test]
+    fn nan_priorities_do_not_panic() {
+        let mut sudoku = Sudoku::new_with_grid(classic_solution(), DefaultConstraint).unwrap();
+        let mut reducer = Reducer::new_default();
+        let prioritizer = |reduction: &Reduction<()>| match reduction {
+            Reduction::RemoveDigit { column, .. } if column % 2 == 0 => f64::NAN,
+            _ => 1.0,
+        };
+        reducer.reduce_with_priority(&mut sudoku, prioritizer);
+
+        let solver = BacktrackingSolver;
+        assert_eq!(Solution::Unique(classic_solution()), solver.solve(&sudoku));
+    }
+
+    #[test]
+    fn reduction_without_jitter_follows_priorities() {
+        // As in integer_priorities_are_applied_in_order, the first row ends up
+        // empty, regardless of the seed.
+        for seed in 0..4 {
+            let mut sudoku = Sudoku::new_with_grid(classic_solution(), DefaultConstraint).unwrap();
+            let mut reducer = Reducer::new(BacktrackingSolver, ChaCha8Rng::seed_from_u64(seed))
+                .with_jitter(Jitter::None)
+                .unwrap();
+            let prioritizer = |reduction: &Reduction<()>| match reduction {
+                Reduction::RemoveDigit { row: 0, .. } => 0.0,
+                _ => 0.1,
+            };
+            reducer.reduce_with_priority(&mut sudoku, prioritizer);
+
+            for column in 0..9 {
+                assert_eq!(None, sudoku.grid().get_cell(column, 0).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn applying_a_plan_matches_reduce() {
+        let mut reduced = Sudoku::new_with_grid(classic_solution(), DefaultConstraint).unwrap();
+        let mut planned = reduced.clone();
+        Reducer::new(BacktrackingSolver, ChaCha8Rng::seed_from_u64(3)).reduce(&mut reduced);
+        let mut reducer = Reducer::new(BacktrackingSolver, ChaCha8Rng::seed_from_u64(3));
+        let plan = reducer.plan(&planned);
+
+        assert_eq!(81, plan.len());
+        assert!(plan.windows(2).all(|w| w[0].priority() <= w[1].priority()));
+        assert_eq!(classic_solution(), *planned.grid());
+
+        reducer.apply_plan(&mut planned, plan);
+
+        assert_eq!(reduced.grid(), planned.grid());
+    }
+
+    #[test]
+    fn selected_plan_entries_are_applied() {
+        let mut sudoku = Sudoku::new_with_grid(classic_solution(), DefaultConstraint).unwrap();
+        let mut reducer = Reducer::new_default();
+        let plan = reducer.plan(&sudoku);
+        let first_row = plan
+            .into_iter()
+            .filter(|p| matches!(p.reduction(), Reduction::RemoveDigit { row: 0, .. }));
+        let applied = reducer.apply_plan(&mut sudoku, first_row);
+
+        assert_eq!(vec![true; 9], applied);
+        assert_eq!(72, sudoku.grid().count_clues());
+    }
+
+    #[test]
+    fn invalid_jitter_is_rejected() {
+        assert_eq!(
+            Some(SudokuError::InvalidJitter),
+            Reducer::new_default()
+                .with_jitter(Jitter::Normal(-1.0))
+                .err()
+        );
+        assert_eq!(
+            Some(SudokuError::InvalidJitter),
+            Reducer::new_default()
+                .with_jitter(Jitter::Uniform(f64::NAN))
+                .err()
+        );
+        assert_eq!(
+            Jitter::Uniform(0.5),
+            Reducer::new_default()
+                .with_jitter(Jitter::Uniform(0.5))
+                .unwrap()
+                .jitter()
+        );
+    }
+
+    #[test]
+    fn sanitizing_prioritizer_replaces_nan() {
+        let mut prioritizer = SanitizingPrioritizer::new(|&x: &f64| x);
+
+        assert_eq!(0.0, prioritizer.rough_priority(&f64::NAN));
+        assert_eq!(2.5, prioritizer.rough_priority(&2.5));
+        assert_eq!(f64::INFINITY, prioritizer.rough_priority(&f64::INFINITY));
+    }
+
+    #[test]
+    fn integer_priorities_are_applied_in_order() {
+        // Any single row can be removed from a full grid without losing
+        // uniqueness, so if the first row is reduced first, it ends up empty.
+        let mut sudoku = Sudoku::new_with_grid(classic_solution(), DefaultConstraint).unwrap();
+        let mut reducer = Reducer::new_default();
+        let prioritizer = |reduction: &Reduction<()>| match reduction {
+            Reduction::RemoveDigit { row: 0, .. } => 0,
+            _ => 1,
+        };
+        reducer.reduce_with_integer_priority(&mut sudoku, prioritizer);
+
+        for column in 0..9 {
+            assert_eq!(None, sudoku.grid().get_cell(column, 0).unwrap());
+        }
+
+        let solver = BacktrackingSolver;
+        assert_eq!(Solution::Unique(classic_solution()), solver.solve(&sudoku));
+    }
+
+    #[test]
+    fn reduce_with_pattern_of_wrong_size() {
+        let mut sudoku = Sudoku::new_with_grid(classic_solution(), DefaultConstraint).unwrap();
+        let pattern = Grid::new(4, true);
+        let mut reducer = Reducer::new_default();
+
+        assert_eq!(
+            Err(SudokuError::InvalidDimensions),
+            reducer.reduce_with_pattern(&mut sudoku, &pattern)
+        );
+    }
 }
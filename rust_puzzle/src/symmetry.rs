@@ -0,0 +1,173 @@
+use crate::SudokuGrid;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum Symmetry {
+    Rotation180,
+    Rotation90,
+    // Mirrored along the vertical axis, i.e. left and right are swapped.
+    HorizontalMirror,
+    // Mirrored along the horizontal axis, i.e. top and bottom are swapped.
+    VerticalMirror,
+    // Mirrored along the top-left to bottom-right diagonal.
+    MainDiagonal,
+    // Mirrored along the top-right to bottom-left diagonal.
+    AntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 6] = [
+        Symmetry::Rotation180,
+        Symmetry::Rotation90,
+        Symmetry::HorizontalMirror,
+        Symmetry::VerticalMirror,
+        Symmetry::MainDiagonal,
+        Symmetry::AntiDiagonal,
+    ];
+
+    // Returns the cell the given cell is mapped to in a grid of the given
+    // size.
+    pub fn map(self, column: usize, row: usize, size: usize) -> (usize, usize) {
+        let last = size - 1;
+
+        match self {
+            Symmetry::Rotation180 => (last - column, last - row),
+            Symmetry::Rotation90 => (last - row, column),
+            Symmetry::HorizontalMirror => (last - column, row),
+            Symmetry::VerticalMirror => (column, last - row),
+            Symmetry::MainDiagonal => (row, column),
+            Symmetry::AntiDiagonal => (last - row, last - column),
+        }
+    }
+
+    // Returns all cells which are mapped onto each other by repeatedly
+    // applying this symmetry, starting with the given cell.
+    pub fn orbit(self, column: usize, row: usize, size: usize) -> Vec<(usize, usize)> {
+        let mut orbit = vec![(column, row)];
+        let mut current = self.map(column, row, size);
+
+        while current != (column, row) {
+            orbit.push(current);
+            current = self.map(current.0, current.1, size);
+        }
+
+        orbit
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct SymmetryReport {
+    symmetries: Vec<Symmetry>,
+}
+
+impl SymmetryReport {
+    pub fn symmetries(&self) -> &[Symmetry] {
+        &self.symmetries
+    }
+
+    pub fn has(&self, symmetry: Symmetry) -> bool {
+        self.symmetries.contains(&symmetry)
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        !self.symmetries.is_empty()
+    }
+}
+
+fn is_clue(grid: &SudokuGrid, column: usize, row: usize) -> bool {
+    grid.get_cell(column, row).unwrap().is_some()
+}
+
+fn satisfies(grid: &SudokuGrid, symmetry: Symmetry) -> bool {
+    let size = grid.size();
+
+    (0..size).all(|row| {
+        (0..size).all(|column| {
+            let (mapped_column, mapped_row) = symmetry.map(column, row, size);
+            is_clue(grid, column, row) == is_clue(grid, mapped_column, mapped_row)
+        })
+    })
+}
+
+// Reports which symmetries the pattern of clues satisfies. Only the
+// positions of the clues are considered, not their digits.
+pub fn detect_symmetry(grid: &SudokuGrid) -> SymmetryReport {
+    let symmetries = Symmetry::ALL
+        .iter()
+        .copied()
+        .filter(|&symmetry| satisfies(grid, symmetry))
+        .collect();
+
+    SymmetryReport { symmetries }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn orbits_have_expected_length() {
+        assert_eq!(vec![(0, 0), (8, 8)], Symmetry::Rotation180.orbit(0, 0, 9));
+        assert_eq!(vec![(4, 4)], Symmetry::Rotation180.orbit(4, 4, 9));
+        assert_eq!(
+            vec![(1, 0), (8, 1), (7, 8), (0, 7)],
+            Symmetry::Rotation90.orbit(1, 0, 9)
+        );
+        assert_eq!(vec![(2, 2)], Symmetry::MainDiagonal.orbit(2, 2, 4));
+        assert_eq!(vec![(0, 0), (3, 3)], Symmetry::AntiDiagonal.orbit(0, 0, 4));
+    }
+
+    #[test]
+    fn empty_and_full_grids_have_all_symmetries() {
+        let empty = SudokuGrid::new(2, 2).unwrap();
+        let full = SudokuGrid::parse("2x2;1,2,3,4,3,4,1,2,2,3,4,1,4,1,2,3").unwrap();
+
+        assert_eq!(&Symmetry::ALL[..], detect_symmetry(&empty).symmetries());
+        assert_eq!(&Symmetry::ALL[..], detect_symmetry(&full).symmetries());
+    }
+
+    #[test]
+    fn rotational_pattern() {
+        let grid = SudokuGrid::parse(
+            "2x2;\
+            1, , , ,\
+             , ,2, ,\
+             ,3, , ,\
+             , , ,4",
+        )
+        .unwrap();
+        let report = detect_symmetry(&grid);
+
+        assert!(report.has(Symmetry::Rotation180));
+        assert!(!report.has(Symmetry::Rotation90));
+        assert!(!report.has(Symmetry::HorizontalMirror));
+        assert!(!report.has(Symmetry::VerticalMirror));
+        assert!(report.has(Symmetry::MainDiagonal));
+        assert!(report.has(Symmetry::AntiDiagonal));
+    }
+
+    #[test]
+    fn mirror_pattern() {
+        let grid = SudokuGrid::parse(
+            "2x2;\
+            1, , ,2,\
+             , , , ,\
+             , , , ,\
+             , , , ",
+        )
+        .unwrap();
+        let report = detect_symmetry(&grid);
+
+        assert_eq!(&[Symmetry::HorizontalMirror][..], report.symmetries());
+        assert!(report.is_symmetric());
+    }
+
+    #[test]
+    fn asymmetric_pattern() {
+        let grid = SudokuGrid::parse("2x2; ,1, , , , , , , , , , , , , , ").unwrap();
+
+        assert!(!detect_symmetry(&grid).is_symmetric());
+    }
+}
@@ -0,0 +1,587 @@
+use crate::constraint::{
+    AdjacentConsecutiveConstraint, BlockConstraint, ColumnConstraint, Constraint,
+    DefaultConstraint, DiagonallyAdjacentConstraint, DiagonalsConstraint, KingsMoveConstraint,
+    KnightsMoveConstraint, RowConstraint,
+};
+use crate::error::{SudokuError, SudokuResult};
+use crate::solver::{Solution, Solver};
+use crate::utilities::shuffle_iter;
+use crate::{Sudoku, SudokuGrid};
+
+use rand::Rng;
+
+// A validity-preserving transformation of a grid: numbers are relabeled,
+// rows are permuted within bands and bands among each other (likewise
+// columns within and among stacks), and finally the grid may be transposed,
+// which swaps the block width and height.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GridTransform {
+    block_width: usize,
+    block_height: usize,
+    numbers: Vec<usize>,
+    rows: Vec<usize>,
+    columns: Vec<usize>,
+    transpose: bool,
+}
+
+// Permutes the groups of a partition of 0..group_size * group_count into
+// consecutive groups, as well as the elements within each group.
+fn grouped_permutation(rng: &mut impl Rng, group_size: usize, group_count: usize) -> Vec<usize> {
+    let groups = shuffle_iter(rng, 0..group_count);
+    let mut result = vec![0; group_size * group_count];
+
+    for (old_group, &new_group) in groups.iter().enumerate() {
+        let offsets = shuffle_iter(rng, 0..group_size);
+
+        for (old_offset, new_offset) in offsets.into_iter().enumerate() {
+            result[old_group * group_size + old_offset] = new_group * group_size + new_offset;
+        }
+    }
+
+    result
+}
+
+impl GridTransform {
+    pub fn identity(block_width: usize, block_height: usize) -> GridTransform {
+        let size = block_width * block_height;
+
+        GridTransform {
+            block_width,
+            block_height,
+            numbers: (1..=size).collect(),
+            rows: (0..size).collect(),
+            columns: (0..size).collect(),
+            transpose: false,
+        }
+    }
+
+    pub fn transposition(block_width: usize, block_height: usize) -> GridTransform {
+        GridTransform {
+            transpose: true,
+            ..GridTransform::identity(block_width, block_height)
+        }
+    }
+
+    // The number n is replaced by numbers[n - 1], which must be a
+    // permutation of 1..=size.
+    pub fn relabeling(
+        block_width: usize,
+        block_height: usize,
+        numbers: Vec<usize>,
+    ) -> SudokuResult<GridTransform> {
+        let size = block_width * block_height;
+        let mut sorted = numbers.clone();
+        sorted.sort_unstable();
+
+        if !sorted.iter().cloned().eq(1..=size) {
+            return Err(SudokuError::InvalidNumber);
+        }
+
+        Ok(GridTransform {
+            numbers,
+            ..GridTransform::identity(block_width, block_height)
+        })
+    }
+
+    pub fn random(block_width: usize, block_height: usize, rng: &mut impl Rng) -> GridTransform {
+        let size = block_width * block_height;
+
+        GridTransform {
+            block_width,
+            block_height,
+            numbers: shuffle_iter(rng, 1..=size),
+            rows: grouped_permutation(rng, block_height, block_width),
+            columns: grouped_permutation(rng, block_width, block_height),
+            transpose: rng.gen(),
+        }
+    }
+
+    pub fn block_width(&self) -> usize {
+        self.block_width
+    }
+
+    pub fn block_height(&self) -> usize {
+        self.block_height
+    }
+
+    pub fn size(&self) -> usize {
+        self.block_width * self.block_height
+    }
+
+    pub fn is_transposing(&self) -> bool {
+        self.transpose
+    }
+
+    // The block width and height of transformed grids.
+    pub fn target_dimensions(&self) -> (usize, usize) {
+        if self.transpose {
+            (self.block_height, self.block_width)
+        } else {
+            (self.block_width, self.block_height)
+        }
+    }
+
+    pub fn map_cell(&self, column: usize, row: usize) -> (usize, usize) {
+        let column = self.columns[column];
+        let row = self.rows[row];
+
+        if self.transpose {
+            (row, column)
+        } else {
+            (column, row)
+        }
+    }
+
+    pub fn map_number(&self, number: usize) -> usize {
+        self.numbers[number - 1]
+    }
+
+    // The transformation which maps transformed grids back to the original.
+    pub fn inverse(&self) -> GridTransform {
+        let invert = |permutation: &[usize], offset: usize| {
+            let mut inverse = vec![0; permutation.len()];
+
+            for (i, &p) in permutation.iter().enumerate() {
+                inverse[p - offset] = i + offset;
+            }
+
+            inverse
+        };
+        let (block_width, block_height) = self.target_dimensions();
+        let (rows, columns) = if self.transpose {
+            (invert(&self.columns, 0), invert(&self.rows, 0))
+        } else {
+            (invert(&self.rows, 0), invert(&self.columns, 0))
+        };
+
+        GridTransform {
+            block_width,
+            block_height,
+            numbers: invert(&self.numbers, 1),
+            rows,
+            columns,
+            transpose: self.transpose,
+        }
+    }
+
+    pub fn apply_to_grid(&self, grid: &SudokuGrid) -> SudokuResult<SudokuGrid> {
+        if grid.block_width() != self.block_width || grid.block_height() != self.block_height {
+            return Err(SudokuError::InvalidDimensions);
+        }
+
+        let (block_width, block_height) = self.target_dimensions();
+        let mut result = SudokuGrid::new(block_width, block_height)?;
+        let size = self.size();
+
+        for row in 0..size {
+            for column in 0..size {
+                if let Some(number) = grid.get_cell(column, row)? {
+                    let (new_column, new_row) = self.map_cell(column, row);
+                    result.set_cell(new_column, new_row, self.map_number(number))?;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+// Constraints that can follow a GridTransform, e.g. by moving the cells they
+// refer to. Constraints that relate numbers to each other or depend on the
+// position of cells beyond rows, columns and blocks can restrict which kinds
+// of transformations scramble uses.
+pub trait TransformableConstraint: Constraint + Clone {
+    fn allows_relabeling(&self) -> bool {
+        true
+    }
+
+    fn allows_permutation(&self) -> bool {
+        true
+    }
+
+    fn transform(&mut self, transform: &GridTransform);
+}
+
+impl TransformableConstraint for DefaultConstraint {
+    fn transform(&mut self, _: &GridTransform) {}
+}
+
+impl TransformableConstraint for RowConstraint {
+    fn transform(&mut self, _: &GridTransform) {}
+}
+
+impl TransformableConstraint for ColumnConstraint {
+    fn transform(&mut self, _: &GridTransform) {}
+}
+
+impl TransformableConstraint for BlockConstraint {
+    fn transform(&mut self, _: &GridTransform) {}
+}
+
+// Transposition keeps both diagonals in place, permutations do not.
+impl TransformableConstraint for DiagonalsConstraint {
+    fn allows_permutation(&self) -> bool {
+        false
+    }
+
+    fn transform(&mut self, _: &GridTransform) {}
+}
+
+// Transposition preserves knight's move distances, permutations do not.
+impl TransformableConstraint for KnightsMoveConstraint {
+    fn allows_permutation(&self) -> bool {
+        false
+    }
+
+    fn transform(&mut self, _: &GridTransform) {}
+}
+
+// Likewise for king's move and diagonal neighbors.
+impl TransformableConstraint for KingsMoveConstraint {
+    fn allows_permutation(&self) -> bool {
+        false
+    }
+
+    fn transform(&mut self, _: &GridTransform) {}
+}
+
+impl TransformableConstraint for DiagonallyAdjacentConstraint {
+    fn allows_permutation(&self) -> bool {
+        false
+    }
+
+    fn transform(&mut self, _: &GridTransform) {}
+}
+
+// Relabeling breaks consecutiveness, so only transposition is possible.
+impl TransformableConstraint for AdjacentConsecutiveConstraint {
+    fn allows_relabeling(&self) -> bool {
+        false
+    }
+
+    fn allows_permutation(&self) -> bool {
+        false
+    }
+
+    fn transform(&mut self, _: &GridTransform) {}
+}
+
+pub fn transform_sudoku<C>(sudoku: &Sudoku<C>, transform: &GridTransform) -> SudokuResult<Sudoku<C>>
+where
+    C: TransformableConstraint,
+{
+    let grid = transform.apply_to_grid(sudoku.grid())?;
+    let mut constraint = sudoku.constraint().clone();
+    constraint.transform(transform);
+    Sudoku::new_with_grid(grid, constraint)
+}
+
+// Applies a random transformation among those the constraint allows, so one
+// puzzle yields many cosmetic variants of the same difficulty.
+pub fn scramble<C, R>(sudoku: &Sudoku<C>, rng: &mut R) -> Sudoku<C>
+where
+    C: TransformableConstraint,
+    R: Rng,
+{
+    let transform = scrambling_transform(sudoku, rng);
+    transform_sudoku(sudoku, &transform).unwrap()
+}
+
+fn scrambling_transform<C, R>(sudoku: &Sudoku<C>, rng: &mut R) -> GridTransform
+where
+    C: TransformableConstraint,
+    R: Rng,
+{
+    let grid = sudoku.grid();
+    let (block_width, block_height) = (grid.block_width(), grid.block_height());
+    let random = GridTransform::random(block_width, block_height, rng);
+    let identity = GridTransform::identity(block_width, block_height);
+    let constraint = sudoku.constraint();
+
+    GridTransform {
+        numbers: if constraint.allows_relabeling() {
+            random.numbers
+        } else {
+            identity.numbers
+        },
+        rows: if constraint.allows_permutation() {
+            random.rows
+        } else {
+            identity.rows
+        },
+        columns: if constraint.allows_permutation() {
+            random.columns
+        } else {
+            identity.columns
+        },
+        ..random
+    }
+}
+
+// A transformed puzzle which remembers how it was derived from the original,
+// so solutions, hints and cell coordinates found for it can be mapped back.
+#[derive(Clone)]
+pub struct TransformedSudoku<C: TransformableConstraint> {
+    sudoku: Sudoku<C>,
+    transform: GridTransform,
+    inverse: GridTransform,
+}
+
+impl<C: TransformableConstraint> TransformedSudoku<C> {
+    pub fn new(
+        original: &Sudoku<C>,
+        transform: GridTransform,
+    ) -> SudokuResult<TransformedSudoku<C>> {
+        Ok(TransformedSudoku {
+            sudoku: transform_sudoku(original, &transform)?,
+            inverse: transform.inverse(),
+            transform,
+        })
+    }
+
+    // Like scramble, but keeps the transformation.
+    pub fn scramble<R: Rng>(original: &Sudoku<C>, rng: &mut R) -> TransformedSudoku<C> {
+        let transform = scrambling_transform(original, rng);
+        TransformedSudoku::new(original, transform).unwrap()
+    }
+
+    pub fn sudoku(&self) -> &Sudoku<C> {
+        &self.sudoku
+    }
+
+    pub fn transform(&self) -> &GridTransform {
+        &self.transform
+    }
+
+    pub fn original_cell(&self, column: usize, row: usize) -> (usize, usize) {
+        self.inverse.map_cell(column, row)
+    }
+
+    pub fn original_number(&self, number: usize) -> usize {
+        self.inverse.map_number(number)
+    }
+
+    // Maps a grid in the orientation of the transformed puzzle, e.g. a
+    // partial solution, back to the orientation of the original.
+    pub fn original_grid(&self, grid: &SudokuGrid) -> SudokuResult<SudokuGrid> {
+        self.inverse.apply_to_grid(grid)
+    }
+
+    pub fn original_solution(&self, solution: Solution) -> Solution {
+        match solution {
+            Solution::Unique(grid) => Solution::Unique(self.original_grid(&grid).unwrap()),
+            other => other,
+        }
+    }
+
+    // Solves the transformed puzzle and returns the solution in the
+    // orientation of the original.
+    pub fn solve_original<S: Solver>(&self, solver: &S) -> Solution
+    where
+        C: 'static,
+    {
+        self.original_solution(solver.solve(&self.sudoku))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{CompositeConstraint, GivenCandidatesConstraint};
+    use crate::set;
+    use crate::solver::BacktrackingSolver;
+    use crate::utilities::USizeSet;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use std::collections::HashSet;
+
+    fn solution() -> SudokuGrid {
+        SudokuGrid::parse(
+            "3x2;\
+            1,2,3,4,5,6,\
+            4,5,6,1,2,3,\
+            2,3,1,5,6,4,\
+            5,6,4,2,3,1,\
+            3,1,2,6,4,5,\
+            6,4,5,3,1,2",
+        )
+        .unwrap()
+    }
+
+    // Scrambles a puzzle whose clues become invalid under some permutations
+    // or relabelings.
+    fn assert_scrambles_stay_valid<C>(code: &str, constraint: C)
+    where
+        C: TransformableConstraint + 'static,
+    {
+        let mut rng = ChaCha8Rng::seed_from_u64(9);
+        let puzzle = Sudoku::parse(code, constraint).unwrap();
+
+        for _ in 0..20 {
+            assert!(scramble(&puzzle, &mut rng).is_valid());
+        }
+    }
+
+    #[test]
+    fn random_transform_maps_cells_bijectively() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let transform = GridTransform::random(3, 2, &mut rng);
+        let cells: HashSet<(usize, usize)> = (0..36)
+            .map(|index| transform.map_cell(index % 6, index / 6))
+            .collect();
+        let numbers: HashSet<usize> = (1..=6).map(|n| transform.map_number(n)).collect();
+
+        assert_eq!(36, cells.len());
+        assert_eq!(6, numbers.len());
+    }
+
+    #[test]
+    fn transposition_swaps_dimensions() {
+        let transform = GridTransform::transposition(3, 2);
+        let transposed = transform.apply_to_grid(&solution()).unwrap();
+
+        assert_eq!(
+            (2, 3),
+            (transposed.block_width(), transposed.block_height())
+        );
+        assert_eq!(Some(2), transposed.get_cell(0, 1).unwrap());
+        assert_eq!(Some(4), transposed.get_cell(1, 0).unwrap());
+
+        let sudoku = Sudoku::new_with_grid(transposed, DefaultConstraint).unwrap();
+
+        assert!(sudoku.is_valid());
+    }
+
+    #[test]
+    fn relabeling() {
+        let transform = GridTransform::relabeling(3, 2, vec![2, 1, 3, 4, 5, 6]).unwrap();
+        let grid = transform.apply_to_grid(&solution()).unwrap();
+
+        assert_eq!(Some(2), grid.get_cell(0, 0).unwrap());
+        assert_eq!(Some(1), grid.get_cell(1, 0).unwrap());
+        assert_eq!(
+            Err(SudokuError::InvalidNumber),
+            GridTransform::relabeling(3, 2, vec![1, 1, 3, 4, 5, 6])
+        );
+        assert_eq!(
+            Err(SudokuError::InvalidDimensions),
+            transform.apply_to_grid(&SudokuGrid::new(2, 2).unwrap())
+        );
+    }
+
+    #[test]
+    fn scramble_preserves_validity() {
+        let mut rng = ChaCha8Rng::seed_from_u64(42);
+        let mut puzzle = Sudoku::new_with_grid(solution(), DefaultConstraint).unwrap();
+        puzzle.grid_mut().clear_cell(0, 0).unwrap();
+        puzzle.grid_mut().clear_cell(4, 3).unwrap();
+
+        for _ in 0..20 {
+            let scrambled = scramble(&puzzle, &mut rng);
+
+            assert!(scrambled.is_valid());
+            assert_eq!(34, scrambled.grid().count_clues());
+        }
+    }
+
+    #[test]
+    fn transformed_composite_keeps_mapped_solution() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut candidates = GivenCandidatesConstraint::new();
+        candidates.restrict(0, 0, set!(1, 6; 1, 2));
+        candidates.restrict(3, 2, set!(1, 6; 5));
+        let constraint = CompositeConstraint::new(DefaultConstraint, candidates);
+        let mut puzzle = Sudoku::new_with_grid(solution(), constraint).unwrap();
+
+        for (column, row) in [(0, 0), (1, 1), (3, 2), (5, 5), (2, 4)] {
+            puzzle.grid_mut().clear_cell(column, row).unwrap();
+        }
+
+        for _ in 0..10 {
+            let transform = GridTransform::random(3, 2, &mut rng);
+            let transformed = transform_sudoku(&puzzle, &transform).unwrap();
+            let expected = transform.apply_to_grid(&solution()).unwrap();
+
+            assert_eq!(
+                Solution::Unique(expected),
+                BacktrackingSolver.solve(&transformed)
+            );
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_transform() {
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+
+        for _ in 0..10 {
+            let transform = GridTransform::random(3, 2, &mut rng);
+            let transformed = transform.apply_to_grid(&solution()).unwrap();
+
+            assert_eq!(
+                Ok(solution()),
+                transform.inverse().apply_to_grid(&transformed)
+            );
+        }
+    }
+
+    #[test]
+    fn transformed_sudoku_maps_back_to_original() {
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        let mut puzzle = Sudoku::new_with_grid(solution(), DefaultConstraint).unwrap();
+        puzzle.grid_mut().clear_cell(0, 0).unwrap();
+        puzzle.grid_mut().clear_cell(4, 3).unwrap();
+
+        for _ in 0..5 {
+            let transformed = TransformedSudoku::scramble(&puzzle, &mut rng);
+            let (column, row) = transformed.transform().map_cell(4, 3);
+
+            assert_eq!((4, 3), transformed.original_cell(column, row));
+            assert_eq!(
+                Solution::Unique(solution()),
+                transformed.solve_original(&BacktrackingSolver)
+            );
+        }
+    }
+
+    #[test]
+    fn scramble_kings_move() {
+        assert_scrambles_stay_valid("2x2;1,,,,,,,,,1,,,,,,", KingsMoveConstraint);
+    }
+
+    #[test]
+    fn scramble_diagonally_adjacent() {
+        assert_scrambles_stay_valid("2x2;1,,,,,,,,,1,,,,,,", DiagonallyAdjacentConstraint);
+    }
+
+    #[test]
+    fn scramble_adjacent_consecutive() {
+        assert_scrambles_stay_valid("2x2;1,3,,,,,,,2,,,,,,,", AdjacentConsecutiveConstraint);
+    }
+
+    #[test]
+    fn scramble_respects_allowed_transformations() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let puzzle = Sudoku::new_with_grid(solution(), DiagonalsConstraint).unwrap();
+        let diagonal = |grid: &SudokuGrid| -> Vec<usize> {
+            (0..6)
+                .map(|i| grid.get_cell(i, i).unwrap().unwrap())
+                .collect()
+        };
+        let original = diagonal(puzzle.grid());
+
+        for _ in 0..10 {
+            let scrambled = diagonal(scramble(&puzzle, &mut rng).grid());
+
+            // The diagonal cells stay in place, only their numbers change.
+            for i in 0..6 {
+                for j in 0..6 {
+                    assert_eq!(original[i] == original[j], scrambled[i] == scrambled[j]);
+                }
+            }
+        }
+    }
+}
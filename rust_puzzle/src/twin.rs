@@ -0,0 +1,292 @@
+use crate::constraint::Constraint;
+use crate::error::{SudokuError, SudokuResult};
+use crate::{Sudoku, SudokuGrid};
+
+// A relation between the numbers in corresponding cells of two grids.
+pub trait CrossConstraint {
+    fn check_pair(&self, first: usize, second: usize) -> bool;
+}
+
+impl<F> CrossConstraint for F
+where
+    F: Fn(usize, usize) -> bool,
+{
+    fn check_pair(&self, first: usize, second: usize) -> bool {
+        self(first, second)
+    }
+}
+
+// Corresponding cells must contain different numbers.
+#[derive(Clone, Copy, Debug)]
+pub struct DifferentCells;
+
+impl CrossConstraint for DifferentCells {
+    fn check_pair(&self, first: usize, second: usize) -> bool {
+        first != second
+    }
+}
+
+// Corresponding cells must sum to the given value.
+#[derive(Clone, Copy, Debug)]
+pub struct CellSum(pub usize);
+
+impl CrossConstraint for CellSum {
+    fn check_pair(&self, first: usize, second: usize) -> bool {
+        first + second == self.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TwinSide {
+    First,
+    Second,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TwinSolution {
+    Impossible,
+    Unique(SudokuGrid, SudokuGrid),
+    Ambiguous,
+}
+
+impl TwinSolution {
+    fn union(self, other: TwinSolution) -> TwinSolution {
+        match (self, other) {
+            (TwinSolution::Impossible, other) | (other, TwinSolution::Impossible) => other,
+            (TwinSolution::Unique(a1, b1), TwinSolution::Unique(a2, b2))
+                if a1 == a2 && b1 == b2 =>
+            {
+                TwinSolution::Unique(a1, b1)
+            }
+            _ => TwinSolution::Ambiguous,
+        }
+    }
+}
+
+// Two sudokus of equal dimensions whose corresponding cells are related by a
+// cross constraint, such as twin puzzles where they must differ.
+#[derive(Clone)]
+pub struct TwinSudoku<C1, C2, X>
+where
+    C1: Constraint + Clone,
+    C2: Constraint + Clone,
+    X: CrossConstraint,
+{
+    first: Sudoku<C1>,
+    second: Sudoku<C2>,
+    cross: X,
+}
+
+impl<C1, C2, X> TwinSudoku<C1, C2, X>
+where
+    C1: Constraint + Clone,
+    C2: Constraint + Clone,
+    X: CrossConstraint,
+{
+    pub fn new(first: Sudoku<C1>, second: Sudoku<C2>, cross: X) -> SudokuResult<Self> {
+        if first.grid().block_width() != second.grid().block_width()
+            || first.grid().block_height() != second.grid().block_height()
+        {
+            return Err(SudokuError::InvalidDimensions);
+        }
+
+        Ok(TwinSudoku {
+            first,
+            second,
+            cross,
+        })
+    }
+
+    pub fn first(&self) -> &Sudoku<C1> {
+        &self.first
+    }
+
+    pub fn second(&self) -> &Sudoku<C2> {
+        &self.second
+    }
+
+    pub fn cross(&self) -> &X {
+        &self.cross
+    }
+
+    pub fn into_parts(self) -> (Sudoku<C1>, Sudoku<C2>, X) {
+        (self.first, self.second, self.cross)
+    }
+
+    fn check_cross(&self, side: TwinSide, column: usize, row: usize, number: usize) -> bool {
+        let other = match side {
+            TwinSide::First => self.second.grid().get_cell(column, row).unwrap(),
+            TwinSide::Second => self.first.grid().get_cell(column, row).unwrap(),
+        };
+
+        match (side, other) {
+            (_, None) => true,
+            (TwinSide::First, Some(other)) => self.cross.check_pair(number, other),
+            (TwinSide::Second, Some(other)) => self.cross.check_pair(other, number),
+        }
+    }
+
+    // Checks the number against the constraint of its own grid and the cross
+    // constraint.
+    pub fn is_valid_number(
+        &self,
+        side: TwinSide,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> SudokuResult<bool> {
+        let valid = match side {
+            TwinSide::First => self.first.is_valid_number(column, row, number)?,
+            TwinSide::Second => self.second.is_valid_number(column, row, number)?,
+        };
+
+        Ok(valid && self.check_cross(side, column, row, number))
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let size = self.first.grid().size();
+
+        self.first.is_valid()
+            && self.second.is_valid()
+            && (0..size * size).all(|i| {
+                match (self.first.grid().cells()[i], self.second.grid().cells()[i]) {
+                    (Some(a), Some(b)) => self.cross.check_pair(a, b),
+                    _ => true,
+                }
+            })
+    }
+
+    fn solve_cell(&mut self, side: TwinSide, index: usize) -> TwinSolution {
+        let size = self.first.grid().size();
+
+        if index == size * size {
+            return TwinSolution::Unique(self.first.grid().clone(), self.second.grid().clone());
+        }
+
+        // Cells are visited in scan order, alternating between the grids, so
+        // every placement is checked against its counterpart right away.
+        let (column, row) = (index % size, index / size);
+        let (next_side, next_index) = match side {
+            TwinSide::First => (TwinSide::Second, index),
+            TwinSide::Second => (TwinSide::First, index + 1),
+        };
+        let grid = match side {
+            TwinSide::First => self.first.grid_mut(),
+            TwinSide::Second => self.second.grid_mut(),
+        };
+
+        if grid.get_cell(column, row).unwrap().is_some() {
+            return self.solve_cell(next_side, next_index);
+        }
+
+        let mut solution = TwinSolution::Impossible;
+
+        for number in 1..=size {
+            if !self.is_valid_number(side, column, row, number).unwrap() {
+                continue;
+            }
+
+            let grid = match side {
+                TwinSide::First => self.first.grid_mut(),
+                TwinSide::Second => self.second.grid_mut(),
+            };
+            grid.set_cell(column, row, number).unwrap();
+            let next_solution = self.solve_cell(next_side, next_index);
+            let grid = match side {
+                TwinSide::First => self.first.grid_mut(),
+                TwinSide::Second => self.second.grid_mut(),
+            };
+            grid.clear_cell(column, row).unwrap();
+            solution = solution.union(next_solution);
+
+            if solution == TwinSolution::Ambiguous {
+                break;
+            }
+        }
+
+        solution
+    }
+
+    pub fn solve(&self) -> TwinSolution
+    where
+        X: Clone,
+    {
+        if !self.is_valid() {
+            return TwinSolution::Impossible;
+        }
+
+        self.clone().solve_cell(TwinSide::First, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+
+    fn sudoku(code: &str) -> Sudoku<DefaultConstraint> {
+        Sudoku::parse(code, DefaultConstraint).unwrap()
+    }
+
+    #[test]
+    fn cross_constraint_restricts_numbers() {
+        let twin = TwinSudoku::new(
+            sudoku("2x2;1,,,,,,,,,,,,,,,"),
+            sudoku("2x2;,2,,,,,,,,,,,,,,"),
+            CellSum(5),
+        )
+        .unwrap();
+
+        assert!(!twin.is_valid_number(TwinSide::Second, 0, 0, 3).unwrap());
+        assert!(twin.is_valid_number(TwinSide::Second, 0, 0, 4).unwrap());
+        assert!(!twin.is_valid_number(TwinSide::First, 1, 0, 2).unwrap());
+        assert!(twin.is_valid_number(TwinSide::First, 1, 0, 3).unwrap());
+    }
+
+    #[test]
+    fn solves_complementary_twins() {
+        let first = "2x2;1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,1";
+        let second = "2x2;4,3,2,1,2,1,4,3,3,4,1,2,1,2,3,4";
+        let twin = TwinSudoku::new(
+            sudoku("2x2;1,2,,,3,4,,,2,1,,,4,3,,"),
+            sudoku("2x2;,,2,,,,4,,,,,,,,,"),
+            CellSum(5),
+        )
+        .unwrap();
+
+        match twin.solve() {
+            TwinSolution::Unique(a, b) => {
+                assert_eq!(SudokuGrid::parse(first).unwrap(), a);
+                assert_eq!(SudokuGrid::parse(second).unwrap(), b);
+            }
+            solution => panic!("expected a unique solution, got {:?}", solution),
+        }
+    }
+
+    #[test]
+    fn detects_impossible_and_ambiguous_twins() {
+        let impossible = TwinSudoku::new(
+            sudoku("2x2;1,,,,,,,,,,,,,,,"),
+            sudoku("2x2;1,,,,,,,,,,,,,,,"),
+            DifferentCells,
+        )
+        .unwrap();
+        let ambiguous = TwinSudoku::new(
+            sudoku("2x2;1,,,,,,,,,,,,,,,"),
+            sudoku("2x2;,,,,,,,,,,,,,,,"),
+            DifferentCells,
+        )
+        .unwrap();
+
+        assert_eq!(TwinSolution::Impossible, impossible.solve());
+        assert_eq!(TwinSolution::Ambiguous, ambiguous.solve());
+        assert!(TwinSudoku::new(
+            sudoku("2x2;,,,,,,,,,,,,,,,"),
+            Sudoku::new_empty(3, 2, DefaultConstraint).unwrap(),
+            DifferentCells
+        )
+        .is_err());
+    }
+}
@@ -1,10 +1,22 @@
+use crate::error::{SudokuError, SudokuResult};
+use crate::SudokuGrid;
+
+use rand::Rng;
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
 use std::collections::HashSet;
 use std::hash::Hash;
+use std::iter::FromIterator;
+use std::mem;
 use std::ops::{
-    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Sub, SubAssign,
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, IndexMut, Not, Sub,
+    SubAssign,
 };
 
-use std::slice::Iter;
+use std::slice::{self, Iter};
+use std::vec::IntoIter;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct USizeSet {
@@ -105,6 +117,44 @@ impl<'a> Iterator for USizeSetIter<'a> {
     }
 }
 
+pub struct USizeSetIntoIter {
+    offset: usize,
+    current: BitIterator,
+    content: IntoIter<u64>,
+}
+
+impl USizeSetIntoIter {
+    fn new(set: USizeSet) -> USizeSetIntoIter {
+        let mut iter = set.content.into_iter();
+        let first_bit_iterator = BitIterator::new(iter.next().unwrap_or(0));
+
+        USizeSetIntoIter {
+            offset: set.lower,
+            current: first_bit_iterator,
+            content: iter,
+        }
+    }
+}
+
+impl Iterator for USizeSetIntoIter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            if let Some(bit_index) = self.current.next() {
+                return Some(self.offset + bit_index);
+            }
+
+            if let Some(next_content) = self.content.next() {
+                self.current = BitIterator::new(next_content);
+                self.offset += U64_BIT_SIZE;
+            } else {
+                return None;
+            }
+        }
+    }
+}
+
 impl USizeSet {
     pub fn new(lower: usize, upper: usize) -> USizeSetResult<USizeSet> {
         if lower > upper {
@@ -233,6 +283,16 @@ impl USizeSet {
         }
     }
 
+    // Inserts all numbers up to the first one outside the bounds, for which
+    // it fails with OutOfBounds. The numbers before it stay inserted.
+    pub fn try_extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) -> USizeSetResult<()> {
+        for number in iter {
+            self.insert(number)?;
+        }
+
+        Ok(())
+    }
+
     pub fn clear(&mut self) {
         for i in 0..self.content.len() {
             self.content[i] = 0;
@@ -241,6 +301,43 @@ impl USizeSet {
         self.len = 0;
     }
 
+    pub fn pop_min(&mut self) -> Option<usize> {
+        let min = self.min()?;
+        self.remove(min).unwrap();
+        Some(min)
+    }
+
+    pub fn pop_max(&mut self) -> Option<usize> {
+        let max = self.max()?;
+        self.remove(max).unwrap();
+        Some(max)
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(usize) -> bool) {
+        let mut len = 0;
+
+        for (index, word) in self.content.iter_mut().enumerate() {
+            let offset = self.lower + index * U64_BIT_SIZE;
+            let mut kept = 0u64;
+
+            for bit_index in BitIterator::new(*word) {
+                if f(offset + bit_index) {
+                    kept |= 1 << bit_index;
+                }
+            }
+
+            *word = kept;
+            len += kept.count_ones() as usize;
+        }
+
+        self.len = len;
+    }
+
+    pub fn drain(&mut self) -> USizeSetIntoIter {
+        let empty = USizeSet::new(self.lower, self.upper).unwrap();
+        mem::replace(self, empty).into_iter()
+    }
+
     pub fn iter(&self) -> USizeSetIter<'_> {
         USizeSetIter::new(self)
     }
@@ -536,6 +633,253 @@ impl Not for USizeSet {
     }
 }
 
+impl IntoIterator for USizeSet {
+    type Item = usize;
+    type IntoIter = USizeSetIntoIter;
+
+    fn into_iter(self) -> USizeSetIntoIter {
+        USizeSetIntoIter::new(self)
+    }
+}
+
+impl<'a> IntoIterator for &'a USizeSet {
+    type Item = usize;
+    type IntoIter = USizeSetIter<'a>;
+
+    fn into_iter(self) -> USizeSetIter<'a> {
+        self.iter()
+    }
+}
+
+// The bounds of a collected set are the smallest and largest collected
+// numbers, an empty iterator yields an empty set with bounds 0 to 0.
+impl FromIterator<usize> for USizeSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> USizeSet {
+        let numbers: Vec<usize> = iter.into_iter().collect();
+        let lower = numbers.iter().copied().min().unwrap_or(0);
+        let upper = numbers.iter().copied().max().unwrap_or(0);
+        let mut set = USizeSet::new(lower, upper).unwrap();
+        set.extend(numbers);
+        set
+    }
+}
+
+// Panics if a number is outside the bounds of the set. Use try_extend to
+// handle that case instead.
+impl Extend<usize> for USizeSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        self.try_extend(iter).unwrap();
+    }
+}
+
+impl<'a> Extend<&'a usize> for USizeSet {
+    fn extend<I: IntoIterator<Item = &'a usize>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename = "USizeSet")]
+struct USizeSetData {
+    lower: usize,
+    upper: usize,
+    content: Vec<usize>,
+}
+
+impl Serialize for USizeSet {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let data = USizeSetData {
+            lower: self.lower,
+            upper: self.upper,
+            content: self.iter().collect(),
+        };
+        data.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for USizeSet {
+    fn deserialize<D>(deserializer: D) -> Result<USizeSet, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = USizeSetData::deserialize(deserializer)?;
+        let mut set = USizeSet::new(data.lower, data.upper)
+            .map_err(|_| de::Error::custom("invalid set bounds"))?;
+
+        for number in data.content {
+            set.insert(number)
+                .map_err(|_| de::Error::custom("set element out of bounds"))?;
+        }
+
+        Ok(set)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Grid<T> {
+    size: usize,
+    cells: Vec<T>,
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Grid<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Grid<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename = "Grid")]
+        struct GridData<T> {
+            size: usize,
+            cells: Vec<T>,
+        }
+
+        let data = GridData::deserialize(deserializer)?;
+        Grid::from_cells(data.size, data.cells).map_err(de::Error::custom)
+    }
+}
+
+pub struct GridIter<'a, T> {
+    size: usize,
+    index: usize,
+    cells: Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for GridIter<'a, T> {
+    type Item = (usize, usize, &'a T);
+
+    fn next(&mut self) -> Option<(usize, usize, &'a T)> {
+        let cell = self.cells.next()?;
+        let index = self.index;
+        self.index += 1;
+        Some((index % self.size, index / self.size, cell))
+    }
+}
+
+pub struct GridIterMut<'a, T> {
+    size: usize,
+    index: usize,
+    cells: slice::IterMut<'a, T>,
+}
+
+impl<'a, T> Iterator for GridIterMut<'a, T> {
+    type Item = (usize, usize, &'a mut T);
+
+    fn next(&mut self) -> Option<(usize, usize, &'a mut T)> {
+        let cell = self.cells.next()?;
+        let index = self.index;
+        self.index += 1;
+        Some((index % self.size, index / self.size, cell))
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn new(size: usize, value: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            size,
+            cells: vec![value; size * size],
+        }
+    }
+
+    pub fn for_sudoku_grid(grid: &SudokuGrid, value: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid::new(grid.size(), value)
+    }
+
+    pub fn from_fn(size: usize, mut f: impl FnMut(usize, usize) -> T) -> Grid<T> {
+        let cells = (0..(size * size))
+            .map(|index| f(index % size, index / size))
+            .collect();
+        Grid { size, cells }
+    }
+
+    pub fn from_cells(size: usize, cells: Vec<T>) -> SudokuResult<Grid<T>> {
+        if cells.len() != size * size {
+            Err(SudokuError::InvalidDimensions)
+        } else {
+            Ok(Grid { size, cells })
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, column: usize, row: usize) -> SudokuResult<&T> {
+        let index = crate::index(column, row, self.size)?;
+        Ok(&self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, column: usize, row: usize) -> SudokuResult<&mut T> {
+        let index = crate::index(column, row, self.size)?;
+        Ok(&mut self.cells[index])
+    }
+
+    pub fn set(&mut self, column: usize, row: usize, value: T) -> SudokuResult<T> {
+        let cell = self.get_mut(column, row)?;
+        Ok(mem::replace(cell, value))
+    }
+
+    pub fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        self.cells.fill(value);
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            size: self.size,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    pub fn iter(&self) -> GridIter<'_, T> {
+        GridIter {
+            size: self.size,
+            index: 0,
+            cells: self.cells.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> GridIterMut<'_, T> {
+        GridIterMut {
+            size: self.size,
+            index: 0,
+            cells: self.cells.iter_mut(),
+        }
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn into_cells(self) -> Vec<T> {
+        self.cells
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (column, row): (usize, usize)) -> &T {
+        self.get(column, row).unwrap()
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (column, row): (usize, usize)) -> &mut T {
+        self.get_mut(column, row).unwrap()
+    }
+}
+
 pub(crate) fn contains_duplicate<I>(mut iter: I) -> bool
 where
     I: Iterator,
@@ -553,10 +897,54 @@ pub(crate) fn abs_diff(a: usize, b: usize) -> usize {
     }
 }
 
+// Shuffles the slice in place with the Fisher-Yates algorithm, so every
+// permutation is equally likely. The result only depends on the state of the
+// RNG, so a seeded RNG gives reproducible shuffles.
+// Indices are drawn as u64, since sampling a usize range consumes the RNG
+// differently depending on the pointer width.
+pub fn shuffle_slice<T>(rng: &mut impl Rng, values: &mut [T]) {
+    let len = values.len();
+
+    for i in 0..len.saturating_sub(1) {
+        let j = rng.gen_range(i as u64..len as u64) as usize;
+        values.swap(i, j);
+    }
+}
+
+pub fn shuffle_iter<T>(rng: &mut impl Rng, values: impl IntoIterator<Item = T>) -> Vec<T> {
+    let mut vec: Vec<T> = values.into_iter().collect();
+    shuffle_slice(rng, &mut vec);
+    vec
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn shuffle_handles_short_inputs() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+        assert!(shuffle_iter(&mut rng, Vec::<usize>::new()).is_empty());
+        assert_eq!(vec![1], shuffle_iter(&mut rng, 1..=1));
+    }
+
+    #[test]
+    fn shuffle_is_deterministic_permutation() {
+        let mut a = ChaCha8Rng::seed_from_u64(7);
+        let mut b = ChaCha8Rng::seed_from_u64(7);
+        let shuffled = shuffle_iter(&mut a, 0..20);
+        let mut values: Vec<usize> = (0..20).collect();
+        shuffle_slice(&mut b, &mut values);
+
+        assert_eq!(shuffled, values);
+        values.sort();
+        assert_eq!((0..20).collect::<Vec<_>>(), values);
+    }
+
     #[test]
     fn new_set_is_empty() {
         let set = USizeSet::new(1, 9).unwrap();
@@ -834,6 +1222,65 @@ mod tests {
         assert_eq!(101, result.len());
     }
 
+    #[test]
+    fn grid_get_and_set() {
+        let mut grid = Grid::new(4, 0u32);
+        assert_eq!(Ok(0), grid.set(3, 1, 7));
+        assert_eq!(Ok(7), grid.set(3, 1, 8));
+        grid[(0, 2)] = 5;
+
+        assert_eq!(Ok(&8), grid.get(3, 1));
+        assert_eq!(5, grid[(0, 2)]);
+        assert_eq!(0, grid[(1, 3)]);
+        assert_eq!(8, grid.cells()[7]);
+    }
+
+    #[test]
+    fn grid_out_of_bounds() {
+        let mut grid = Grid::new(4, ());
+        assert_eq!(Err(SudokuError::OutOfBounds), grid.get(4, 0));
+        assert_eq!(Err(SudokuError::OutOfBounds), grid.get(0, 4));
+        assert_eq!(Err(SudokuError::OutOfBounds), grid.set(7, 7, ()));
+    }
+
+    #[test]
+    fn grid_from_fn_and_iter() {
+        let grid = Grid::from_fn(3, |column, row| column * 10 + row);
+        let cells: Vec<(usize, usize, usize)> = grid.iter().map(|(c, r, &v)| (c, r, v)).collect();
+
+        assert_eq!(9, cells.len());
+        assert_eq!((0, 0, 0), cells[0]);
+        assert_eq!((2, 0, 20), cells[2]);
+        assert_eq!((0, 1, 1), cells[3]);
+        assert_eq!((2, 2, 22), cells[8]);
+    }
+
+    #[test]
+    fn grid_fill_and_map() {
+        let mut grid = Grid::for_sudoku_grid(&SudokuGrid::new(2, 2).unwrap(), 1usize);
+        assert_eq!(4, grid.size());
+
+        for (column, row, cell) in grid.iter_mut() {
+            *cell = column + row;
+        }
+
+        let doubled = grid.map(|&v| v * 2);
+        assert_eq!(12, doubled[(3, 3)]);
+        assert_eq!(2, doubled[(1, 0)]);
+
+        grid.fill(9);
+        assert!(grid.cells().iter().all(|&v| v == 9));
+    }
+
+    #[test]
+    fn grid_from_cells_checks_length() {
+        assert!(Grid::from_cells(2, vec![1, 2, 3, 4]).is_ok());
+        assert_eq!(
+            Err(SudokuError::InvalidDimensions),
+            Grid::from_cells(2, vec![1, 2, 3])
+        );
+    }
+
     #[test]
     fn contains_duplicate_false() {
         let vec = vec![1, 5, 2, 4, 3];
@@ -870,6 +1317,105 @@ mod tests {
         assert_eq!(Some(100), set!(1, 200; 5, 95, 100).max());
     }
 
+    #[test]
+    fn pop_min_and_max() {
+        let mut set = set!(1, 100; 3, 64, 65, 99);
+
+        assert_eq!(Some(3), set.pop_min());
+        assert_eq!(Some(99), set.pop_max());
+        assert_eq!(Some(64), set.pop_min());
+        assert_eq!(Some(65), set.pop_max());
+        assert_eq!(None, set.pop_min());
+        assert_eq!(None, set.pop_max());
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn retain() {
+        let mut set = USizeSet::range(1, 100).unwrap();
+        set.retain(|n| n % 10 == 0);
+
+        assert_eq!(10, set.len());
+        assert!(set.contains(10));
+        assert!(set.contains(70));
+        assert!(set.contains(100));
+        assert!(!set.contains(1));
+        assert!(!set.contains(65));
+    }
+
+    #[test]
+    fn drain() {
+        let mut set = set!(1, 100; 2, 50, 100);
+        let drained: Vec<usize> = set.drain().collect();
+
+        assert_eq!(vec![2, 50, 100], drained);
+        assert!(set.is_empty());
+        assert_eq!(1, set.lower());
+        assert_eq!(100, set.upper());
+    }
+
+    #[test]
+    fn into_iter() {
+        let set = set!(1, 200; 1, 64, 65, 128, 200);
+        let numbers: Vec<usize> = set.into_iter().collect();
+
+        assert_eq!(vec![1, 64, 65, 128, 200], numbers);
+    }
+
+    #[test]
+    fn from_iter_infers_bounds() {
+        let set: USizeSet = vec![7, 3, 12, 3].into_iter().collect();
+
+        assert_eq!(3, set.lower());
+        assert_eq!(12, set.upper());
+        assert_eq!(3, set.len());
+        assert!(set.contains(3));
+        assert!(set.contains(7));
+        assert!(set.contains(12));
+
+        let empty: USizeSet = Vec::new().into_iter().collect();
+
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn extend() {
+        let mut set = set!(1, 9; 1);
+        set.extend(vec![4, 5]);
+        set.extend(&[9, 1]);
+
+        assert_eq!(set!(1, 9; 1, 4, 5, 9), set);
+        assert_eq!(4, set.len());
+
+        assert_eq!(
+            Err(USizeSetError::OutOfBounds),
+            set.try_extend(vec![2, 10, 3])
+        );
+        assert_eq!(set!(1, 9; 1, 2, 4, 5, 9), set);
+    }
+
+    #[test]
+    fn set_serde_round_trip() {
+        let set = set!(1, 70; 2, 65, 70);
+        let json = serde_json::to_string(&set).unwrap();
+        let deserialized: USizeSet = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(set, deserialized);
+        assert!(
+            serde_json::from_str::<USizeSet>(r#"{"lower":1,"upper":4,"content":[5]}"#).is_err()
+        );
+        assert!(serde_json::from_str::<USizeSet>(r#"{"lower":4,"upper":1,"content":[]}"#).is_err());
+    }
+
+    #[test]
+    fn set_postcard_round_trip() {
+        let set = set!(1, 70; 2, 65, 70);
+        let bytes = postcard::to_allocvec(&set).unwrap();
+        let deserialized: USizeSet = postcard::from_bytes(&bytes).unwrap();
+
+        assert_eq!(set, deserialized);
+    }
+
     #[test]
     fn disjoint_relations() {
         let primes = set!(1, 10; 2, 3, 5, 7);
@@ -0,0 +1,223 @@
+use crate::constraint::Violation;
+use crate::SudokuGrid;
+
+use std::fmt::{self, Display, Formatter};
+
+// A problem that makes a sudoku meaningless rather than merely wrong, such as
+// a stored number outside the grid's range or a constraint that refers to a
+// cell the grid does not have. These typically stem from deserialized or
+// hand-assembled data and would otherwise cause panics inside solvers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StructuralProblem {
+    InvalidDimensions {
+        block_width: usize,
+        block_height: usize,
+    },
+    WrongCellCount {
+        expected: usize,
+        found: usize,
+    },
+    NumberOutOfRange {
+        column: usize,
+        row: usize,
+        number: usize,
+    },
+    CellOutOfBounds {
+        column: usize,
+        row: usize,
+    },
+    DimensionMismatch {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for StructuralProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StructuralProblem::InvalidDimensions {
+                block_width,
+                block_height,
+            } => write!(f, "invalid dimensions {}x{}", block_width, block_height),
+            StructuralProblem::WrongCellCount { expected, found } => {
+                write!(f, "{} cells, expected {}", found, expected)
+            }
+            StructuralProblem::NumberOutOfRange {
+                column,
+                row,
+                number,
+            } => write!(
+                f,
+                "number {} out of range in column {}, row {}",
+                number, column, row
+            ),
+            StructuralProblem::CellOutOfBounds { column, row } => {
+                write!(f, "cell in column {}, row {} is out of bounds", column, row)
+            }
+            StructuralProblem::DimensionMismatch { expected, found } => write!(
+                f,
+                "constraint is made for size {}, but the grid has size {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+pub(crate) fn check_cell_in_bounds(
+    grid: &SudokuGrid,
+    column: usize,
+    row: usize,
+    problems: &mut Vec<StructuralProblem>,
+) {
+    if column >= grid.size() || row >= grid.size() {
+        problems.push(StructuralProblem::CellOutOfBounds { column, row });
+    }
+}
+
+pub(crate) fn grid_structural_problems(grid: &SudokuGrid) -> Vec<StructuralProblem> {
+    let block_width = grid.block_width();
+    let block_height = grid.block_height();
+    let size = grid.size();
+
+    if block_width == 0 || block_height == 0 || block_width * block_height != size {
+        return vec![StructuralProblem::InvalidDimensions {
+            block_width,
+            block_height,
+        }];
+    }
+
+    if grid.cells().len() != size * size {
+        return vec![StructuralProblem::WrongCellCount {
+            expected: size * size,
+            found: grid.cells().len(),
+        }];
+    }
+
+    grid.cells()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, cell)| match *cell {
+            Some(number) if number == 0 || number > size => {
+                Some(StructuralProblem::NumberOutOfRange {
+                    column: index % size,
+                    row: index / size,
+                    number,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// The result of Sudoku::validate. Rule violations are only determined if the
+// sudoku is structurally sound, since constraints may not be able to check a
+// malformed grid at all.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidationReport {
+    structural_problems: Vec<StructuralProblem>,
+    violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    pub(crate) fn new(
+        structural_problems: Vec<StructuralProblem>,
+        violations: Vec<Violation>,
+    ) -> ValidationReport {
+        ValidationReport {
+            structural_problems,
+            violations,
+        }
+    }
+
+    pub fn structural_problems(&self) -> &[StructuralProblem] {
+        &self.structural_problems
+    }
+
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    pub fn is_structurally_sound(&self) -> bool {
+        self.structural_problems.is_empty()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.is_structurally_sound() && self.violations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{
+        ConsecutivePairsConstraint, DefaultConstraint, Edge, GivenCandidatesConstraint,
+    };
+    use crate::set;
+    use crate::utilities::USizeSet;
+    use crate::Sudoku;
+
+    #[test]
+    fn valid_sudoku() {
+        let sudoku = Sudoku::parse("2x2;1,,,,,,,,,,,,,,,", DefaultConstraint).unwrap();
+        let report = sudoku.validate();
+
+        assert!(report.is_valid());
+        assert!(report.violations().is_empty());
+    }
+
+    #[test]
+    fn rule_violations_are_not_structural() {
+        let sudoku = Sudoku::parse("2x2;1,,1,,,,,,,,,,,,,", DefaultConstraint).unwrap();
+        let report = sudoku.validate();
+
+        assert!(report.is_structurally_sound());
+        assert!(!report.is_valid());
+        assert_eq!(1, report.violations().len());
+    }
+
+    #[test]
+    fn out_of_range_numbers_are_structural() {
+        let mut sudoku = Sudoku::new_empty(2, 2, DefaultConstraint).unwrap();
+        sudoku.grid_mut().cells_mut_unchecked()[5] = Some(7);
+        let report = sudoku.validate();
+
+        assert_eq!(
+            &[StructuralProblem::NumberOutOfRange {
+                column: 1,
+                row: 1,
+                number: 7
+            }],
+            report.structural_problems()
+        );
+        assert!(report.violations().is_empty());
+    }
+
+    #[test]
+    fn constraints_referring_to_missing_cells_are_structural() {
+        let mut consecutive = Sudoku::new_empty(2, 2, ConsecutivePairsConstraint::new()).unwrap();
+        let mut candidates = Sudoku::new_empty(2, 2, GivenCandidatesConstraint::new()).unwrap();
+
+        // Constraints can still be broken after construction.
+        consecutive.constraint_mut().mark(Edge::right(3, 0));
+        consecutive.constraint_mut().mark(Edge::below(0, 1));
+        candidates.constraint_mut().restrict(0, 4, set!(1, 4; 1));
+        candidates.constraint_mut().restrict(0, 0, set!(1, 9; 1));
+
+        assert_eq!(
+            &[StructuralProblem::CellOutOfBounds { column: 4, row: 0 }],
+            consecutive.validate().structural_problems()
+        );
+        assert_eq!(
+            &[
+                StructuralProblem::CellOutOfBounds { column: 0, row: 4 },
+                StructuralProblem::DimensionMismatch {
+                    expected: 4,
+                    found: 9
+                }
+            ],
+            candidates.validate().structural_problems()
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use crate::constraint::Constraint;
+use crate::error::{SudokuError, SudokuResult};
+use crate::{index, Sudoku, SudokuGrid};
+
+// Read-only access to the cells of a puzzle. Implemented by the owned types
+// as well as by borrowed views such as OverlayView, which allows looking at
+// a hypothetical state without mutating or cloning the underlying grid.
+pub trait PuzzleView {
+    fn block_width(&self) -> usize;
+
+    fn block_height(&self) -> usize;
+
+    fn get_cell(&self, column: usize, row: usize) -> SudokuResult<Option<usize>>;
+
+    fn size(&self) -> usize {
+        self.block_width() * self.block_height()
+    }
+
+    fn has_number(&self, column: usize, row: usize, number: usize) -> SudokuResult<bool> {
+        Ok(self.get_cell(column, row)? == Some(number))
+    }
+
+    fn count_clues(&self) -> usize {
+        let size = self.size();
+        let mut clues = 0usize;
+
+        for row in 0..size {
+            for column in 0..size {
+                if let Ok(Some(_)) = self.get_cell(column, row) {
+                    clues += 1;
+                }
+            }
+        }
+
+        clues
+    }
+
+    fn is_full(&self) -> bool {
+        self.count_clues() == self.size() * self.size()
+    }
+
+    // Materializes the view, for APIs which require an actual grid.
+    fn to_grid(&self) -> SudokuGrid {
+        let size = self.size();
+        let mut grid = SudokuGrid::new(self.block_width(), self.block_height()).unwrap();
+
+        for row in 0..size {
+            for column in 0..size {
+                if let Some(number) = self.get_cell(column, row).unwrap() {
+                    grid.set_cell(column, row, number).unwrap();
+                }
+            }
+        }
+
+        grid
+    }
+}
+
+impl PuzzleView for SudokuGrid {
+    fn block_width(&self) -> usize {
+        SudokuGrid::block_width(self)
+    }
+
+    fn block_height(&self) -> usize {
+        SudokuGrid::block_height(self)
+    }
+
+    fn get_cell(&self, column: usize, row: usize) -> SudokuResult<Option<usize>> {
+        SudokuGrid::get_cell(self, column, row)
+    }
+
+    fn size(&self) -> usize {
+        SudokuGrid::size(self)
+    }
+
+    fn count_clues(&self) -> usize {
+        SudokuGrid::count_clues(self)
+    }
+
+    fn to_grid(&self) -> SudokuGrid {
+        self.clone()
+    }
+}
+
+impl<C: Constraint + Clone> PuzzleView for Sudoku<C> {
+    fn block_width(&self) -> usize {
+        self.grid().block_width()
+    }
+
+    fn block_height(&self) -> usize {
+        self.grid().block_height()
+    }
+
+    fn get_cell(&self, column: usize, row: usize) -> SudokuResult<Option<usize>> {
+        self.grid().get_cell(column, row)
+    }
+
+    fn size(&self) -> usize {
+        self.grid().size()
+    }
+
+    fn count_clues(&self) -> usize {
+        self.grid().count_clues()
+    }
+
+    fn to_grid(&self) -> SudokuGrid {
+        self.grid().clone()
+    }
+}
+
+impl<V: PuzzleView + ?Sized> PuzzleView for &V {
+    fn block_width(&self) -> usize {
+        (**self).block_width()
+    }
+
+    fn block_height(&self) -> usize {
+        (**self).block_height()
+    }
+
+    fn get_cell(&self, column: usize, row: usize) -> SudokuResult<Option<usize>> {
+        (**self).get_cell(column, row)
+    }
+
+    fn size(&self) -> usize {
+        (**self).size()
+    }
+
+    fn count_clues(&self) -> usize {
+        (**self).count_clues()
+    }
+
+    fn to_grid(&self) -> SudokuGrid {
+        (**self).to_grid()
+    }
+}
+
+// A view of another view with the content of a single cell replaced.
+pub struct OverlayView<'a, V: PuzzleView + ?Sized> {
+    base: &'a V,
+    column: usize,
+    row: usize,
+    content: Option<usize>,
+}
+
+impl<'a, V: PuzzleView + ?Sized> OverlayView<'a, V> {
+    pub fn with_number(
+        base: &'a V,
+        column: usize,
+        row: usize,
+        number: usize,
+    ) -> SudokuResult<OverlayView<'a, V>> {
+        index(column, row, base.size())?;
+
+        if number == 0 || number > base.size() {
+            return Err(SudokuError::InvalidNumber);
+        }
+
+        Ok(OverlayView {
+            base,
+            column,
+            row,
+            content: Some(number),
+        })
+    }
+
+    pub fn with_cleared(
+        base: &'a V,
+        column: usize,
+        row: usize,
+    ) -> SudokuResult<OverlayView<'a, V>> {
+        index(column, row, base.size())?;
+
+        Ok(OverlayView {
+            base,
+            column,
+            row,
+            content: None,
+        })
+    }
+
+    pub fn base(&self) -> &'a V {
+        self.base
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn content(&self) -> Option<usize> {
+        self.content
+    }
+}
+
+impl<'a, V: PuzzleView + ?Sized> PuzzleView for OverlayView<'a, V> {
+    fn block_width(&self) -> usize {
+        self.base.block_width()
+    }
+
+    fn block_height(&self) -> usize {
+        self.base.block_height()
+    }
+
+    fn get_cell(&self, column: usize, row: usize) -> SudokuResult<Option<usize>> {
+        if column == self.column && row == self.row {
+            Ok(self.content)
+        } else {
+            self.base.get_cell(column, row)
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.base.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::DefaultConstraint;
+
+    fn grid() -> SudokuGrid {
+        SudokuGrid::parse("2x2;1, , ,2, ,3, , , , ,4, ,3, , ,1").unwrap()
+    }
+
+    #[test]
+    fn grid_view_agrees_with_grid() {
+        let grid = grid();
+        let view: &dyn PuzzleView = &grid;
+
+        assert_eq!(4, view.size());
+        assert_eq!(Some(1), view.get_cell(0, 0).unwrap());
+        assert_eq!(None, view.get_cell(1, 0).unwrap());
+        assert_eq!(6, view.count_clues());
+        assert_eq!(grid, view.to_grid());
+    }
+
+    #[test]
+    fn overlay_replaces_single_cell() {
+        let grid = grid();
+        let placed = OverlayView::with_number(&grid, 1, 0, 4).unwrap();
+        let cleared = OverlayView::with_cleared(&placed, 0, 0).unwrap();
+
+        assert_eq!(Some(4), placed.get_cell(1, 0).unwrap());
+        assert_eq!(7, placed.count_clues());
+        assert_eq!(None, cleared.get_cell(0, 0).unwrap());
+        assert_eq!(Some(4), cleared.get_cell(1, 0).unwrap());
+        assert_eq!(6, cleared.count_clues());
+        assert_eq!(None, grid.get_cell(1, 0).unwrap());
+
+        let mut expected = grid.clone();
+        expected.set_cell(1, 0, 4).unwrap();
+        expected.clear_cell(0, 0).unwrap();
+        assert_eq!(expected, cleared.to_grid());
+    }
+
+    #[test]
+    fn constraints_check_overlays() {
+        let grid = grid();
+        let placed = OverlayView::with_number(&grid, 1, 0, 4).unwrap();
+        let conflicting = OverlayView::with_number(&grid, 1, 0, 2).unwrap();
+
+        assert!(DefaultConstraint.check(&placed));
+        assert!(!DefaultConstraint.check_number(&placed, 0, 1, 4));
+        assert!(DefaultConstraint.check_number(&grid, 0, 1, 4));
+        assert!(!DefaultConstraint.check(&conflicting));
+    }
+
+    #[test]
+    fn overlay_rejects_invalid_input() {
+        let grid = grid();
+
+        assert!(OverlayView::with_number(&grid, 4, 0, 1).is_err());
+        assert!(OverlayView::with_number(&grid, 0, 0, 5).is_err());
+        assert!(OverlayView::with_number(&grid, 0, 0, 0).is_err());
+        assert!(OverlayView::with_cleared(&grid, 0, 4).is_err());
+    }
+}